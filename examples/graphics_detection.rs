@@ -28,6 +28,7 @@ fn main() {
         width: 100,
         height: 60,
         data: b"\x1bPq\"1;1;100;60#0~\x1b\\".to_vec(),
+        params: vec![],
     });
     println!("   Added Sixel graphic at (5, 10), 100x60 pixels");
 
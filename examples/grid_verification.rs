@@ -4,7 +4,7 @@
 //! to compare terminal emulator implementations by inspecting the final
 //! grid state after processing ANSI sequences.
 
-use ratatui_testlib::ScreenState;
+use ratatui_testlib::{Color, ScreenState};
 
 fn main() {
     println!("=== Grid State Verification Example ===\n");
@@ -58,7 +58,7 @@ fn main() {
     if let Some(cells) = screen.iter_row(0) {
         let colored_chars: Vec<_> = cells
             .enumerate()
-            .filter(|(_, cell)| cell.fg.is_some())
+            .filter(|(_, cell)| !cell.fg.is_default())
             .map(|(col, cell)| (col, cell.c, cell.fg))
             .collect();
         for (col, ch, color) in colored_chars {
@@ -76,11 +76,11 @@ fn main() {
 
     // Verify specific cells in snapshot
     assert_eq!(snapshot.cells[0][0].c, 'R');
-    assert_eq!(snapshot.cells[0][0].fg, Some(1)); // Red
+    assert_eq!(snapshot.cells[0][0].fg, Color::Indexed(1)); // Red
     assert_eq!(snapshot.cells[0][4].c, 'G');
-    assert_eq!(snapshot.cells[0][4].fg, Some(2)); // Green
+    assert_eq!(snapshot.cells[0][4].fg, Color::Indexed(2)); // Green
     assert_eq!(snapshot.cells[0][10].c, 'B');
-    assert_eq!(snapshot.cells[0][10].fg, Some(4)); // Blue
+    assert_eq!(snapshot.cells[0][10].fg, Color::Indexed(4)); // Blue
     println!("   Verification: Red='R', Green='G', Blue='B' all found with correct colors!");
     println!();
 
@@ -10,7 +10,7 @@ use similar::{ChangeTag, TextDiff};
 
 use crate::{
     error::{Result, TermTestError},
-    screen::ScreenState,
+    screen::{Color, ScreenState},
 };
 
 /// Default directory for golden files.
@@ -22,6 +22,9 @@ const GOLDEN_HEADER_START: &str = "--- GOLDEN FILE ---";
 /// Content marker for golden file format.
 const GOLDEN_CONTENT_START: &str = "--- CONTENT ---";
 
+/// Color section marker for golden file format.
+const GOLDEN_COLORS_START: &str = "--- COLORS ---";
+
 /// Get the golden file directory from environment or use default.
 pub fn get_golden_dir() -> PathBuf {
     std::env::var("GOLDEN_DIR")
@@ -115,6 +118,10 @@ pub struct GoldenFile {
     pub metadata: GoldenMetadata,
     /// The actual terminal content (with ANSI escape codes preserved).
     pub content: String,
+    /// Runs of colored cells, one per line as `row:start..end fg=<color> bg=<color>`
+    /// using the [`Color`] text format. `None` for golden files saved before
+    /// colors were recorded, which are compared on text only.
+    pub colors: Option<String>,
 }
 
 impl GoldenFile {
@@ -123,13 +130,19 @@ impl GoldenFile {
         let (width, height) = state.size();
         let metadata = GoldenMetadata::new(test_name, width, height);
         let content = state.contents();
+        let colors = Some(color_runs(state));
 
-        Self { metadata, content }
+        Self { metadata, content, colors }
     }
 
     /// Serialize the golden file to a string.
     pub fn to_string(&self) -> String {
-        format!("{}{}\n{}", self.metadata.to_header(), GOLDEN_CONTENT_START, self.content)
+        let mut output =
+            format!("{}{}\n{}", self.metadata.to_header(), GOLDEN_CONTENT_START, self.content);
+        if let Some(colors) = &self.colors {
+            output.push_str(&format!("\n{}\n{}", GOLDEN_COLORS_START, colors));
+        }
+        output
     }
 
     /// Parse a golden file from a string.
@@ -145,9 +158,19 @@ impl GoldenFile {
             TermTestError::Parse("Failed to parse golden file header".to_string())
         })?;
 
+        let marker = format!("\n{}", GOLDEN_COLORS_START);
+        let (content_body, colors) = match content_body.find(&marker) {
+            Some(pos) => {
+                let colors = &content_body[pos + marker.len()..];
+                (&content_body[..pos], Some(colors.strip_prefix('\n').unwrap_or(colors)))
+            }
+            None => (content_body, None),
+        };
+
         let content = content_body.trim_start_matches('\n').to_string();
+        let colors = colors.map(str::to_string);
 
-        Ok(Self { metadata, content })
+        Ok(Self { metadata, content, colors })
     }
 
     /// Save the golden file to disk.
@@ -178,19 +201,62 @@ impl GoldenFile {
     pub fn compare(&self, state: &ScreenState) -> Result<()> {
         let current_content = state.contents();
 
-        if self.content == current_content {
-            return Ok(());
+        if self.content != current_content {
+            let diff = generate_diff(&self.content, &current_content);
+
+            return Err(TermTestError::Parse(format!(
+                "Golden file mismatch: {}\n{}",
+                self.metadata.test_name, diff
+            )));
         }
 
-        let diff = generate_diff(&self.content, &current_content);
+        if let Some(colors) = &self.colors {
+            let current_colors = color_runs(state);
+            if *colors != current_colors {
+                let diff = generate_diff(colors, &current_colors);
+
+                return Err(TermTestError::Parse(format!(
+                    "Golden file color mismatch: {}\n{}",
+                    self.metadata.test_name, diff
+                )));
+            }
+        }
 
-        Err(TermTestError::Parse(format!(
-            "Golden file mismatch: {}\n{}",
-            self.metadata.test_name, diff
-        )))
+        Ok(())
     }
 }
 
+/// Describes the colored cells of a screen, one run of identically colored
+/// cells per line. Cells with default foreground and background are omitted.
+fn color_runs(state: &ScreenState) -> String {
+    let mut lines = Vec::new();
+
+    for (row, cells) in state.iter_rows().enumerate() {
+        let mut start = 0;
+        while start < cells.len() {
+            let colors = (cells[start].fg, cells[start].bg);
+            let len = cells[start..]
+                .iter()
+                .take_while(|cell| (cell.fg, cell.bg) == colors)
+                .count();
+
+            if colors != (Color::Default, Color::Default) {
+                lines.push(format!(
+                    "{}:{}..{} fg={} bg={}",
+                    row,
+                    start,
+                    start + len,
+                    colors.0,
+                    colors.1
+                ));
+            }
+            start += len;
+        }
+    }
+
+    lines.join("\n")
+}
+
 /// Generate a unified diff between expected and actual content.
 pub fn generate_diff(expected: &str, actual: &str) -> String {
     let diff = TextDiff::from_lines(expected, actual);
@@ -397,21 +397,10 @@ impl GraphicsCapture {
         let mut regions = Vec::new();
 
         // Convert Sixel regions to GraphicsRegions
+        // Pixel sizes are converted to cells with the screen's cell size
         for sixel_region in screen.sixel_regions() {
-            const PIXELS_PER_COL: u32 = 8;
-            const PIXELS_PER_ROW: u32 = 6;
-
-            let width_cells = if sixel_region.width > 0 {
-                ((sixel_region.width + PIXELS_PER_COL - 1) / PIXELS_PER_COL) as u16
-            } else {
-                0
-            };
-
-            let height_cells = if sixel_region.height > 0 {
-                ((sixel_region.height + PIXELS_PER_ROW - 1) / PIXELS_PER_ROW) as u16
-            } else {
-                0
-            };
+            let (width_cells, height_cells) =
+                screen.pixels_to_cells(sixel_region.width, sixel_region.height);
 
             regions.push(GraphicsRegion::new(
                 GraphicsProtocol::Sixel,
@@ -423,20 +412,8 @@ impl GraphicsCapture {
 
         // Convert Kitty regions to GraphicsRegions
         for kitty_region in screen.kitty_regions() {
-            const PIXELS_PER_COL: u32 = 8;
-            const PIXELS_PER_ROW: u32 = 6;
-
-            let width_cells = if kitty_region.width > 0 {
-                ((kitty_region.width + PIXELS_PER_COL - 1) / PIXELS_PER_COL) as u16
-            } else {
-                0
-            };
-
-            let height_cells = if kitty_region.height > 0 {
-                ((kitty_region.height + PIXELS_PER_ROW - 1) / PIXELS_PER_ROW) as u16
-            } else {
-                0
-            };
+            let (width_cells, height_cells) =
+                screen.pixels_to_cells(kitty_region.width, kitty_region.height);
 
             regions.push(GraphicsRegion::new(
                 GraphicsProtocol::Kitty,
@@ -935,6 +912,7 @@ mod tests {
             width: 100,
             height: 60,
             data: vec![0x1b, b'P', b'q'],
+            params: vec![],
         });
 
         let capture = GraphicsCapture::from_screen_state(&screen);
@@ -944,6 +922,23 @@ mod tests {
         let region = &capture.regions()[0];
         assert_eq!(region.protocol, GraphicsProtocol::Sixel);
         assert_eq!(region.position, (5, 10));
+        assert_eq!(region.bounds, (5, 10, 13, 10));
+    }
+
+    #[test]
+    fn test_from_screen_state_uses_cell_size() {
+        let mut screen = ScreenState::new(80, 24).with_cell_size(10, 20);
+        screen.sixel_regions_mut().push(SixelRegion {
+            start_row: 5,
+            start_col: 10,
+            width: 100,
+            height: 60,
+            data: vec![0x1b, b'P', b'q'],
+            params: vec![],
+        });
+
+        let capture = GraphicsCapture::from_screen_state(&screen);
+        assert_eq!(capture.regions()[0].bounds, (5, 10, 10, 3));
     }
 
     #[test]
@@ -1001,6 +996,7 @@ mod tests {
             width: 80,
             height: 60,
             data: vec![],
+            params: vec![],
         });
 
         screen.kitty_regions_mut().push(KittyRegion {
@@ -1037,6 +1033,7 @@ mod tests {
             width: 80,  // 10 cells
             height: 60, // 10 cells
             data: vec![],
+            params: vec![],
         });
 
         screen.kitty_regions_mut().push(KittyRegion {
@@ -40,13 +40,16 @@
 //! ```
 
 use std::{
+    fmt,
     fs::File,
     io::Write,
     path::Path,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
 use portable_pty::{CommandBuilder, ExitStatus};
+use unicode_width::UnicodeWidthStr;
 
 use crate::{
     error::{Result, TermTestError},
@@ -55,7 +58,10 @@ use crate::{
         MouseEvent, ScrollDirection,
     },
     pty::TestTerminal,
-    screen::ScreenState,
+    screen::{
+        ClipboardWrite, CursorStyle, Palette, ResizeMode, ScreenState, TerminalModes,
+        TerminalQuery, TerminalReset,
+    },
     terminal_profiles::{Feature, TerminalCapabilities, TerminalProfile},
     timing::{fps_to_frame_budget, LatencyProfile, TimingHooks, TimingRecorder},
 };
@@ -190,6 +196,23 @@ pub enum RecordedEvent {
         /// Cursor position (row, col).
         cursor: (u16, u16),
     },
+    /// The application reset the terminal (RIS or DECSTR).
+    Reset(TerminalReset),
+}
+
+/// Function returning the reply to a terminal query, if any.
+type QueryReplyFn = dyn Fn(&TerminalQuery) -> Option<Vec<u8>> + Send + Sync;
+
+/// Produces the replies to terminal queries in place of the terminal profile.
+///
+/// See [`TuiTestHarness::with_query_handler`].
+#[derive(Clone)]
+struct QueryHandler(Arc<QueryReplyFn>);
+
+impl fmt::Debug for QueryHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("QueryHandler(..)")
+    }
 }
 
 /// A timestamped recorded event.
@@ -247,9 +270,14 @@ pub struct TuiTestHarness {
     verbose: bool,
     // Terminal profile configuration
     terminal_profile: TerminalProfile,
+    // Automatic replies to terminal queries
+    query_replies: bool,
+    query_handler: Option<QueryHandler>,
     // Timing and latency profiling
     timing_recorder: TimingRecorder,
     latency_profile: LatencyProfile,
+    /// Prompt selected with `jump_to_prompt`, until input is sent
+    prompt_view: Option<usize>,
 }
 
 impl TuiTestHarness {
@@ -264,8 +292,9 @@ impl TuiTestHarness {
     ///
     /// Returns an error if terminal creation fails.
     pub fn new(width: u16, height: u16) -> Result<Self> {
-        let terminal = TestTerminal::new(width, height)?;
         let state = ScreenState::new(width, height);
+        let (cell_width, cell_height) = state.cell_size();
+        let terminal = TestTerminal::new(width, height)?.with_cell_size(cell_width, cell_height)?;
 
         Ok(Self {
             terminal,
@@ -279,8 +308,11 @@ impl TuiTestHarness {
             recording_start: None,
             verbose: false,
             terminal_profile: TerminalProfile::default(),
+            query_replies: true,
+            query_handler: None,
             timing_recorder: TimingRecorder::new(),
             latency_profile: LatencyProfile::new(),
+            prompt_view: None,
         })
     }
 
@@ -352,6 +384,74 @@ impl TuiTestHarness {
         self
     }
 
+    /// Enables or disables automatic replies to terminal queries.
+    ///
+    /// By default the harness answers the queries applications send at
+    /// startup (DA1/DA2, DSR, cursor position reports, XTVERSION and DECRQM)
+    /// the way the configured [`TerminalProfile`] would, so applications that
+    /// wait for a reply do not hang. Disable this to test how an application
+    /// copes with a terminal that never answers.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::TuiTestHarness;
+    ///
+    /// # fn test() -> ratatui_testlib::Result<()> {
+    /// let harness = TuiTestHarness::new(80, 24)?.with_query_replies(false);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_query_replies(mut self, enabled: bool) -> Self {
+        self.query_replies = enabled;
+        self
+    }
+
+    /// Replaces the profile's replies to terminal queries.
+    ///
+    /// `handler` is called for each query the application sends and returns
+    /// the bytes to write back, or `None` to leave the query unanswered. Use
+    /// [`TerminalQuery::reply`] to fall back to a profile's reply.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::{TerminalProfile, TerminalQuery, TuiTestHarness};
+    ///
+    /// # fn test() -> ratatui_testlib::Result<()> {
+    /// // Pretend to be a terminal that answers XTVERSION with a custom name
+    /// let harness = TuiTestHarness::new(80, 24)?.with_query_handler(|query| match query {
+    ///     TerminalQuery::TerminalVersion => Some(b"\x1bP>|MyTerm 1.0\x1b\\".to_vec()),
+    ///     _ => query.reply(TerminalProfile::Xterm256),
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_query_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&TerminalQuery) -> Option<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.query_handler = Some(QueryHandler(Arc::new(handler)));
+        self
+    }
+
+    /// Sets how [`resize`](Self::resize) fits the screen contents to the new
+    /// width: reflowing soft-wrapped lines (the default) or truncating them.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ratatui_testlib::{ResizeMode, TuiTestHarness};
+    ///
+    /// let mut harness = TuiTestHarness::new(80, 24)?.with_resize_mode(ResizeMode::Truncate);
+    /// harness.resize(40, 24)?;
+    /// # Ok::<(), ratatui_testlib::TermTestError>(())
+    /// ```
+    pub fn with_resize_mode(mut self, mode: ResizeMode) -> Self {
+        self.state = self.state.with_resize_mode(mode);
+        self
+    }
+
     /// Configures the harness to simulate a specific TERMINFO environment.
     ///
     /// This is a convenience method that looks up a terminal profile by name
@@ -488,6 +588,7 @@ impl TuiTestHarness {
         // Record input timestamp for latency profiling
         self.timing_recorder.record_event("input_sent");
         self.latency_profile.mark_input();
+        self.prompt_view = None;
 
         let bytes = text.as_bytes();
         self.record_input(bytes);
@@ -798,6 +899,7 @@ impl TuiTestHarness {
         // Record input timestamp for latency profiling
         self.timing_recorder.record_event("input_sent");
         self.latency_profile.mark_input();
+        self.prompt_view = None;
 
         let bytes = encode_mouse_event(&event);
         self.record_input(&bytes);
@@ -921,6 +1023,7 @@ impl TuiTestHarness {
         // Record input timestamp for latency profiling
         self.timing_recorder.record_event("input_sent");
         self.latency_profile.mark_input();
+        self.prompt_view = None;
 
         let bytes = encode_key_event(&event);
         self.record_input(&bytes);
@@ -970,6 +1073,7 @@ impl TuiTestHarness {
                     Ok(n) => {
                         self.record_output(&buf[..n]);
                         self.state.feed(&buf[..n]);
+                        self.record_resets();
                         self.record_state_change();
                     }
                     Err(_) => break, // Any error, just stop reading
@@ -987,7 +1091,9 @@ impl TuiTestHarness {
                 Ok(n) => {
                     self.record_output(&buf[..n]);
                     self.state.feed(&buf[..n]);
+                    self.record_resets();
                     self.record_state_change();
+                    self.answer_queries()?;
                 }
                 Err(e) => {
                     // Use proper ErrorKind matching instead of string matching
@@ -1350,6 +1456,74 @@ impl TuiTestHarness {
         self.cursor_position()
     }
 
+    /// Returns whether the cursor is visible (DECTCEM).
+    ///
+    /// See [`ScreenState::cursor_visible`].
+    pub fn cursor_visible(&self) -> bool {
+        self.state.cursor_visible()
+    }
+
+    /// Returns the cursor style selected with DECSCUSR.
+    ///
+    /// See [`ScreenState::cursor_style`].
+    pub fn cursor_style(&self) -> CursorStyle {
+        self.state.cursor_style()
+    }
+
+    /// Returns the DEC private and ANSI modes the application has set.
+    ///
+    /// See [`ScreenState::modes`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use ratatui_testlib::TuiTestHarness;
+    /// # use portable_pty::CommandBuilder;
+    /// let mut harness = TuiTestHarness::new(80, 24)?;
+    /// harness.spawn(CommandBuilder::new("my-tui-app"))?;
+    /// harness.wait_for_text("Ready")?;
+    /// assert!(harness.modes().mouse_enabled());
+    /// assert!(harness.modes().bracketed_paste);
+    /// # Ok::<(), ratatui_testlib::TermTestError>(())
+    /// ```
+    pub fn modes(&self) -> &TerminalModes {
+        self.state.modes()
+    }
+
+    /// Returns what the application last copied to the clipboard with
+    /// OSC 52, or the text set with [`set_clipboard`](Self::set_clipboard).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use ratatui_testlib::TuiTestHarness;
+    /// # use portable_pty::CommandBuilder;
+    /// let mut harness = TuiTestHarness::new(80, 24)?;
+    /// harness.spawn(CommandBuilder::new("my-editor"))?;
+    /// harness.send_text("yy")?;
+    /// harness.wait_for(|state| state.clipboard().is_some())?;
+    /// assert_eq!(harness.clipboard_contents(), Some("first line\n"));
+    /// # Ok::<(), ratatui_testlib::TermTestError>(())
+    /// ```
+    pub fn clipboard_contents(&self) -> Option<&str> {
+        self.state.clipboard()
+    }
+
+    /// Sets the simulated clipboard, which the harness returns when the
+    /// application reads it with OSC 52.
+    ///
+    /// Reads are only answered when the terminal profile allows them (see
+    /// [`TerminalProfile::allows_clipboard_read`]).
+    pub fn set_clipboard(&mut self, contents: impl Into<String>) {
+        self.state.set_clipboard(contents);
+    }
+
+    /// Returns the OSC 52 writes the application made, with their selection
+    /// target, oldest first. Only the most recent 256 are kept.
+    pub fn clipboard_writes(&self) -> &[ClipboardWrite] {
+        self.state.clipboard_writes()
+    }
+
     /// Returns the current screen state.
     ///
     /// Provides immutable access to the terminal screen state for inspecting
@@ -1402,8 +1576,11 @@ impl TuiTestHarness {
 
     /// Resizes the terminal.
     ///
-    /// Changes the terminal dimensions and resets the screen state.
-    /// This can be useful for testing responsive TUI layouts.
+    /// Changes the terminal dimensions. The screen keeps its contents, fitted
+    /// to the new size as configured with
+    /// [`with_resize_mode`](Self::with_resize_mode) (see
+    /// [`ScreenState::resize`]), until the application redraws. This can be
+    /// useful for testing responsive TUI layouts.
     ///
     /// # Arguments
     ///
@@ -1428,7 +1605,7 @@ impl TuiTestHarness {
     /// ```
     pub fn resize(&mut self, width: u16, height: u16) -> Result<()> {
         self.terminal.resize(width, height)?;
-        self.state = ScreenState::new(width, height);
+        self.state.resize(width, height);
         Ok(())
     }
 
@@ -1527,8 +1704,9 @@ impl TuiTestHarness {
         let (width, height) = self.state.size();
 
         // Estimate screen buffer size
-        // Each cell contains: char (4 bytes) + 2 Option<u8> (2 bytes) + 3 bool (3 bytes) = ~9 bytes
-        // But with padding/alignment it's likely more
+        // Each cell holds its char, grapheme cluster storage, colors and
+        // attribute flags; size_of includes padding but not heap-allocated
+        // combining characters
         let screen_buffer_size =
             (width as usize) * (height as usize) * size_of::<crate::screen::Cell>();
 
@@ -1644,17 +1822,11 @@ impl TuiTestHarness {
             )));
         }
 
-        // Extract text at the position
-        let mut actual = String::new();
-        for (i, _) in text.chars().enumerate() {
-            let current_col = col.saturating_add(i as u16);
-            if current_col >= width {
-                break;
-            }
-            if let Some(cell) = self.state.get_cell(row, current_col) {
-                actual.push(cell.c);
-            }
-        }
+        // Extract the columns the text would cover (wide characters take two)
+        let text_width = UnicodeWidthStr::width(text) as u16;
+        let actual = self
+            .state
+            .text_in_columns(row, col, col.saturating_add(text_width));
 
         // Compare
         if actual != text {
@@ -1700,27 +1872,13 @@ impl TuiTestHarness {
         let (width, height) = self.state.size();
 
         // Search within the area
+        let text_width = UnicodeWidthStr::width(text) as u16;
+        let right = area.right().min(width);
         for row in area.y..area.bottom().min(height) {
-            for col in area.x..area.right().min(width) {
+            for col in area.x..right {
                 // Try to match text starting at this position
-                let mut matches = true;
-                for (i, expected_char) in text.chars().enumerate() {
-                    let current_col = col.saturating_add(i as u16);
-                    if current_col >= area.right() || current_col >= width {
-                        matches = false;
-                        break;
-                    }
-                    if let Some(cell) = self.state.get_cell(row, current_col) {
-                        if cell.c != expected_char {
-                            matches = false;
-                            break;
-                        }
-                    } else {
-                        matches = false;
-                        break;
-                    }
-                }
-                if matches {
+                let end = col.saturating_add(text_width);
+                if end <= right && self.state.text_in_columns(row, col, end) == text {
                     return Ok(()); // Found it!
                 }
             }
@@ -2451,6 +2609,14 @@ impl TuiTestHarness {
                         comma
                     )
                 }
+                RecordedEvent::Reset(reset) => {
+                    format!(
+                        r#"  {{"timestamp_ms": {}, "event": {{"type": "Reset", "kind": "{:?}"}}}}{}"#,
+                        event.timestamp.as_millis(),
+                        reset,
+                        comma
+                    )
+                }
                 RecordedEvent::StateChange { contents, cursor } => {
                     let escaped_contents = contents
                         .replace('\\', "\\\\")
@@ -2619,6 +2785,16 @@ impl TuiTestHarness {
         self.verbose = verbose;
     }
 
+    /// Returns the prompt selected with `jump_to_prompt`, if any.
+    pub(crate) fn prompt_view(&self) -> Option<usize> {
+        self.prompt_view
+    }
+
+    /// Selects the current prompt, or the prompt at the cursor with `None`.
+    pub(crate) fn set_prompt_view(&mut self, index: Option<usize>) {
+        self.prompt_view = index;
+    }
+
     /// Records an input event if recording is active.
     fn record_input(&mut self, data: &[u8]) {
         if self.recording {
@@ -2632,6 +2808,24 @@ impl TuiTestHarness {
         }
     }
 
+    /// Writes replies to the queries found in the output fed so far.
+    fn answer_queries(&mut self) -> Result<()> {
+        for query in self.state.take_queries() {
+            if !self.query_replies {
+                continue;
+            }
+            let reply = match &self.query_handler {
+                Some(handler) => (handler.0)(&query),
+                None => query.reply(self.terminal_profile),
+            };
+            if let Some(reply) = reply {
+                self.terminal.write_all(&reply)?;
+                self.record_input(&reply);
+            }
+        }
+        Ok(())
+    }
+
     /// Records an output event if recording is active.
     fn record_output(&mut self, data: &[u8]) {
         if self.recording {
@@ -2654,6 +2848,23 @@ impl TuiTestHarness {
         }
     }
 
+    /// Records the terminal resets in the output just fed if recording is
+    /// active. The resets are taken either way so they do not accumulate.
+    fn record_resets(&mut self) {
+        let resets = self.state.take_resets();
+        if self.recording {
+            if let Some(start) = self.recording_start {
+                let timestamp = start.elapsed();
+                for reset in resets {
+                    self.recorded_events.push(TimestampedEvent {
+                        timestamp,
+                        event: RecordedEvent::Reset(reset),
+                    });
+                }
+            }
+        }
+    }
+
     /// Records a state change event if recording is active.
     fn record_state_change(&mut self) {
         if self.recording {
@@ -2977,6 +3188,11 @@ pub struct TuiTestHarnessBuilder {
     poll_interval: Duration,
     buffer_size: usize,
     terminal_profile: TerminalProfile,
+    query_replies: bool,
+    query_handler: Option<QueryHandler>,
+    palette: Palette,
+    resize_mode: ResizeMode,
+    cell_size: Option<(u16, u16)>,
 }
 
 impl Default for TuiTestHarnessBuilder {
@@ -2988,6 +3204,11 @@ impl Default for TuiTestHarnessBuilder {
             poll_interval: DEFAULT_POLL_INTERVAL,
             buffer_size: DEFAULT_BUFFER_SIZE,
             terminal_profile: TerminalProfile::default(),
+            query_replies: true,
+            query_handler: None,
+            palette: Palette::default(),
+            resize_mode: ResizeMode::default(),
+            cell_size: None,
         }
     }
 }
@@ -3045,14 +3266,89 @@ impl TuiTestHarnessBuilder {
         self
     }
 
+    /// Enables or disables automatic replies to terminal queries.
+    ///
+    /// See [`TuiTestHarness::with_query_replies`].
+    pub fn with_query_replies(mut self, enabled: bool) -> Self {
+        self.query_replies = enabled;
+        self
+    }
+
+    /// Replaces the profile's replies to terminal queries.
+    ///
+    /// See [`TuiTestHarness::with_query_handler`].
+    pub fn with_query_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&TerminalQuery) -> Option<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.query_handler = Some(QueryHandler(Arc::new(handler)));
+        self
+    }
+
+    /// Sets how the harness fits the screen contents to a new size.
+    ///
+    /// See [`TuiTestHarness::with_resize_mode`].
+    pub fn with_resize_mode(mut self, mode: ResizeMode) -> Self {
+        self.resize_mode = mode;
+        self
+    }
+
+    /// Sets the colors the terminal reports when the application queries its
+    /// foreground, background or palette (OSC 10, 11 and 4).
+    ///
+    /// Applications that pick a light or dark theme from the background color
+    /// can be tested in both modes by changing the palette. The palette also
+    /// backs [`ScreenState::resolve_rgb`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ratatui_testlib::{Palette, TuiTestHarness};
+    ///
+    /// let harness = TuiTestHarness::builder().with_palette(Palette::light()).build()?;
+    /// # Ok::<(), ratatui_testlib::TermTestError>(())
+    /// ```
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Sets the size of a character cell in pixels (8x6 by default).
+    ///
+    /// The PTY window size reports the screen size in pixels from it
+    /// (`ws_xpixel`/`ws_ypixel`), the harness answers XTWINOPS size reports
+    /// (`CSI 14 t`, `CSI 16 t`) with it, and graphics regions are converted
+    /// from pixels to cells with it. See [`ScreenState::with_cell_size`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ratatui_testlib::TuiTestHarness;
+    ///
+    /// let harness = TuiTestHarness::builder().with_cell_size(10, 20).build()?;
+    /// assert_eq!(harness.state().cell_size(), (10, 20));
+    /// # Ok::<(), ratatui_testlib::TermTestError>(())
+    /// ```
+    pub fn with_cell_size(mut self, width_px: u16, height_px: u16) -> Self {
+        self.cell_size = Some((width_px, height_px));
+        self
+    }
+
     /// Builds the test harness with the configured settings.
     ///
     /// # Errors
     ///
     /// Returns an error if terminal creation fails.
     pub fn build(self) -> Result<TuiTestHarness> {
-        let terminal = TestTerminal::new(self.width, self.height)?;
-        let state = ScreenState::new(self.width, self.height);
+        let mut state = ScreenState::new(self.width, self.height)
+            .with_palette(self.palette)
+            .with_resize_mode(self.resize_mode);
+        if let Some((width_px, height_px)) = self.cell_size {
+            state = state.with_cell_size(width_px, height_px);
+        }
+        let (cell_width, cell_height) = state.cell_size();
+        let terminal =
+            TestTerminal::new(self.width, self.height)?.with_cell_size(cell_width, cell_height)?;
 
         Ok(TuiTestHarness {
             terminal,
@@ -3066,8 +3362,11 @@ impl TuiTestHarnessBuilder {
             recording_start: None,
             verbose: false,
             terminal_profile: self.terminal_profile,
+            query_replies: self.query_replies,
+            query_handler: self.query_handler,
             timing_recorder: TimingRecorder::new(),
             latency_profile: LatencyProfile::new(),
+            prompt_view: None,
         })
     }
 }
@@ -3153,6 +3452,20 @@ mod tests {
         assert_eq!(harness.buffer_size, buffer_size);
     }
 
+    #[test]
+    fn test_builder_with_cell_size() {
+        let harness = TuiTestHarness::builder().build().unwrap();
+        assert_eq!(harness.state.cell_size(), (8, 6));
+        assert_eq!(harness.terminal.cell_size(), (8, 6));
+
+        let harness = TuiTestHarness::builder().with_cell_size(10, 20).build().unwrap();
+        assert_eq!(harness.state.cell_size(), (10, 20));
+        assert_eq!(harness.terminal.cell_size(), (10, 20));
+
+        let harness = TuiTestHarness::builder().with_cell_size(0, 0).build().unwrap();
+        assert_eq!(harness.terminal.cell_size(), (1, 1));
+    }
+
     #[test]
     fn test_builder_chaining() {
         let harness = TuiTestHarness::builder()
@@ -3247,6 +3560,20 @@ mod tests {
         assert_eq!(harness.state.size(), (100, 30));
     }
 
+    #[test]
+    fn test_resize_keeps_contents() {
+        let mut harness = TuiTestHarness::builder()
+            .with_size(10, 4)
+            .with_resize_mode(ResizeMode::Truncate)
+            .build()
+            .unwrap();
+        harness.state_mut().feed(b"first line\r\nsecond");
+
+        harness.resize(6, 4).unwrap();
+        assert_eq!(harness.state.row_contents(0), "first ");
+        assert_eq!(harness.state.row_contents(1), "second");
+    }
+
     #[test]
     fn test_is_running_no_process() {
         let mut harness = TuiTestHarness::new(80, 24).unwrap();
@@ -3905,6 +4232,21 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_assert_text_at_position_wide_characters() -> Result<()> {
+        let mut harness = TuiTestHarness::new(80, 24)?;
+
+        // Localized labels: each CJK character takes two columns
+        harness.state_mut().feed("\x1b[1;1H設定 | Café".as_bytes());
+
+        harness.assert_text_at_position("設定", 0, 0)?;
+        harness.assert_text_at_position("Café", 0, 7)?;
+        assert!(harness.assert_text_at_position("定", 0, 1).is_err());
+        harness.assert_text_within_bounds("Café", crate::screen::Rect::new(0, 0, 20, 1))?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_assert_text_at_position_failure() -> Result<()> {
         let mut harness = TuiTestHarness::new(80, 24)?;
@@ -4235,6 +4577,7 @@ mod tests {
             width: 100,
             height: 50,
             data: sixel_data,
+            params: vec![],
         };
 
         // Manually add the region to the state for testing
@@ -4438,4 +4781,22 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_reset_is_recorded() -> Result<()> {
+        let mut harness = TuiTestHarness::new(80, 24)?;
+        harness.start_recording();
+
+        let mut cmd = CommandBuilder::new("bash");
+        cmd.args(["-c", "printf 'stale\\033cfresh'; sleep 1"]);
+        harness.spawn(cmd)?;
+        harness.wait_for_text("fresh")?;
+
+        assert!(!harness.screen_contents().contains("stale"));
+        assert!(harness
+            .recorded_events
+            .iter()
+            .any(|event| matches!(event.event, RecordedEvent::Reset(TerminalReset::Full))));
+        Ok(())
+    }
 }
@@ -63,7 +63,7 @@
 //!
 //!     // Verify parsed state
 //!     assert!(screen.contains("Hello"));
-//!     assert_eq!(screen.get_cell(0, 0).unwrap().fg, Some(1)); // Red
+//!     assert_eq!(screen.get_cell(0, 0).unwrap().fg, Color::Indexed(1)); // Red
 //!     assert_eq!(screen.cursor_position(), (0, 5));
 //! }
 //! ```
@@ -191,6 +191,7 @@ pub mod navigation;
 pub mod parallel;
 mod pty;
 mod screen;
+pub mod shell;
 pub mod terminal_profiles;
 pub mod timing;
 
@@ -230,7 +231,13 @@ pub use parallel::{
     IsolatedTerminal, PoolConfig, PoolStats, TerminalGuard, TerminalId, TerminalPool, TestContext,
 };
 pub use pty::TestTerminal;
-pub use screen::{Cell, GridSnapshot, ITerm2Region, KittyRegion, Rect, ScreenState, SixelRegion};
+pub use screen::{
+    Cell, ClipboardWrite, Color, CursorStyle, GridSnapshot, Hyperlink, HyperlinkSpan,
+    ITerm2Region, KittyImage, KittyPlaceholder, KittyPlacement, KittyRegion, MouseEncoding,
+    MouseTracking, Notification, Palette, Rect, ResizeMode, ResolvedColors, ScreenState,
+    SixelRegion, TerminalModes, TerminalQuery, TerminalReset, UnderlineStyle,
+};
+pub use shell::{CommandOutput, Shell, ShellSession, ShellSessionBuilder};
 pub use terminal_profiles::{
     ColorDepth, Feature, MouseProtocol, TerminalCapabilities, TerminalProfile,
 };
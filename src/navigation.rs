@@ -49,6 +49,7 @@
 use std::time::Duration;
 
 use regex::Regex;
+use unicode_width::UnicodeWidthStr;
 
 use crate::{
     error::{Result, TermTestError},
@@ -221,18 +222,22 @@ pub enum PromptMarkerType {
 ///     line: 10,
 ///     marker_type: PromptMarkerType::PromptStart,
 ///     command: None,
+///     exit_code: None,
 /// };
 ///
 /// assert_eq!(marker.line, 10);
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PromptMarker {
-    /// Line number where the marker was detected (0-indexed).
+    /// Line number where the marker was detected (0-indexed), counted from
+    /// the oldest scrollback line.
     pub line: usize,
     /// Type of prompt marker.
     pub marker_type: PromptMarkerType,
     /// Command text if this is a CommandStart marker.
     pub command: Option<String>,
+    /// Exit code if this is a CommandFinished marker that reported one.
+    pub exit_code: Option<i32>,
 }
 
 /// Extension trait for navigation testing.
@@ -411,8 +416,10 @@ pub trait NavigationTestExt {
 
     /// Get all shell prompt markers from OSC 133 sequences.
     ///
-    /// Parses the screen state for OSC 133 prompt markers, returning
-    /// a list of all detected markers in order.
+    /// Returns the OSC 133 prompt markers on the screen and in the
+    /// scrollback, in order. See [`ScreenState::prompt_markers`].
+    ///
+    /// [`ScreenState::prompt_markers`]: crate::ScreenState::prompt_markers
     ///
     /// # Example
     ///
@@ -432,7 +439,12 @@ pub trait NavigationTestExt {
 
     /// Jump to a specific prompt by index.
     ///
-    /// Moves the cursor or scrolls to the Nth prompt marker.
+    /// Selects the Nth prompt, counting
+    /// [`PromptStart`](PromptMarkerType::PromptStart) markers only, as the
+    /// one [`current_prompt_index`](Self::current_prompt_index) reports. The
+    /// harness has no scrollable view, so the screen itself is unchanged. The
+    /// selection lasts until input is sent, which returns to the prompt at
+    /// the cursor.
     ///
     /// # Arguments
     ///
@@ -440,14 +452,14 @@ pub trait NavigationTestExt {
     ///
     /// # Errors
     ///
-    /// Returns an error if:
-    /// - The index is out of bounds
-    /// - The jump command cannot be sent
+    /// Returns an error if the index is out of bounds.
     fn jump_to_prompt(&mut self, index: usize) -> Result<()>;
 
     /// Get the index of the current prompt.
     ///
-    /// Determines which prompt the cursor is currently at or near.
+    /// Returns the prompt jumped to with
+    /// [`jump_to_prompt`](Self::jump_to_prompt), or else the last prompt
+    /// starting at or above the cursor.
     ///
     /// # Returns
     ///
@@ -567,16 +579,37 @@ impl NavigationTestExt for TuiTestHarness {
         // Regex to match hint labels: [a], [aa], [ab], etc.
         // Matches: [ followed by 1-2 lowercase letters followed by ]
         let hint_regex = Regex::new(r"\[([a-z]{1,2})\]").unwrap();
+        let links = self.state().hyperlinks();
 
         for (row_idx, line) in contents.lines().enumerate() {
-            for capture in hint_regex.captures_iter(line) {
+            // Regex offsets are bytes; hyperlinks and hint positions are columns
+            let column = |offset: usize| line[..offset].width() as u16;
+            let captures: Vec<_> = hint_regex.captures_iter(line).collect();
+            for (index, capture) in captures.iter().enumerate() {
                 if let Some(matched) = capture.get(0) {
                     let label = capture.get(1).unwrap().as_str().to_string();
-                    let col = matched.start() as u16;
+                    let col = column(matched.start());
                     let row = row_idx as u16;
 
+                    // An OSC 8 hyperlink between the label and the next label on
+                    // the same row is its target
+                    let end_col = column(matched.end());
+                    let next_col = captures
+                        .get(index + 1)
+                        .and_then(|next| next.get(0))
+                        .map_or(u16::MAX, |next| column(next.start()));
+                    let target_url = links
+                        .iter()
+                        .find(|link| {
+                            link.row == row && (end_col..next_col).contains(&link.start_col)
+                        })
+                        .map(|link| link.uri.clone());
+
                     // Try to determine element type from context
-                    let element_type = if line.contains("http://") || line.contains("https://") {
+                    let element_type = if target_url.is_some()
+                        || line.contains("http://")
+                        || line.contains("https://")
+                    {
                         HintElementType::Url
                     } else if line.contains('/') && line.contains('.') {
                         HintElementType::FilePath
@@ -596,7 +629,7 @@ impl NavigationTestExt for TuiTestHarness {
                     hints.push(HintLabel {
                         label,
                         position: (col, row),
-                        target_url: None, // Would need more context to extract
+                        target_url,
                         element_type,
                     });
                 }
@@ -670,28 +703,43 @@ impl NavigationTestExt for TuiTestHarness {
     }
 
     fn prompt_markers(&self) -> Vec<PromptMarker> {
-        // OSC 133 sequences are typically not visible in the rendered output
-        // They would need to be tracked during parsing
-        // This is a placeholder that would need integration with screen.rs
-
-        // For now, return empty - this requires OSC 133 tracking in TerminalState
-        Vec::new()
+        self.state().prompt_markers()
     }
 
-    fn jump_to_prompt(&mut self, _index: usize) -> Result<()> {
-        // This would require application-specific commands
-        // Placeholder implementation
-        Err(TermTestError::Parse(
-            "Prompt jumping not yet implemented - requires OSC 133 tracking".to_string(),
-        ))
+    fn jump_to_prompt(&mut self, index: usize) -> Result<()> {
+        let count = prompt_lines(&self.prompt_markers()).count();
+        if index >= count {
+            return Err(TermTestError::Parse(format!(
+                "Prompt {} out of range: {} prompts found",
+                index, count
+            )));
+        }
+        self.set_prompt_view(Some(index));
+        Ok(())
     }
 
     fn current_prompt_index(&self) -> Option<usize> {
-        // Would need to compare cursor position to prompt marker positions
-        None
+        if let Some(index) = self.prompt_view() {
+            return Some(index);
+        }
+
+        // The last prompt starting at or above the cursor
+        let cursor_line = self.state().primary_cursor_line();
+        prompt_lines(&self.prompt_markers())
+            .take_while(|&line| line <= cursor_line)
+            .count()
+            .checked_sub(1)
     }
 }
 
+/// Helper function returning the lines of the prompt start markers.
+fn prompt_lines(markers: &[PromptMarker]) -> impl Iterator<Item = usize> + '_ {
+    markers
+        .iter()
+        .filter(|marker| marker.marker_type == PromptMarkerType::PromptStart)
+        .map(|marker| marker.line)
+}
+
 /// Helper function to detect if content contains hint labels.
 fn contains_hint_labels(contents: &str) -> bool {
     // Look for hint label patterns like [a], [b], [aa]
@@ -804,6 +852,48 @@ mod tests {
         assert_eq!(hint.element_type, HintElementType::Url);
     }
 
+    #[test]
+    fn test_visible_hints_use_hyperlink_targets() {
+        let mut harness = TuiTestHarness::new(60, 3).unwrap();
+        harness.state_mut().feed(
+            b"[a] \x1b]8;;https://docs.rs/ratatui\x1b\\docs\x1b]8;;\x1b\\\r\n[b] src/main.rs",
+        );
+
+        let hints = harness.visible_hints();
+        assert_eq!(hints.len(), 2);
+        assert_eq!(hints[0].element_type, HintElementType::Url);
+        assert_eq!(hints[0].target_url.as_deref(), Some("https://docs.rs/ratatui"));
+        assert_eq!(hints[1].element_type, HintElementType::FilePath);
+        assert_eq!(hints[1].target_url, None);
+    }
+
+    #[test]
+    fn test_visible_hints_on_bordered_row() {
+        let mut harness = TuiTestHarness::new(60, 3).unwrap();
+        harness.state_mut().feed(
+            "│ [a] \x1b]8;;https://docs.rs/ratatui\x1b\\docs\x1b]8;;\x1b\\ │".as_bytes(),
+        );
+
+        let hints = harness.visible_hints();
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].position, (2, 0));
+        assert_eq!(hints[0].target_url.as_deref(), Some("https://docs.rs/ratatui"));
+    }
+
+    #[test]
+    fn test_visible_hints_link_belongs_to_nearest_label() {
+        let mut harness = TuiTestHarness::new(60, 3).unwrap();
+        harness
+            .state_mut()
+            .feed(b"[a] plain  [b] \x1b]8;;https://docs.rs/ratatui\x1b\\docs\x1b]8;;\x1b\\");
+
+        let hints = harness.visible_hints();
+        assert_eq!(hints.len(), 2);
+        assert_eq!(hints[0].target_url, None);
+        assert_eq!(hints[1].position, (11, 0));
+        assert_eq!(hints[1].target_url.as_deref(), Some("https://docs.rs/ratatui"));
+    }
+
     #[test]
     fn test_focus_info_creation() {
         let focus = FocusInfo {
@@ -828,6 +918,7 @@ mod tests {
             line: 10,
             marker_type: PromptMarkerType::PromptStart,
             command: None,
+            exit_code: None,
         };
 
         assert_eq!(marker.line, 10);
@@ -841,6 +932,7 @@ mod tests {
             line: 15,
             marker_type: PromptMarkerType::CommandStart,
             command: Some("ls -la".to_string()),
+            exit_code: None,
         };
 
         assert_eq!(marker.line, 15);
@@ -848,6 +940,25 @@ mod tests {
         assert_eq!(marker.command, Some("ls -la".to_string()));
     }
 
+    #[test]
+    fn test_prompt_navigation() {
+        let mut harness = TuiTestHarness::new(20, 4).unwrap();
+        harness.state_mut().feed(
+            b"\x1b]133;A\x07$ \x1b]133;B\x07false\r\n\x1b]133;C\x07\x1b]133;D;1\x07\
+              \x1b]133;A\x07$ \x1b]133;B\x07",
+        );
+
+        let markers = harness.prompt_markers();
+        assert_eq!(markers.len(), 6);
+        assert_eq!(markers[1].command.as_deref(), Some("false"));
+        assert_eq!(markers[3].exit_code, Some(1));
+        assert_eq!(harness.current_prompt_index(), Some(1));
+
+        harness.jump_to_prompt(0).unwrap();
+        assert_eq!(harness.current_prompt_index(), Some(0));
+        assert!(harness.jump_to_prompt(2).is_err());
+    }
+
     #[test]
     fn test_hint_element_types() {
         // Just verify the types exist and can be compared
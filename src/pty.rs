@@ -29,6 +29,14 @@ pub struct TestTerminal {
     exit_status: Option<ExitStatus>,
     buffer_size: usize,
     writer: Option<Box<dyn Write + Send>>,
+    /// Output from the background reader thread, started on the first read
+    output: Option<mpsc::Receiver<std::io::Result<Vec<u8>>>>,
+    /// Output received but not yet returned, when it did not fit the caller's buffer
+    pending: Vec<u8>,
+    /// Size in columns and rows
+    size: (u16, u16),
+    /// Cell size in pixels reported in the window size, or (0, 0) if unknown
+    cell_size: (u16, u16),
 }
 
 impl TestTerminal {
@@ -72,9 +80,38 @@ impl TestTerminal {
             exit_status: None,
             buffer_size: DEFAULT_BUFFER_SIZE,
             writer: None,
+            output: None,
+            pending: Vec::new(),
+            size: (width, height),
+            cell_size: (0, 0),
         })
     }
 
+    /// Sets the cell size in pixels reported to the application.
+    ///
+    /// The PTY window size then carries the screen size in pixels
+    /// (`ws_xpixel`/`ws_ypixel` of `TIOCGWINSZ`), which applications use to
+    /// size images. By default the pixel size is 0, meaning unknown.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PTY cannot be resized.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ratatui_testlib::TestTerminal;
+    ///
+    /// let terminal = TestTerminal::new(80, 24)?.with_cell_size(10, 20)?;
+    /// # Ok::<(), ratatui_testlib::TermTestError>(())
+    /// ```
+    pub fn with_cell_size(mut self, width_px: u16, height_px: u16) -> Result<Self> {
+        self.cell_size = (width_px, height_px);
+        let (width, height) = self.size;
+        self.resize(width, height)?;
+        Ok(self)
+    }
+
     /// Sets the buffer size for read operations.
     ///
     /// # Arguments
@@ -208,49 +245,70 @@ impl TestTerminal {
         // This ensures we return quickly when no data is available
         let read_timeout = Duration::from_millis(100);
 
-        let mut reader = self.pty_pair.master.try_clone_reader().map_err(|e| {
-            TermTestError::Io(std::io::Error::new(
-                ErrorKind::Other,
-                format!("Failed to clone PTY reader: {}", e),
-            ))
-        })?;
+        if self.pending.is_empty() {
+            match self.output()?.recv_timeout(read_timeout) {
+                Ok(Ok(chunk)) => self.pending = chunk,
+                Ok(Err(e)) => return Err(TermTestError::Io(e)),
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    // No data available within timeout - return 0 (non-blocking behavior)
+                    return Ok(0);
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    // The reader stopped at end of output; a later read starts a new one
+                    self.output = None;
+                    return Ok(0);
+                }
+            }
+        }
 
-        // Use a channel to implement timeout on blocking read
-        let (tx, rx) = mpsc::channel();
-        let buf_len = buf.len();
+        let n = self.pending.len().min(buf.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
 
-        std::thread::spawn(move || {
-            let mut local_buf = vec![0u8; buf_len];
-            let result = reader.read(&mut local_buf);
-            let _ = tx.send((result, local_buf));
-        });
+    /// Returns the channel fed by the background reader thread, starting it
+    /// if needed.
+    ///
+    /// A single thread owns the blocking PTY reader for as long as there is
+    /// output, so data arriving after a read has timed out is kept for the
+    /// next read instead of being lost.
+    fn output(&mut self) -> Result<&mpsc::Receiver<std::io::Result<Vec<u8>>>> {
+        if self.output.is_none() {
+            let mut reader = self.pty_pair.master.try_clone_reader().map_err(|e| {
+                TermTestError::Io(std::io::Error::new(
+                    ErrorKind::Other,
+                    format!("Failed to clone PTY reader: {}", e),
+                ))
+            })?;
 
-        match rx.recv_timeout(read_timeout) {
-            Ok((Ok(n), local_buf)) => {
-                if n > 0 {
-                    buf[..n].copy_from_slice(&local_buf[..n]);
-                }
-                Ok(n)
-            }
-            Ok((Err(e), _)) => {
-                if e.kind() == ErrorKind::Interrupted {
-                    // Retry on interrupt - but return 0 to let caller retry
-                    Ok(0)
-                } else if e.kind() == ErrorKind::WouldBlock {
-                    Ok(0)
-                } else {
-                    Err(TermTestError::Io(e))
+            let (tx, rx) = mpsc::channel();
+            let buffer_size = self.buffer_size.max(1);
+            std::thread::spawn(move || loop {
+                let mut chunk = vec![0u8; buffer_size];
+                match reader.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        chunk.truncate(n);
+                        if tx.send(Ok(chunk)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                        std::thread::sleep(Duration::from_millis(10));
+                    }
+                    Err(e) => {
+                        // EIO once the child has closed its side of the PTY
+                        let _ = tx.send(Err(e));
+                        break;
+                    }
                 }
-            }
-            Err(mpsc::RecvTimeoutError::Timeout) => {
-                // No data available within timeout - return 0 (non-blocking behavior)
-                Ok(0)
-            }
-            Err(mpsc::RecvTimeoutError::Disconnected) => {
-                // Thread panicked or channel closed
-                Ok(0)
-            }
+            });
+            self.output = Some(rx);
         }
+
+        Ok(self.output.as_ref().expect("reader thread was just started"))
     }
 
     /// Reads output from the PTY with a timeout.
@@ -438,19 +496,23 @@ impl TestTerminal {
         self.pty_pair.master.resize(PtySize {
             rows: height,
             cols: width,
-            pixel_width: 0,
-            pixel_height: 0,
+            pixel_width: width.saturating_mul(self.cell_size.0),
+            pixel_height: height.saturating_mul(self.cell_size.1),
         })?;
+        self.size = (width, height);
 
         Ok(())
     }
 
     /// Returns the current PTY dimensions.
     pub fn size(&self) -> (u16, u16) {
-        // Note: portable-pty doesn't provide a way to query current size,
-        // so we'll need to track this ourselves in the future
-        // For now, return a placeholder
-        (80, 24)
+        self.size
+    }
+
+    /// Returns the cell size in pixels set with
+    /// [`with_cell_size`](Self::with_cell_size), or (0, 0) if none was set.
+    pub fn cell_size(&self) -> (u16, u16) {
+        self.cell_size
     }
 
     /// Checks if the child process is still running.
@@ -703,6 +765,18 @@ mod tests {
         assert!(matches!(result, Err(TermTestError::InvalidDimensions { .. })));
     }
 
+    #[test]
+    fn test_cell_size_sets_pixel_size() {
+        let mut terminal = TestTerminal::new(80, 24).unwrap().with_cell_size(10, 20).unwrap();
+        terminal.resize(100, 30).unwrap();
+
+        let size = terminal.pty_pair.master.get_size().unwrap();
+        assert_eq!((size.cols, size.rows), (100, 30));
+        assert_eq!((size.pixel_width, size.pixel_height), (1000, 600));
+        assert_eq!(terminal.size(), (100, 30));
+        assert_eq!(terminal.cell_size(), (10, 20));
+    }
+
     #[test]
     fn test_spawn_process() {
         let mut terminal = TestTerminal::new(80, 24).unwrap();
@@ -796,6 +870,24 @@ mod tests {
         assert!(String::from_utf8_lossy(&buf[..n]).contains("hello world"));
     }
 
+    #[test]
+    fn test_read_keeps_output_after_idle_reads() {
+        let mut terminal = TestTerminal::new(80, 24).unwrap();
+        terminal.spawn(CommandBuilder::new("cat")).unwrap();
+
+        // Reads that time out must not lose output arriving later
+        let mut buf = [0u8; 1024];
+        for _ in 0..3 {
+            terminal.read(&mut buf).unwrap();
+        }
+        terminal.write_all(b"after idle\n").unwrap();
+
+        let n = terminal
+            .read_timeout(&mut buf, Duration::from_secs(2))
+            .unwrap();
+        assert!(String::from_utf8_lossy(&buf[..n]).contains("after idle"));
+    }
+
     #[test]
     fn test_read_timeout() {
         let mut terminal = TestTerminal::new(80, 24).unwrap();
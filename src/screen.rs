@@ -18,7 +18,7 @@
 //! against deterministic byte sequences.
 //!
 //! ```rust
-//! use ratatui_testlib::ScreenState;
+//! use ratatui_testlib::{Color, ScreenState};
 //!
 //! // Create a parser without any PTY
 //! let mut screen = ScreenState::new(80, 24);
@@ -29,7 +29,7 @@
 //!
 //! // Query the parsed state
 //! assert!(screen.contains("Hello"));
-//! assert_eq!(screen.get_cell(0, 0).unwrap().fg, Some(1)); // Red color
+//! assert_eq!(screen.get_cell(0, 0).unwrap().fg, Color::Indexed(1)); // Red color
 //! ```
 //!
 //! ## 2. PTY-Based Testing (Full TUI Integration)
@@ -69,41 +69,410 @@
 //! // - Sixel regions: oracle.sixel_regions()
 //! ```
 
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    sync::Arc,
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 use vtparse::{CsiParam, VTActor, VTParser};
 
+use crate::{
+    navigation::{PromptMarker, PromptMarkerType},
+    terminal_profiles::{MouseProtocol, TerminalProfile},
+};
+
+/// Default number of lines kept in the scrollback buffer.
+const DEFAULT_SCROLLBACK_LIMIT: usize = 1000;
+
+/// Maximum number of unanswered queries kept; older ones are dropped.
+const MAX_PENDING_QUERIES: usize = 64;
+
+/// Columns between the tab stops set initially.
+const DEFAULT_TAB_WIDTH: u16 = 8;
+
+/// Maximum number of resets kept until taken; older ones are dropped.
+const MAX_PENDING_RESETS: usize = 64;
+
+/// Maximum depth of the XTPUSHTITLE stack, as in xterm.
+const MAX_TITLE_STACK: usize = 10;
+
+/// Maximum number of bells and notifications kept; older ones are dropped.
+const MAX_NOTIFICATIONS: usize = 256;
+
+/// Maximum number of OSC 52 clipboard writes kept; older ones are dropped.
+const MAX_CLIPBOARD_WRITES: usize = 256;
+
+/// Default cell size in pixels: 8 wide and 6 tall (one Sixel band).
+pub(crate) const DEFAULT_CELL_SIZE: (u16, u16) = (8, 6);
+
+/// Selection targets accepted in OSC 52: clipboard, primary, secondary,
+/// select and cut buffers 0-7.
+const CLIPBOARD_TARGETS: &str = "cpqs01234567";
+
+/// A foreground or background color.
+///
+/// # Example
+///
+/// ```rust
+/// use ratatui_testlib::{Color, ScreenState};
+///
+/// let mut screen = ScreenState::new(80, 24);
+/// screen.feed(b"\x1b[31;48;2;255;128;0mX");
+///
+/// let cell = screen.get_cell(0, 0).unwrap();
+/// assert_eq!(cell.fg, Color::Indexed(1));
+/// assert_eq!(cell.bg, Color::Rgb(255, 128, 0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Color {
+    /// The terminal's default color
+    #[default]
+    Default,
+    /// An entry of the 256-color palette (0-7 standard, 8-15 bright)
+    Indexed(u8),
+    /// A 24-bit truecolor value
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Returns true for [`Color::Default`].
+    pub fn is_default(&self) -> bool {
+        matches!(self, Color::Default)
+    }
+}
+
+impl From<u8> for Color {
+    fn from(index: u8) -> Self {
+        Color::Indexed(index)
+    }
+}
+
+/// Formats as `default`, the palette index (`196`) or `#rrggbb`.
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Color::Default => write!(f, "default"),
+            Color::Indexed(index) => write!(f, "{}", index),
+            Color::Rgb(r, g, b) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+        }
+    }
+}
+
+/// Parses the format written by the [`Display`](std::fmt::Display) impl.
+impl std::str::FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "default" {
+            return Ok(Color::Default);
+        }
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+                    .ok_or_else(|| format!("Invalid RGB color: {:?}", s))
+            };
+            if hex.len() != 6 {
+                return Err(format!("Invalid RGB color: {:?}", s));
+            }
+            return Ok(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+        s.parse::<u8>()
+            .map(Color::Indexed)
+            .map_err(|_| format!("Invalid color: {:?}", s))
+    }
+}
+
+/// xterm's 16 ANSI colors, used for palette entries 0-15.
+const ANSI_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// The RGB values the terminal displays for the default colors and the
+/// 256-color palette.
+///
+/// The palette answers color queries (OSC 10, 11 and 4) and backs
+/// [`ScreenState::resolve_rgb`]. Entries 0-15 are xterm's ANSI colors,
+/// 16-231 the 6x6x6 color cube and 232-255 the grayscale ramp.
+///
+/// # Example
+///
+/// ```rust
+/// use ratatui_testlib::Palette;
+///
+/// let palette = Palette::light().with_color(1, (200, 40, 40));
+/// assert_eq!(palette.background, (255, 255, 255));
+/// assert_eq!(palette.colors[1], (200, 40, 40));
+/// assert_eq!(palette.colors[196], (255, 0, 0));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    /// Default foreground color (OSC 10)
+    pub foreground: (u8, u8, u8),
+    /// Default background color (OSC 11)
+    pub background: (u8, u8, u8),
+    /// The 256 indexed colors (OSC 4)
+    pub colors: [(u8, u8, u8); 256],
+}
+
+impl Default for Palette {
+    /// Returns [`Palette::dark`].
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Palette {
+    /// Light gray text on a black background.
+    pub fn dark() -> Self {
+        let mut colors = [(0, 0, 0); 256];
+        colors[..16].copy_from_slice(&ANSI_COLORS);
+        let level = |n: usize| if n == 0 { 0 } else { (55 + n * 40) as u8 };
+        for i in 0..216 {
+            colors[16 + i] = (level(i / 36), level(i / 6 % 6), level(i % 6));
+        }
+        for i in 0..24 {
+            let gray = (8 + i * 10) as u8;
+            colors[232 + i] = (gray, gray, gray);
+        }
+
+        Self { foreground: (229, 229, 229), background: (0, 0, 0), colors }
+    }
+
+    /// Black text on a white background.
+    pub fn light() -> Self {
+        Self { foreground: (0, 0, 0), background: (255, 255, 255), ..Self::dark() }
+    }
+
+    /// Sets the default foreground color.
+    pub fn with_foreground(mut self, rgb: (u8, u8, u8)) -> Self {
+        self.foreground = rgb;
+        self
+    }
+
+    /// Sets the default background color.
+    pub fn with_background(mut self, rgb: (u8, u8, u8)) -> Self {
+        self.background = rgb;
+        self
+    }
+
+    /// Sets one entry of the 256-color palette.
+    pub fn with_color(mut self, index: u8, rgb: (u8, u8, u8)) -> Self {
+        self.colors[index as usize] = rgb;
+        self
+    }
+
+    /// Returns the RGB value of `color`, using `default` for [`Color::Default`].
+    fn rgb(&self, color: Color, default: (u8, u8, u8)) -> (u8, u8, u8) {
+        match color {
+            Color::Default => default,
+            Color::Indexed(index) => self.colors[index as usize],
+            Color::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+}
+
+/// The RGB values a cell is displayed with, from [`ScreenState::resolve_rgb`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResolvedColors {
+    /// Text color
+    pub fg: (u8, u8, u8),
+    /// Background color
+    pub bg: (u8, u8, u8),
+}
+
+/// Underline style selected with SGR 4, SGR 21 or the `4:N` sub-parameter form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum UnderlineStyle {
+    /// No underline
+    #[default]
+    None,
+    /// Single underline (`4`, `4:1`)
+    Single,
+    /// Double underline (`21`, `4:2`)
+    Double,
+    /// Curly underline (`4:3`)
+    Curly,
+    /// Dotted underline (`4:4`)
+    Dotted,
+    /// Dashed underline (`4:5`)
+    Dashed,
+}
+
+/// An OSC 8 hyperlink (`OSC 8 ; params ; URI ST`).
+///
+/// Every cell printed while the link is open shares the same `Hyperlink`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hyperlink {
+    /// The `id=` parameter; applications use it to mark separate runs of cells
+    /// (for example a URL wrapped across rows) as the same link
+    pub id: Option<String>,
+    /// Target URI
+    pub uri: String,
+}
+
+/// A run of cells on one row covered by the same hyperlink.
+///
+/// Returned by [`ScreenState::hyperlinks`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HyperlinkSpan {
+    /// Row of the run (0-based)
+    pub row: u16,
+    /// First column of the run (0-based)
+    pub start_col: u16,
+    /// Column after the last cell of the run
+    pub end_col: u16,
+    /// The link's `id=` parameter, if any
+    pub id: Option<String>,
+    /// Target URI
+    pub uri: String,
+    /// Text displayed in the run
+    pub text: String,
+}
+
 /// Represents a single terminal cell with character and attributes.
 ///
 /// This struct tracks the complete state of a terminal cell including:
-/// - The character being displayed
-/// - Foreground color (ANSI color code, 0-255, or None for default)
-/// - Background color (ANSI color code, 0-255, or None for default)
-/// - Text attributes (bold, italic, underline, etc.)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// - The grapheme cluster being displayed and its display width
+/// - Foreground, background and underline [`Color`]
+/// - Text attributes (bold, dim, italic, underline style, blink, reverse,
+///   hidden, strikethrough and overline)
+/// - The OSC 8 [`Hyperlink`] it belongs to, if any
+///
+/// A double-width character (CJK, most emoji) occupies two cells: the first
+/// holds the character with `width == 2` and the second is a spacer with
+/// `width == 0` that contributes no text.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Cell {
-    /// The character displayed in this cell
+    /// The character displayed in this cell (the first character of its
+    /// grapheme cluster)
     pub c: char,
-    /// Foreground color (None = default, Some(0-255) = ANSI color)
-    pub fg: Option<u8>,
-    /// Background color (None = default, Some(0-255) = ANSI color)
-    pub bg: Option<u8>,
+    /// Characters following `c` in the same grapheme cluster: combining marks,
+    /// variation selectors and ZWJ-joined characters
+    pub combining: String,
+    /// Display width in columns: 1 for most characters, 2 for wide characters
+    /// and 0 for the spacer cell after a wide character
+    pub width: u8,
+    /// Foreground color
+    pub fg: Color,
+    /// Background color
+    pub bg: Color,
     /// Bold attribute
     pub bold: bool,
     /// Italic attribute
     pub italic: bool,
-    /// Underline attribute
+    /// Underline attribute (true for any [`UnderlineStyle`] other than `None`)
     pub underline: bool,
+    /// Dim (faint) attribute
+    pub dim: bool,
+    /// Blink attribute (slow or rapid)
+    pub blink: bool,
+    /// Reverse video attribute
+    pub reverse: bool,
+    /// Hidden (concealed) attribute
+    pub hidden: bool,
+    /// Strikethrough attribute
+    pub strikethrough: bool,
+    /// Overline attribute
+    pub overline: bool,
+    /// Underline style
+    pub underline_style: UnderlineStyle,
+    /// Underline color (`Color::Default` draws the underline in the foreground color)
+    pub underline_color: Color,
+    /// OSC 8 hyperlink open when the cell was printed
+    pub hyperlink: Option<Arc<Hyperlink>>,
 }
 
 impl Default for Cell {
     fn default() -> Self {
         Self {
             c: ' ',
-            fg: None,
-            bg: None,
+            combining: String::new(),
+            width: 1,
+            fg: Color::Default,
+            bg: Color::Default,
             bold: false,
             italic: false,
             underline: false,
+            dim: false,
+            blink: false,
+            reverse: false,
+            hidden: false,
+            strikethrough: false,
+            overline: false,
+            underline_style: UnderlineStyle::None,
+            underline_color: Color::Default,
+            hyperlink: None,
+        }
+    }
+}
+
+impl Cell {
+    /// Returns the full grapheme cluster displayed in this cell.
+    ///
+    /// The spacer cell after a wide character returns an empty string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(10, 1);
+    /// screen.feed("e\u{301}".as_bytes());
+    ///
+    /// let cell = screen.get_cell(0, 0).unwrap();
+    /// assert_eq!(cell.c, 'e');
+    /// assert_eq!(cell.grapheme(), "e\u{301}");
+    /// ```
+    pub fn grapheme(&self) -> String {
+        if self.is_wide_continuation() {
+            return String::new();
         }
+        let mut text = String::with_capacity(self.c.len_utf8() + self.combining.len());
+        text.push(self.c);
+        text.push_str(&self.combining);
+        text
+    }
+
+    /// Returns true if this cell holds a double-width character.
+    pub fn is_wide(&self) -> bool {
+        self.width == 2
+    }
+
+    /// Returns true if this cell is the spacer covered by the double-width
+    /// character to its left.
+    pub fn is_wide_continuation(&self) -> bool {
+        self.width == 0
+    }
+
+    /// Returns the spacer cell that follows this cell when it is wide.
+    fn wide_spacer(&self) -> Cell {
+        Cell { c: ' ', combining: String::new(), width: 0, ..self.clone() }
+    }
+
+    /// Turns this cell into a narrow blank, keeping its attributes.
+    fn clear_text(&mut self) {
+        self.c = ' ';
+        self.combining.clear();
+        self.width = 1;
     }
 }
 
@@ -218,6 +587,7 @@ impl Rect {
 /// - `width`: Width of the Sixel image in pixels
 /// - `height`: Height of the Sixel image in pixels
 /// - `data`: The raw Sixel escape sequence data
+/// - `params`: The DCS parameters (`P1;P2;P3`) that introduced the sequence
 ///
 /// # Example
 ///
@@ -246,6 +616,8 @@ pub struct SixelRegion {
     pub height: u32,
     /// Raw Sixel escape sequence data.
     pub data: Vec<u8>,
+    /// DCS parameters (`P1;P2;P3`). `P2 = 1` means unset pixels stay transparent.
+    pub params: Vec<i64>,
 }
 
 /// Represents a Kitty graphics region in the terminal.
@@ -274,6 +646,136 @@ pub struct KittyRegion {
     pub data: Vec<u8>,
 }
 
+/// An image stored by the Kitty graphics protocol.
+///
+/// Images are created by transmit commands (`a=t`, or `a=T` to also display
+/// them), which may be split into several chunks with `m=1`. Placements refer
+/// to images by id; see [`KittyPlacement`].
+///
+/// # Example
+///
+/// ```rust
+/// use ratatui_testlib::ScreenState;
+///
+/// let mut screen = ScreenState::new(80, 24);
+/// // A 1x1 RGBA image uploaded in two chunks
+/// screen.feed(b"\x1b_Ga=t,i=7,f=32,s=1,v=1,m=1;AAAA\x1b\\");
+/// screen.feed(b"\x1b_Gm=0;AA==\x1b\\");
+///
+/// let images = screen.kitty_images();
+/// assert_eq!(images.len(), 1);
+/// assert_eq!(images[0].id, 7);
+/// assert_eq!(images[0].chunks, 2);
+/// assert_eq!(images[0].data, vec![0, 0, 0, 0]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KittyImage {
+    /// Image id (`i=`). Images transmitted without one are assigned an id.
+    pub id: u32,
+    /// Image number (`I=`), if the client used one.
+    pub number: Option<u32>,
+    /// Pixel format (`f=`): 24 (RGB), 32 (RGBA) or 100 (PNG).
+    pub format: u32,
+    /// Width of the pixel data in pixels (`s=`), 0 if not given.
+    pub width: u32,
+    /// Height of the pixel data in pixels (`v=`), 0 if not given.
+    pub height: u32,
+    /// Transmission medium (`t=`): `d` (direct), `f` (file), `t` (temporary
+    /// file) or `s` (shared memory).
+    pub medium: char,
+    /// Base64-decoded payload: the pixel data for direct transmission, or the
+    /// file or shared memory name otherwise. Compressed data (`o=z`) is kept
+    /// compressed.
+    pub data: Vec<u8>,
+    /// Number of chunks the image was transmitted in.
+    pub chunks: usize,
+}
+
+/// A placement of a [`KittyImage`] on the screen.
+///
+/// Placements are created by `a=T` and `a=p` commands at the cursor position
+/// and removed by `a=d`. Their size comes from `c=`/`r=` when given, and from
+/// the image's pixel size otherwise.
+///
+/// Virtual placements (`U=1`) are not drawn at the cursor: the application
+/// prints Unicode placeholder cells instead, which
+/// [`ScreenState::kitty_placeholders`] resolves back to the image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KittyPlacement {
+    /// Id of the placed image.
+    pub image_id: u32,
+    /// Placement id (`p=`), 0 if the client did not set one.
+    pub placement_id: u32,
+    /// Top row of the placement (0-indexed).
+    pub row: u16,
+    /// Left column of the placement (0-indexed).
+    pub col: u16,
+    /// Width in cells.
+    pub cols: u16,
+    /// Height in cells.
+    pub rows: u16,
+    /// Stacking order (`z=`); negative values are drawn below text.
+    pub z_index: i32,
+    /// True for a virtual placement (`U=1`), shown through Unicode
+    /// placeholders. Its `row` and `col` are 0 and its size is the
+    /// placeholder grid.
+    pub virtual_placement: bool,
+}
+
+/// A cell that shows part of a Kitty image through a Unicode placeholder.
+///
+/// Placeholder cells hold `U+10EEEE` followed by up to three diacritics that
+/// encode the image row, the image column and the most significant byte of
+/// the image id. The foreground color carries the lower 24 bits of the image
+/// id and the underline color the placement id. Missing diacritics continue
+/// the placeholder to the left, as in kitty.
+///
+/// # Example
+///
+/// ```rust
+/// use ratatui_testlib::ScreenState;
+///
+/// let mut screen = ScreenState::new(80, 24);
+/// // Virtual placement of image 42, 2 columns by 1 row
+/// screen.feed(b"\x1b_Ga=p,U=1,i=42,c=2,r=1,q=2\x1b\\");
+/// // Image id 42 in the foreground color; row 0 and column 0 diacritics on
+/// // the first cell, the second cell continues it
+/// screen.feed("\x1b[38;5;42m\u{10EEEE}\u{305}\u{305}\u{10EEEE}\x1b[39m".as_bytes());
+///
+/// let cell = screen.kitty_placeholder_at(0, 1).unwrap();
+/// assert_eq!(cell.image_id, 42);
+/// assert_eq!((cell.image_row, cell.image_col), (0, 1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KittyPlaceholder {
+    /// Screen row of the cell (0-indexed).
+    pub row: u16,
+    /// Screen column of the cell (0-indexed).
+    pub col: u16,
+    /// Id of the image shown.
+    pub image_id: u32,
+    /// Placement id from the underline color, 0 if none was given.
+    pub placement_id: u32,
+    /// Row of the image shown in this cell, in cells from the image's top.
+    pub image_row: u16,
+    /// Column of the image shown in this cell, in cells from its left edge.
+    pub image_col: u16,
+}
+
+impl KittyPlacement {
+    /// Returns the cell rectangle as (row, col, width, height), the same
+    /// layout as `GraphicsRegion::bounds`.
+    pub fn bounds(&self) -> (u16, u16, u16, u16) {
+        (self.row, self.col, self.cols, self.rows)
+    }
+
+    /// Returns `true` if the cell at (`row`, `col`) is covered by this placement.
+    pub fn contains(&self, row: u16, col: u16) -> bool {
+        (self.row..self.row.saturating_add(self.rows)).contains(&row)
+            && (self.col..self.col.saturating_add(self.cols)).contains(&col)
+    }
+}
+
 /// Represents an iTerm2 inline image region in the terminal.
 ///
 /// iTerm2 inline images use OSC 1337;File= sequences to embed
@@ -283,9 +785,31 @@ pub struct KittyRegion {
 ///
 /// - `start_row`: The row where the image begins (0-indexed)
 /// - `start_col`: The column where the image begins (0-indexed)
-/// - `width`: Width in cells (if specified in params)
-/// - `height`: Height in cells (if specified in params)
+/// - `width`: Width in cells
+/// - `height`: Height in cells
 /// - `data`: The raw OSC escape sequence data
+///
+/// The size follows iTerm2's `width` and `height` arguments: `N` cells, `Npx`
+/// pixels, `N%` of the screen, or `auto` for the image's own size. With
+/// `preserveAspectRatio=1` (the default), an `auto` dimension is scaled from
+/// the other one. The image's own size is only known with the `sixel-image`
+/// feature, which decodes the embedded file; without it, `auto` dimensions
+/// are 0.
+///
+/// # Example
+///
+/// ```rust
+/// use ratatui_testlib::ScreenState;
+///
+/// let mut screen = ScreenState::new(80, 24);
+/// // "aGkudHh0" is base64 for "hi.txt", "SGVsbG8=" for "Hello"
+/// screen.feed(b"\x1b]1337;File=name=aGkudHh0;inline=1;width=50%;height=24px:SGVsbG8=\x07");
+///
+/// let region = &screen.iterm2_regions()[0];
+/// assert_eq!((region.width, region.height), (40, 4));
+/// assert_eq!(region.file_name().as_deref(), Some("hi.txt"));
+/// assert_eq!(region.file_data(), Some(b"Hello".to_vec()));
+/// ```
 #[derive(Debug, Clone)]
 pub struct ITerm2Region {
     /// Starting row (0-indexed).
@@ -300,6 +824,109 @@ pub struct ITerm2Region {
     pub data: Vec<u8>,
 }
 
+impl ITerm2Region {
+    /// Returns the file name from the `name` argument, decoded from base64.
+    pub fn file_name(&self) -> Option<String> {
+        let (args, _) = iterm2_file_parts(&self.data)?;
+        let name = iterm2_file_args(args).find(|(key, _)| *key == "name")?.1;
+        let name = BASE64.decode(name).ok()?;
+        Some(String::from_utf8_lossy(&name).into_owned())
+    }
+
+    /// Returns the embedded file, decoded from base64.
+    pub fn file_data(&self) -> Option<Vec<u8>> {
+        let (_, payload) = iterm2_file_parts(&self.data)?;
+        BASE64.decode(payload).ok()
+    }
+
+    /// Decodes the embedded file (PNG, JPEG, GIF or another format the
+    /// `image` crate reads) into an RGBA image.
+    ///
+    /// Requires the `sixel-image` feature. Use
+    /// [`sixel::assert_image_matches`](crate::sixel::assert_image_matches) to
+    /// compare the result with a reference PNG.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TermTestError::Parse`](crate::TermTestError::Parse) if the
+    /// payload is not valid base64 or not an image.
+    #[cfg(feature = "sixel-image")]
+    pub fn to_image(&self) -> crate::Result<image::RgbaImage> {
+        let data = self.file_data().ok_or_else(|| {
+            crate::TermTestError::Parse("iTerm2 image has no valid base64 payload".to_string())
+        })?;
+        let image = image::load_from_memory(&data).map_err(|e| {
+            crate::TermTestError::Parse(format!("failed to decode iTerm2 image: {e}"))
+        })?;
+        Ok(image.to_rgba8())
+    }
+}
+
+/// Splits the data of an `OSC 1337 ; File=args : payload` sequence into the
+/// argument list and the base64 payload.
+fn iterm2_file_parts(data: &[u8]) -> Option<(&str, &str)> {
+    let text = std::str::from_utf8(data).ok()?;
+    let file = text.strip_prefix("1337;File=")?;
+    Some(file.split_once(':').unwrap_or((file, "")))
+}
+
+/// Iterates over the `key=value` pairs of iTerm2 file arguments.
+fn iterm2_file_args(args: &str) -> impl Iterator<Item = (&str, &str)> {
+    args.split(';').filter_map(|arg| arg.split_once('='))
+}
+
+/// An iTerm2 inline image `width` or `height` argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ITerm2Size {
+    Auto,
+    Cells(u32),
+    Pixels(u32),
+    Percent(u32),
+}
+
+impl ITerm2Size {
+    fn parse(value: &str) -> Self {
+        let value = value.trim();
+        let parsed = if let Some(pixels) = value.strip_suffix("px") {
+            pixels.parse().map(Self::Pixels)
+        } else if let Some(percent) = value.strip_suffix('%') {
+            percent.parse().map(Self::Percent)
+        } else {
+            value.parse().map(Self::Cells)
+        };
+        parsed.unwrap_or(Self::Auto)
+    }
+
+    /// Converts to pixels, given the pixels per cell and the screen size in
+    /// cells along the same axis. `Auto` has no size of its own.
+    fn to_pixels(self, cell_pixels: u32, screen_cells: u16) -> Option<u32> {
+        match self {
+            Self::Auto => None,
+            Self::Cells(cells) => Some(cells.saturating_mul(cell_pixels)),
+            Self::Pixels(pixels) => Some(pixels),
+            Self::Percent(percent) => {
+                Some(u32::from(screen_cells) * cell_pixels * percent.min(100) / 100)
+            }
+        }
+    }
+}
+
+/// Returns the pixel size of an image file, if the `image` crate can read it.
+#[cfg(feature = "sixel-image")]
+fn image_file_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Image files are only decoded with the `sixel-image` feature.
+#[cfg(not(feature = "sixel-image"))]
+fn image_file_dimensions(_data: &[u8]) -> Option<(u32, u32)> {
+    None
+}
+
 /// A complete snapshot of the terminal screen grid state.
 ///
 /// This structure provides a point-in-time capture of the entire screen state,
@@ -344,1164 +971,5544 @@ pub struct GridSnapshot {
     pub cursor: (u16, u16),
 }
 
-/// Terminal state tracking for vtparse parser.
+/// Structured view of the parameters of a CSI sequence.
 ///
-/// Implements VTActor to handle escape sequences including DCS for Sixel,
-/// APC for Kitty graphics, and OSC for iTerm2 inline images.
-struct TerminalState {
-    cursor_pos: (u16, u16),
-    sixel_regions: Vec<SixelRegion>,
-    current_sixel_data: Vec<u8>,
-    current_sixel_params: Vec<i64>,
-    in_sixel_mode: bool,
+/// vtparse reports CSI parameters as a flat list of integers interleaved with
+/// separator and marker bytes. This splits them back into `;`-separated
+/// parameters (each with its `:`-separated sub-parameters), the private marker
+/// (`?`, `>`, `=`, `<`) and any intermediate bytes, preserving empty positions
+/// so that sequences like `ESC [ ; 5 H` keep their defaults.
+#[derive(Debug, Default)]
+struct CsiArgs {
+    /// Private marker byte, if the sequence had one.
+    private: Option<u8>,
+    /// Intermediate bytes (e.g. `b' '` for DECSCUSR, `b'!'` for DECSTR).
+    intermediates: Vec<u8>,
+    /// Parameters, each holding its colon-separated sub-parameters.
+    params: Vec<Vec<Option<i64>>>,
+}
 
-    // Kitty graphics protocol state
-    kitty_regions: Vec<KittyRegion>,
-    current_kitty_data: Vec<u8>,
-    in_kitty_mode: bool,
+impl CsiArgs {
+    fn parse(raw: &[CsiParam]) -> Self {
+        let mut args = CsiArgs::default();
+        let mut current: Vec<Option<i64>> = vec![None];
+        let mut seen_any = false;
+
+        for param in raw {
+            match param {
+                CsiParam::Integer(value) => {
+                    if let Some(last) = current.last_mut() {
+                        *last = Some(*value);
+                    }
+                    seen_any = true;
+                }
+                CsiParam::P(b';') => {
+                    args.params
+                        .push(std::mem::replace(&mut current, vec![None]));
+                    seen_any = true;
+                }
+                CsiParam::P(b':') => {
+                    current.push(None);
+                    seen_any = true;
+                }
+                CsiParam::P(marker @ (b'?' | b'>' | b'=' | b'<')) if !seen_any => {
+                    args.private = Some(*marker);
+                }
+                CsiParam::P(byte) => args.intermediates.push(*byte),
+            }
+        }
 
-    // iTerm2 inline images state
-    iterm2_regions: Vec<ITerm2Region>,
-    current_iterm2_data: Vec<u8>,
-    in_iterm2_mode: bool,
+        if seen_any {
+            args.params.push(current);
+        }
 
-    width: u16,
-    height: u16,
-    cells: Vec<Vec<Cell>>,
-    /// Current text attributes (for SGR sequences)
-    current_fg: Option<u8>,
-    current_bg: Option<u8>,
-    current_bold: bool,
-    current_italic: bool,
-    current_underline: bool,
+        args
+    }
+
+    /// Returns the parameter at `index`, or `None` if it was omitted.
+    fn get(&self, index: usize) -> Option<i64> {
+        self.params.get(index).and_then(|sub| sub[0])
+    }
+
+    /// Returns the parameter at `index`, falling back to `default` when omitted.
+    fn get_or(&self, index: usize, default: i64) -> i64 {
+        self.get(index).unwrap_or(default)
+    }
+
+    /// Returns a count/position parameter, where both 0 and omission mean 1.
+    fn count(&self, index: usize) -> u16 {
+        self.get_or(index, 1).clamp(1, u16::MAX as i64) as u16
+    }
 }
 
-impl TerminalState {
-    fn new(width: u16, height: u16) -> Self {
-        let cells = vec![vec![Cell::default(); width as usize]; height as usize];
+/// An OSC 133 shell integration mark, kept on the row it was received on
+/// so that it moves with the row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PromptMark {
+    col: u16,
+    marker_type: PromptMarkerType,
+    exit_code: Option<i32>,
+}
 
-        Self {
-            cursor_pos: (0, 0),
-            sixel_regions: Vec::new(),
-            current_sixel_data: Vec::new(),
-            current_sixel_params: Vec::new(),
-            in_sixel_mode: false,
-            kitty_regions: Vec::new(),
-            current_kitty_data: Vec::new(),
-            in_kitty_mode: false,
-            iterm2_regions: Vec::new(),
-            current_iterm2_data: Vec::new(),
-            in_iterm2_mode: false,
-            width,
-            height,
-            cells,
-            current_fg: None,
-            current_bg: None,
-            current_bold: false,
-            current_italic: false,
-            current_underline: false,
+/// A single screen row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Row {
+    cells: Vec<Cell>,
+    /// Set when autowrap continued this row onto the next one.
+    wrapped: bool,
+    /// Set when the last cell is a gap left by a wide character that did not
+    /// fit and wrapped to the next row; the gap is not part of the text.
+    padded: bool,
+    /// OSC 133 marks received on this row, in arrival order.
+    marks: Vec<PromptMark>,
+}
+
+impl Row {
+    fn new(cells: Vec<Cell>) -> Self {
+        Self { cells, wrapped: false, padded: false, marks: Vec::new() }
+    }
+
+    fn text(&self) -> String {
+        self.cells.iter().map(Cell::grapheme).collect()
+    }
+
+    /// Returns the cells holding printed text, without the gap before a
+    /// wide character that wrapped to the next row.
+    fn text_cells(&self) -> &[Cell] {
+        if self.wrapped && self.padded {
+            &self.cells[..self.cells.len().saturating_sub(1)]
+        } else {
+            &self.cells
         }
     }
 
-    fn put_char(&mut self, ch: char) {
-        let (row, col) = self.cursor_pos;
-        if row < self.height && col < self.width {
-            self.cells[row as usize][col as usize] = Cell {
-                c: ch,
-                fg: self.current_fg,
-                bg: self.current_bg,
-                bold: self.current_bold,
-                italic: self.current_italic,
-                underline: self.current_underline,
-            };
-            // Move cursor forward, but don't wrap automatically
-            if col + 1 < self.width {
-                self.cursor_pos.1 = col + 1;
+    /// Cuts or pads the row to `width` cells, blanking a wide character
+    /// whose spacer is cut off.
+    fn truncate(&mut self, width: usize) {
+        self.cells.resize(width, Cell::default());
+        for mark in &mut self.marks {
+            mark.col = mark.col.min(width.saturating_sub(1) as u16);
+        }
+        if self.cells.last().is_some_and(Cell::is_wide) {
+            if let Some(cell) = self.cells.last_mut() {
+                cell.clear_text();
             }
         }
     }
+}
 
-    fn move_cursor(&mut self, row: u16, col: u16) {
-        self.cursor_pos = (row.min(self.height - 1), col.min(self.width - 1));
+/// Rejoins soft-wrapped `rows` into lines and wraps them again at `width`.
+///
+/// `cursor` is the absolute row and column of a cell to follow; its new
+/// position is returned with the rows.
+fn reflow_rows(
+    rows: Vec<Row>,
+    width: usize,
+    cursor: Option<(usize, u16)>,
+) -> (Vec<Row>, Option<(usize, u16)>) {
+    let mut reflowed = Vec::new();
+    let mut new_cursor = None;
+    let mut line = Vec::new();
+    let mut line_cursor = None;
+    let mut line_marks = Vec::new();
+    let count = rows.len();
+
+    for (index, row) in rows.into_iter().enumerate() {
+        if let Some((_, col)) = cursor.filter(|&(cursor_row, _)| cursor_row == index) {
+            line_cursor = Some(line.len() + col as usize);
+        }
+        // The gap before a wide character that wrapped is not part of the line
+        let text_len = row.text_cells().len();
+        line_marks.extend(row.marks.into_iter().map(|mark| (line.len() + mark.col as usize, mark)));
+        line.extend(row.cells.into_iter().take(text_len));
+        if !row.wrapped || index + 1 == count {
+            let start = reflowed.len();
+            let cells = std::mem::take(&mut line);
+            let marks = std::mem::take(&mut line_marks);
+            let cursor = line_cursor.take();
+            if let Some((row, col)) = wrap_line(cells, width, cursor, marks, &mut reflowed) {
+                new_cursor = Some((start + row, col));
+            }
+        }
     }
+    (reflowed, new_cursor)
+}
 
-    /// Parse raster attributes from sixel data.
-    ///
-    /// Sixel raster attributes follow the format: "Pan;Pad;Ph;Pv
-    /// Where:
-    /// - Pan: Pixel aspect ratio numerator (typically 1)
-    /// - Pad: Pixel aspect ratio denominator (typically 1)
-    /// - Ph: Horizontal pixel dimension (width)
-    /// - Pv: Vertical pixel dimension (height)
-    ///
-    /// # Arguments
-    ///
-    /// * `data` - Raw sixel data bytes containing raster attributes
-    ///
-    /// # Returns
-    ///
-    /// `Some((width, height))` in pixels if raster attributes are found and valid,
-    /// `None` otherwise.
-    ///
-    /// # Examples
-    ///
-    /// - "1;1;100;50" → Some((100, 50))
-    /// - "100;50" → Some((100, 50)) (missing aspect ratio parameters)
-    /// - "" → None (no raster attributes)
-    fn parse_raster_attributes(&self, data: &[u8]) -> Option<(u32, u32)> {
-        let data_str = std::str::from_utf8(data).ok()?;
+/// Appends the cells of one logical line to `rows`, wrapped at `width`.
+///
+/// Trailing blank cells are dropped, except up to the `cursor` offset, and a
+/// wide character that does not fit at the end of a row moves to the next.
+/// `marks` are prompt marks keyed by their offset in the line; each goes to
+/// the row holding its cell, or the last row if that cell was dropped.
+/// Returns the cursor's row (relative to the line) and column.
+fn wrap_line(
+    mut cells: Vec<Cell>,
+    width: usize,
+    cursor: Option<usize>,
+    mut marks: Vec<(usize, PromptMark)>,
+    rows: &mut Vec<Row>,
+) -> Option<(usize, u16)> {
+    let keep = cursor.map_or(0, |offset| offset + 1);
+    while cells.len() > keep && cells.last() == Some(&Cell::default()) {
+        cells.pop();
+    }
+    marks.sort_by_key(|&(offset, _)| offset);
+    let mut marks = marks.into_iter().peekable();
+
+    let first = rows.len();
+    let mut position = None;
+    let mut row = Vec::with_capacity(width);
+    let mut row_marks = Vec::new();
+    for (index, cell) in cells.into_iter().enumerate() {
+        let full = row.len() == width || (cell.is_wide() && width > 1 && row.len() + 1 == width);
+        if full {
+            let padded = row.len() < width;
+            row.resize(width, Cell::default());
+            let cells = std::mem::replace(&mut row, Vec::with_capacity(width));
+            let marks = std::mem::take(&mut row_marks);
+            rows.push(Row { cells, wrapped: true, padded, marks });
+        }
+        if cursor == Some(index) {
+            position = Some((rows.len() - first, row.len() as u16));
+        }
+        while let Some((_, mark)) = marks.next_if(|&(offset, _)| offset <= index) {
+            row_marks.push(PromptMark { col: row.len() as u16, ..mark });
+        }
+        row.push(cell);
+    }
+    let last_col = row.len().min(width.saturating_sub(1)) as u16;
+    row_marks.extend(marks.map(|(_, mark)| PromptMark { col: last_col, ..mark }));
+    row.resize(width, Cell::default());
+    rows.push(Row { marks: row_marks, ..Row::new(row) });
+    position
+}
 
-        // Find the raster attributes command starting with '"'
-        let raster_start = data_str.find('"')?;
-        let after_quote = &data_str[raster_start + 1..];
+/// Returns the last of `rows` belonging to the logical line at `line`.
+fn logical_line_end(rows: &[&Row], mut line: usize) -> usize {
+    while rows.get(line).is_some_and(|row| row.wrapped) {
+        line += 1;
+    }
+    line
+}
 
-        // Find where the raster attributes end (terminated by non-digit, non-semicolon)
-        let end_pos = after_quote
-            .find(|c: char| !c.is_ascii_digit() && c != ';')
-            .unwrap_or(after_quote.len());
+/// Returns the text of `rows` from `start` up to (not including) `end`, both
+/// given as line and column. Rows that end a line are trimmed and separated
+/// by newlines, and trailing whitespace is removed.
+fn text_between(rows: &[&Row], start: (usize, u16), end: (usize, u16)) -> String {
+    let mut text = String::new();
+    for (line, row) in rows.iter().enumerate().take(end.0 + 1).skip(start.0) {
+        let from = if line == start.0 { start.1 as usize } else { 0 };
+        let cells = row.text_cells();
+        let to = if line == end.0 { end.1 as usize } else { cells.len() };
+        let to = to.min(cells.len());
+        if from < to {
+            text.extend(cells[from..to].iter().map(Cell::grapheme));
+        }
+        if !row.wrapped && line < end.0 {
+            text.truncate(text.trim_end().len());
+            text.push('\n');
+        }
+    }
+    text.truncate(text.trim_end().len());
+    text
+}
 
-        let raster_part = &after_quote[..end_pos];
+/// Splits `rows` into the lines above the screen and a screen of `height`
+/// rows, keeping the `cursor` row on screen.
+///
+/// Blank rows below the cursor are dropped first, so a taller screen shows
+/// lines from above it again. Returns those lines, the screen and the
+/// cursor's position on it.
+fn fit_rows(
+    mut rows: Vec<Row>,
+    width: u16,
+    height: u16,
+    cursor: Option<(usize, u16)>,
+) -> (Vec<Row>, Vec<Row>, Option<(u16, u16)>) {
+    let blank = Row::new(vec![Cell::default(); width as usize]);
+    let min_len = cursor.map_or(0, |(row, _)| row + 1);
+    while rows.len() > min_len && rows.last() == Some(&blank) {
+        rows.pop();
+    }
 
-        // Parse semicolon-separated numeric parameters
-        // Format: Pa;Pb;Ph;Pv where we need Ph (index 2) and Pv (index 3)
-        let parts: Vec<&str> = raster_part.split(';').filter(|s| !s.is_empty()).collect();
+    let mut start = rows.len().saturating_sub(height as usize);
+    if let Some((row, _)) = cursor {
+        start = start.min(row);
+    }
+    let mut screen = rows.split_off(start);
+    screen.truncate(height as usize);
+    screen.resize(height as usize, blank);
 
-        // Handle different parameter counts:
-        // - 4 params: Pan;Pad;Ph;Pv (full format)
-        // - 2 params: Ph;Pv (abbreviated format, aspect ratio omitted)
-        match parts.len() {
-            4 => {
-                // Full format: Pan;Pad;Ph;Pv
-                let width = parts[2].parse::<u32>().ok()?;
-                let height = parts[3].parse::<u32>().ok()?;
-                if width > 0 && height > 0 {
-                    Some((width, height))
-                } else {
-                    None
-                }
-            }
-            2 => {
-                // Abbreviated format: Ph;Pv
-                let width = parts[0].parse::<u32>().ok()?;
-                let height = parts[1].parse::<u32>().ok()?;
-                if width > 0 && height > 0 {
-                    Some((width, height))
-                } else {
-                    None
+    let cursor = cursor.map(|(row, col)| ((row - start) as u16, col.min(width - 1)));
+    (rows, screen, cursor)
+}
+
+/// Parses the color selector following SGR 38, 48 or 58.
+///
+/// `values` starts at the color space: `5;N` selects a palette entry and
+/// `2;R;G;B` a truecolor value. The colon form may carry an extra, usually
+/// empty, color space id before the components (`2::R:G:B`). Returns the color,
+/// if valid, and how many values were consumed.
+fn extended_color(values: &[Option<i64>], colon_form: bool) -> (Option<Color>, usize) {
+    let channel = |value: Option<i64>| value.unwrap_or(0).clamp(0, 255) as u8;
+
+    match values.first().copied().flatten() {
+        Some(5) => {
+            let color = values
+                .get(1)
+                .copied()
+                .flatten()
+                .filter(|index| (0..=255).contains(index))
+                .map(|index| Color::Indexed(index as u8));
+            (color, values.len().min(2))
+        }
+        Some(2) => {
+            let start = if colon_form && values.len() >= 5 { 2 } else { 1 };
+            match values.get(start..start + 3) {
+                Some(rgb) => {
+                    let color = Color::Rgb(channel(rgb[0]), channel(rgb[1]), channel(rgb[2]));
+                    (Some(color), start + 3)
                 }
+                None => (None, values.len()),
             }
-            _ => None,
         }
+        _ => (None, values.len().min(1)),
     }
+}
 
-    /// Converts pixel dimensions to terminal cell dimensions.
-    ///
-    /// Uses standard Sixel-to-terminal conversion ratios:
-    /// - 8 pixels per column (horizontal)
-    /// - 6 pixels per row (vertical - based on Sixel sixel height)
-    ///
-    /// These ratios are typical for Sixel graphics in VT340-compatible terminals.
-    /// Each Sixel band is 6 pixels tall, and character cells are typically 8 pixels wide.
-    ///
-    /// # Arguments
-    ///
-    /// * `width_px` - Width in pixels
-    /// * `height_px` - Height in pixels
-    ///
-    /// # Returns
-    ///
-    /// A tuple of (columns, rows) in terminal cells, with fractional cells rounded up.
-    ///
-    /// # Examples
-    ///
-    /// - (80, 60) pixels → (10, 10) cells
-    /// - (100, 50) pixels → (13, 9) cells (rounded up)
-    /// - (0, 0) pixels → (0, 0) cells
-    fn pixels_to_cells(width_px: u32, height_px: u32) -> (u16, u16) {
-        // Standard Sixel pixel-to-cell ratios
-        const PIXELS_PER_COL: u32 = 8;
-        const PIXELS_PER_ROW: u32 = 6;
+/// Cursor style selected with DECSCUSR (`CSI Ps SP q`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CursorStyle {
+    /// The terminal's default style (`CSI 0 SP q`, usually a blinking block)
+    #[default]
+    Default,
+    /// Blinking block (`CSI 1 SP q`)
+    BlinkingBlock,
+    /// Steady block (`CSI 2 SP q`)
+    SteadyBlock,
+    /// Blinking underline (`CSI 3 SP q`)
+    BlinkingUnderline,
+    /// Steady underline (`CSI 4 SP q`)
+    SteadyUnderline,
+    /// Blinking bar (`CSI 5 SP q`)
+    BlinkingBar,
+    /// Steady bar (`CSI 6 SP q`)
+    SteadyBar,
+}
 
-        let cols = if width_px > 0 {
-            ((width_px + PIXELS_PER_COL - 1) / PIXELS_PER_COL) as u16
-        } else {
-            0
-        };
+/// Mouse tracking mode requested by the application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MouseTracking {
+    /// No mouse reports
+    #[default]
+    None,
+    /// X10 compatibility: button presses only (`CSI ? 9 h`)
+    X10,
+    /// Button presses and releases (`CSI ? 1000 h`)
+    Normal,
+    /// Presses, releases and motion while a button is held (`CSI ? 1002 h`)
+    ButtonEvent,
+    /// Presses, releases and all motion (`CSI ? 1003 h`)
+    AnyEvent,
+}
 
-        let rows = if height_px > 0 {
-            ((height_px + PIXELS_PER_ROW - 1) / PIXELS_PER_ROW) as u16
-        } else {
-            0
-        };
+/// Encoding the application requested for mouse reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MouseEncoding {
+    /// Legacy `CSI M Cb Cx Cy` bytes
+    #[default]
+    Default,
+    /// UTF-8 encoded coordinates (`CSI ? 1005 h`)
+    Utf8,
+    /// SGR `CSI < ... M/m` reports (`CSI ? 1006 h`)
+    Sgr,
+    /// urxvt decimal reports (`CSI ? 1015 h`)
+    Urxvt,
+    /// SGR reports with pixel coordinates (`CSI ? 1016 h`)
+    SgrPixels,
+}
 
-        (cols, rows)
-    }
+/// The DEC private and ANSI modes set by the application.
+///
+/// Well-known modes are exposed as typed fields. Every mode the application
+/// has set or reset, recognized or not, can also be looked up by number with
+/// [`dec_mode`](Self::dec_mode) and [`ansi_mode`](Self::ansi_mode).
+///
+/// # Example
+///
+/// ```rust
+/// use ratatui_testlib::{MouseEncoding, MouseTracking, ScreenState};
+///
+/// let mut screen = ScreenState::new(80, 24);
+/// screen.feed(b"\x1b[?1003h\x1b[?1006h\x1b[?2004h");
+///
+/// let modes = screen.modes();
+/// assert_eq!(modes.mouse_tracking, MouseTracking::AnyEvent);
+/// assert_eq!(modes.mouse_encoding, MouseEncoding::Sgr);
+/// assert!(modes.bracketed_paste);
+/// assert_eq!(modes.dec_mode(2004), Some(true));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalModes {
+    /// DECCKM - cursor keys send application sequences (`CSI ? 1 h`)
+    pub application_cursor_keys: bool,
+    /// DECKPAM/DECKPNM - keypad sends application sequences (`ESC =` / `ESC >`)
+    pub application_keypad: bool,
+    /// DECAWM - autowrap at the right margin (`CSI ? 7 h`, on by default)
+    pub autowrap: bool,
+    /// DECTCEM - cursor visible (`CSI ? 25 h`, on by default)
+    pub cursor_visible: bool,
+    /// Mouse tracking (`CSI ? 9/1000/1002/1003 h`)
+    pub mouse_tracking: MouseTracking,
+    /// Mouse report encoding (`CSI ? 1005/1006/1015/1016 h`)
+    pub mouse_encoding: MouseEncoding,
+    /// Focus in/out reports (`CSI ? 1004 h`)
+    pub focus_events: bool,
+    /// Bracketed paste (`CSI ? 2004 h`)
+    pub bracketed_paste: bool,
+    /// Synchronized output (`CSI ? 2026 h`)
+    pub synchronized_output: bool,
+    /// IRM - printed characters shift the rest of the line right (`CSI 4 h`)
+    pub insert_mode: bool,
+    /// LNM - line feed also returns the carriage (`CSI 20 h`)
+    pub linefeed_newline: bool,
+    dec: BTreeMap<u16, bool>,
+    ansi: BTreeMap<u16, bool>,
+}
 
-    /// Parse Kitty graphics control data to extract dimensions.
-    ///
-    /// Kitty graphics protocol uses key-value pairs like:
-    /// - `w=<width>` - width in pixels
-    /// - `h=<height>` - height in pixels
-    /// - `c=<cols>` - width in terminal cells
-    /// - `r=<rows>` - height in terminal cells
-    ///
-    /// # Arguments
-    ///
-    /// * `data` - Raw Kitty graphics control data
-    ///
-    /// # Returns
-    ///
-    /// `Some((width, height))` in pixels if dimensions are found, `None` otherwise.
-    fn parse_kitty_dimensions(&self, data: &[u8]) -> Option<(u32, u32)> {
-        let data_str = std::str::from_utf8(data).ok()?;
+impl Default for TerminalModes {
+    fn default() -> Self {
+        Self {
+            application_cursor_keys: false,
+            application_keypad: false,
+            autowrap: true,
+            cursor_visible: true,
+            mouse_tracking: MouseTracking::None,
+            mouse_encoding: MouseEncoding::Default,
+            focus_events: false,
+            bracketed_paste: false,
+            synchronized_output: false,
+            insert_mode: false,
+            linefeed_newline: false,
+            dec: BTreeMap::new(),
+            ansi: BTreeMap::new(),
+        }
+    }
+}
 
-        let mut width = None;
-        let mut height = None;
+impl TerminalModes {
+    /// Returns the last state the application set for DEC private mode
+    /// `mode` (`CSI ? mode h/l`), or `None` if it never set or reset it.
+    pub fn dec_mode(&self, mode: u16) -> Option<bool> {
+        self.dec.get(&mode).copied()
+    }
 
-        // Parse key-value pairs separated by commas or semicolons
-        for part in data_str.split(|c| c == ',' || c == ';') {
-            if let Some((key, value)) = part.split_once('=') {
-                match key.trim() {
-                    "w" => width = value.trim().parse::<u32>().ok(),
-                    "h" => height = value.trim().parse::<u32>().ok(),
-                    _ => {}
-                }
-            }
-        }
+    /// Returns the last state the application set for ANSI mode `mode`
+    /// (`CSI mode h/l`), or `None` if it never set or reset it.
+    pub fn ansi_mode(&self, mode: u16) -> Option<bool> {
+        self.ansi.get(&mode).copied()
+    }
 
-        match (width, height) {
-            (Some(w), Some(h)) if w > 0 && h > 0 => Some((w, h)),
-            _ => None,
-        }
+    /// Returns true if any mouse tracking mode is enabled.
+    pub fn mouse_enabled(&self) -> bool {
+        self.mouse_tracking != MouseTracking::None
     }
+}
 
-    /// Parse iTerm2 inline image parameters to extract dimensions.
-    ///
-    /// iTerm2 uses parameters like:
-    /// - `width=<n>` or `width=<n>px` - width in pixels
-    /// - `height=<n>` or `height=<n>px` - height in pixels
-    /// - `width=auto` or `height=auto` - automatic sizing
-    ///
-    /// # Arguments
+/// A query the application sent that expects a reply from the terminal.
+///
+/// Queries are collected while output is fed to [`ScreenState`] and taken with
+/// [`ScreenState::take_queries`]. State the reply depends on, such as the
+/// cursor position, is captured when the query is parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TerminalQuery {
+    /// DA1 - Primary Device Attributes (`CSI c`)
+    PrimaryDeviceAttributes,
+    /// DA2 - Secondary Device Attributes (`CSI > c`)
+    SecondaryDeviceAttributes,
+    /// DSR - Device Status Report (`CSI 5 n`)
+    DeviceStatus,
+    /// CPR - Cursor Position Report (`CSI 6 n`, or `CSI ? 6 n` for DECXCPR)
+    CursorPosition {
+        /// 0-based cursor row
+        row: u16,
+        /// 0-based cursor column
+        col: u16,
+        /// True for the DEC form, whose reply also carries the page number
+        extended: bool,
+    },
+    /// XTVERSION - terminal name and version (`CSI > q`)
+    TerminalVersion,
+    /// DECRQM - Request Mode (`CSI ? Ps $ p` for DEC modes, `CSI Ps $ p` for ANSI)
+    Mode {
+        /// Mode number
+        mode: u16,
+        /// True for a DEC private mode
+        private: bool,
+        /// Whether the mode is set, or `None` if the emulator does not know it
+        state: Option<bool>,
+    },
+    /// XTWINOPS text area size in pixels (`CSI 14 t`)
+    TextAreaPixels {
+        /// Width in pixels
+        width: u32,
+        /// Height in pixels
+        height: u32,
+    },
+    /// XTWINOPS cell size in pixels (`CSI 16 t`)
+    CellPixels {
+        /// Cell width in pixels
+        width: u16,
+        /// Cell height in pixels
+        height: u16,
+    },
+    /// XTWINOPS text area size in characters (`CSI 18 t`)
+    TextAreaCells {
+        /// Number of rows
+        rows: u16,
+        /// Number of columns
+        cols: u16,
+    },
+    /// Default foreground color query (`OSC 10 ; ? ST`)
+    ForegroundColor {
+        /// Current foreground color
+        rgb: (u8, u8, u8),
+    },
+    /// Default background color query (`OSC 11 ; ? ST`)
+    BackgroundColor {
+        /// Current background color
+        rgb: (u8, u8, u8),
+    },
+    /// Palette color query (`OSC 4 ; index ; ? ST`)
+    PaletteColor {
+        /// Palette index
+        index: u8,
+        /// Current color of the entry
+        rgb: (u8, u8, u8),
+    },
+    /// Clipboard read (`OSC 52 ; Pc ; ? ST`)
+    Clipboard {
+        /// Selection targets as in [`ClipboardWrite::selection`]
+        selection: String,
+        /// Contents of the first target when the query was made
+        contents: String,
+    },
+    /// Response to a Kitty graphics command that carries an image id or
+    /// number, such as the `a=q` support probe (`ESC _ G a=q,i=31 ; ... ESC \`)
+    KittyGraphics {
+        /// Image id (`i=`, or the id assigned to an `I=` transmission)
+        image_id: u32,
+        /// Image number (`I=`), 0 if not given
+        image_number: u32,
+        /// Placement id (`p=`), 0 if not given
+        placement_id: u32,
+        /// `OK`, or an error such as `ENOENT:...` for a missing image
+        status: String,
+    },
+}
+
+impl TerminalQuery {
+    /// Returns the reply a terminal matching `profile` sends, or `None` if
+    /// that terminal does not answer this query.
     ///
-    /// * `data` - Raw iTerm2 parameter data (before the base64 payload)
+    /// # Example
     ///
-    /// # Returns
+    /// ```rust
+    /// use ratatui_testlib::{TerminalProfile, TerminalQuery};
     ///
-    /// `Some((width, height))` in cells if dimensions are found, `None` otherwise.
-    /// Note: iTerm2 dimensions are typically specified in cells, not pixels.
-    fn parse_iterm2_dimensions(&self, data: &[u8]) -> Option<(u32, u32)> {
-        let data_str = std::str::from_utf8(data).ok()?;
-
-        let mut width = None;
-        let mut height = None;
-
-        // Parse semicolon-separated key=value pairs
-        for part in data_str.split(';') {
-            if let Some((key, value)) = part.split_once('=') {
-                match key.trim() {
-                    "width" => {
-                        // Extract numeric value, ignoring units like "px"
-                        let val_str = value.trim().trim_end_matches("px");
-                        if val_str != "auto" {
-                            width = val_str.parse::<u32>().ok();
-                        }
-                    }
-                    "height" => {
-                        let val_str = value.trim().trim_end_matches("px");
-                        if val_str != "auto" {
-                            height = val_str.parse::<u32>().ok();
-                        }
-                    }
-                    _ => {}
+    /// let query = TerminalQuery::CursorPosition { row: 4, col: 9, extended: false };
+    /// assert_eq!(query.reply(TerminalProfile::Xterm256), Some(b"\x1b[5;10R".to_vec()));
+    /// ```
+    pub fn reply(&self, profile: TerminalProfile) -> Option<Vec<u8>> {
+        let reply = match *self {
+            Self::PrimaryDeviceAttributes => profile.primary_device_attributes(),
+            Self::SecondaryDeviceAttributes => profile.secondary_device_attributes().to_string(),
+            Self::DeviceStatus => "\x1b[0n".to_string(),
+            Self::CursorPosition { row, col, extended: false } => {
+                format!("\x1b[{};{}R", row + 1, col + 1)
+            }
+            Self::CursorPosition { row, col, extended: true } => {
+                format!("\x1b[?{};{};1R", row + 1, col + 1)
+            }
+            Self::TerminalVersion => format!("\x1bP>|{}\x1b\\", profile.version_string()?),
+            Self::TextAreaPixels { width, height } => format!("\x1b[4;{};{}t", height, width),
+            Self::CellPixels { width, height } => format!("\x1b[6;{};{}t", height, width),
+            Self::TextAreaCells { rows, cols } => format!("\x1b[8;{};{}t", rows, cols),
+            Self::ForegroundColor { rgb } if profile.answers_color_queries() => {
+                format!("\x1b]10;{}\x1b\\", rgb_spec(rgb))
+            }
+            Self::BackgroundColor { rgb } if profile.answers_color_queries() => {
+                format!("\x1b]11;{}\x1b\\", rgb_spec(rgb))
+            }
+            Self::PaletteColor { index, rgb } if profile.answers_color_queries() => {
+                format!("\x1b]4;{};{}\x1b\\", index, rgb_spec(rgb))
+            }
+            Self::ForegroundColor { .. }
+            | Self::BackgroundColor { .. }
+            | Self::PaletteColor { .. } => return None,
+            Self::Clipboard { ref selection, ref contents } => {
+                if !profile.allows_clipboard_read() {
+                    return None;
                 }
+                format!("\x1b]52;{};{}\x1b\\", selection, BASE64.encode(contents))
             }
-        }
+            Self::KittyGraphics { image_id, image_number, placement_id, ref status } => {
+                if !profile.capabilities().kitty_graphics {
+                    return None;
+                }
+                let mut keys = format!("i={}", image_id);
+                if image_number != 0 {
+                    keys.push_str(&format!(",I={}", image_number));
+                }
+                if placement_id != 0 {
+                    keys.push_str(&format!(",p={}", placement_id));
+                }
+                format!("\x1b_G{};{}\x1b\\", keys, status)
+            }
+            Self::Mode { mode, private, state } => {
+                // 0 = not recognized, 1 = set, 2 = reset
+                let value = match state {
+                    Some(_) if private && !profile_supports_dec_mode(profile, mode) => 0,
+                    Some(true) => 1,
+                    Some(false) => 2,
+                    None => 0,
+                };
+                let marker = if private { "?" } else { "" };
+                format!("\x1b[{}{};{}$y", marker, mode, value)
+            }
+        };
+        Some(reply.into_bytes())
+    }
+}
 
-        match (width, height) {
-            (Some(w), Some(h)) if w > 0 && h > 0 => Some((w, h)),
-            _ => None,
+/// Formats a color as in xterm's color query replies: `rgb:rrrr/gggg/bbbb`.
+fn rgb_spec((r, g, b): (u8, u8, u8)) -> String {
+    format!("rgb:{:02x}{:02x}/{:02x}{:02x}/{:02x}{:02x}", r, r, g, g, b, b)
+}
+
+/// Returns false for DEC modes of features the profile lacks, which that
+/// terminal reports as not recognized.
+fn profile_supports_dec_mode(profile: TerminalProfile, mode: u16) -> bool {
+    let caps = profile.capabilities();
+    match mode {
+        9 | 1000 | 1002 | 1003 | 1005 | 1006 | 1015 | 1016 => {
+            caps.mouse_protocol != MouseProtocol::None
         }
+        47 | 1047 | 1049 => caps.alternate_screen,
+        1004 => caps.focus_events,
+        2004 => caps.bracketed_paste,
+        2026 => caps.synchronized_output,
+        _ => true,
     }
 }
 
-impl VTActor for TerminalState {
-    fn print(&mut self, ch: char) {
-        self.put_char(ch);
-    }
+/// A bell or desktop notification raised by the application.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Notification {
+    /// BEL (`0x07`)
+    Bell,
+    /// Desktop notification: OSC 9 (`OSC 9 ; body ST`) or OSC 777
+    /// (`OSC 777 ; notify ; title ; body ST`)
+    Desktop {
+        /// Notification title; OSC 9 notifications have none
+        title: Option<String>,
+        /// Notification text
+        body: String,
+    },
+}
 
-    fn execute_c0_or_c1(&mut self, control: u8) {
-        match control {
-            b'\r' => {
-                // Carriage return
-                self.cursor_pos.1 = 0;
-            }
-            b'\n' => {
-                // Line feed
-                if self.cursor_pos.0 + 1 < self.height {
-                    self.cursor_pos.0 += 1;
-                }
+/// How [`ScreenState::resize`] fits the existing content to a new width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ResizeMode {
+    /// Rejoin soft-wrapped rows and wrap them again at the new width, as
+    /// modern emulators (kitty, WezTerm, Alacritty, ...) do
+    #[default]
+    Reflow,
+    /// Cut rows at the new width, or pad them, without rewrapping, as xterm
+    /// does
+    Truncate,
+}
+
+/// A terminal reset requested by the application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TerminalReset {
+    /// RIS - Reset to Initial State (`ESC c`)
+    Full,
+    /// DECSTR - Soft Terminal Reset (`CSI ! p`)
+    Soft,
+}
+
+/// A clipboard write made with OSC 52 (`OSC 52 ; Pc ; base64 ST`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClipboardWrite {
+    /// Selection targets written: `c` for the clipboard, `p` for the primary
+    /// selection, `q`, `s` or `0`-`7` for the others. An empty target is
+    /// recorded as `c`, as most terminals use the clipboard for it.
+    pub selection: String,
+    /// Decoded text; empty when the application cleared the selection
+    pub contents: String,
+}
+
+/// Returns the tab stops a terminal starts with: every eight columns.
+fn default_tab_stops(width: u16) -> BTreeSet<u16> {
+    (DEFAULT_TAB_WIDTH..width).step_by(DEFAULT_TAB_WIDTH as usize).collect()
+}
+
+/// Extracts the percent-decoded path from an OSC 7 `file://host/path` URI.
+/// Values without a scheme are taken as a plain path.
+fn cwd_from_uri(uri: &str) -> String {
+    let path = match uri.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |slash| &rest[slash..]),
+        None => uri,
+    };
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
             }
-            b'\t' => {
-                // Tab - advance to next tab stop (every 8 columns)
-                let next_tab = ((self.cursor_pos.1 / 8) + 1) * 8;
-                self.cursor_pos.1 = next_tab.min(self.width - 1);
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
             }
-            _ => {}
         }
     }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
 
-    fn dcs_hook(
-        &mut self,
-        mode: u8,
-        params: &[i64],
-        _intermediates: &[u8],
-        _ignored_excess_intermediates: bool,
-    ) {
-        // Sixel sequences are identified by mode byte 'q' (0x71)
-        if mode == b'q' {
-            self.in_sixel_mode = true;
-            self.current_sixel_data.clear();
-            self.current_sixel_params = params.to_vec();
-        }
+/// A parsed Kitty graphics command (`ESC _ G <keys> ; <payload> ESC \`).
+#[derive(Debug, Clone, Default)]
+struct KittyCommand {
+    /// Control data as sent, e.g. `a=T,i=1`.
+    control: Vec<u8>,
+    /// Control data keys, e.g. `a=T,i=1` as `{'a': "T", 'i': "1"}`.
+    keys: BTreeMap<char, String>,
+    /// Base64 payload as sent.
+    payload: Vec<u8>,
+}
+
+impl KittyCommand {
+    /// Parses the APC data following the `G`.
+    fn parse(data: &[u8]) -> Self {
+        let (control, payload) = match data.iter().position(|&b| b == b';') {
+            Some(split) => (&data[..split], data[split + 1..].to_vec()),
+            None => (data, Vec::new()),
+        };
+
+        let keys = String::from_utf8_lossy(control)
+            .split(',')
+            .filter_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                let mut key_chars = key.trim().chars();
+                match (key_chars.next(), key_chars.next()) {
+                    (Some(key), None) => Some((key, value.trim().to_string())),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        Self { control: control.to_vec(), keys, payload }
     }
 
-    fn dcs_put(&mut self, byte: u8) {
-        if self.in_sixel_mode {
-            self.current_sixel_data.push(byte);
+    /// Returns the command as APC data, with the payload of every chunk.
+    fn raw(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity(self.control.len() + self.payload.len() + 2);
+        raw.push(b'G');
+        raw.extend_from_slice(&self.control);
+        if !self.payload.is_empty() {
+            raw.push(b';');
+            raw.extend_from_slice(&self.payload);
         }
+        raw
     }
 
-    fn dcs_unhook(&mut self) {
-        if self.in_sixel_mode {
-            // Parse dimensions from raster attributes if present
-            let (width, height) = self
-                .parse_raster_attributes(&self.current_sixel_data)
-                .unwrap_or((0, 0));
+    /// Returns a single-character value such as `a=T`, or `default`.
+    fn char(&self, key: char, default: char) -> char {
+        self.keys
+            .get(&key)
+            .and_then(|value| value.chars().next())
+            .unwrap_or(default)
+    }
 
-            let region = SixelRegion {
-                start_row: self.cursor_pos.0,
-                start_col: self.cursor_pos.1,
-                width,
-                height,
-                data: self.current_sixel_data.clone(),
-            };
-            self.sixel_regions.push(region);
+    /// Returns an unsigned numeric value, 0 when missing or invalid.
+    fn num(&self, key: char) -> u32 {
+        self.keys.get(&key).and_then(|value| value.parse().ok()).unwrap_or(0)
+    }
 
-            self.in_sixel_mode = false;
-            self.current_sixel_data.clear();
-            self.current_sixel_params.clear();
-        }
+    /// Returns a signed numeric value, 0 when missing or invalid.
+    fn int(&self, key: char) -> i32 {
+        self.keys.get(&key).and_then(|value| value.parse().ok()).unwrap_or(0)
     }
+}
 
-    fn csi_dispatch(&mut self, params: &[CsiParam], _truncated: bool, byte: u8) {
-        match byte {
-            b'H' | b'f' => {
-                // CUP - Cursor Position ESC [ row ; col H
-                // CSI uses 1-based indexing, convert to 0-based
-                // Filter out P variants (separators) and collect only integers
-                let integers: Vec<i64> = params.iter().filter_map(|p| p.as_integer()).collect();
+/// A Kitty transmission split into chunks (`m=1`) that has not finished yet.
+#[derive(Debug, Clone)]
+struct KittyTransfer {
+    /// The first chunk, which carries the control keys; later chunks' payloads
+    /// are appended to it.
+    command: KittyCommand,
+    chunks: usize,
+}
 
-                let row = integers.get(0).copied().unwrap_or(1).saturating_sub(1) as u16;
-                let col = integers.get(1).copied().unwrap_or(1).saturating_sub(1) as u16;
+/// The character Kitty Unicode placeholders are drawn with.
+const KITTY_PLACEHOLDER: char = '\u{10EEEE}';
+
+/// Diacritics that encode row, column and id byte values 0, 1, 2, ... in
+/// Kitty Unicode placeholders (kitty's `rowcolumn-diacritics.txt`), sorted.
+const KITTY_DIACRITICS: [u32; 297] = [
+    0x0305, 0x030D, 0x030E, 0x0310, 0x0312, 0x033D, 0x033E, 0x033F, 0x0346, 0x034A, 0x034B,
+    0x034C, 0x0350, 0x0351, 0x0352, 0x0357, 0x035B, 0x0363, 0x0364, 0x0365, 0x0366, 0x0367,
+    0x0368, 0x0369, 0x036A, 0x036B, 0x036C, 0x036D, 0x036E, 0x036F, 0x0483, 0x0484, 0x0485,
+    0x0486, 0x0487, 0x0592, 0x0593, 0x0594, 0x0595, 0x0597, 0x0598, 0x0599, 0x059C, 0x059D,
+    0x059E, 0x059F, 0x05A0, 0x05A1, 0x05A8, 0x05A9, 0x05AB, 0x05AC, 0x05AF, 0x05C4, 0x0610,
+    0x0611, 0x0612, 0x0613, 0x0614, 0x0615, 0x0616, 0x0617, 0x0657, 0x0658, 0x0659, 0x065A,
+    0x065B, 0x065D, 0x065E, 0x06D6, 0x06D7, 0x06D8, 0x06D9, 0x06DA, 0x06DB, 0x06DC, 0x06DF,
+    0x06E0, 0x06E1, 0x06E2, 0x06E4, 0x06E7, 0x06E8, 0x06EB, 0x06EC, 0x0730, 0x0732, 0x0733,
+    0x0735, 0x0736, 0x073A, 0x073D, 0x073F, 0x0740, 0x0741, 0x0743, 0x0745, 0x0747, 0x0749,
+    0x074A, 0x07EB, 0x07EC, 0x07ED, 0x07EE, 0x07EF, 0x07F0, 0x07F1, 0x07F3, 0x0816, 0x0817,
+    0x0818, 0x0819, 0x081B, 0x081C, 0x081D, 0x081E, 0x081F, 0x0820, 0x0821, 0x0822, 0x0823,
+    0x0825, 0x0826, 0x0827, 0x0829, 0x082A, 0x082B, 0x082C, 0x082D, 0x0951, 0x0953, 0x0954,
+    0x0F82, 0x0F83, 0x0F86, 0x0F87, 0x135D, 0x135E, 0x135F, 0x17DD, 0x193A, 0x1A17, 0x1A75,
+    0x1A76, 0x1A77, 0x1A78, 0x1A79, 0x1A7A, 0x1A7B, 0x1A7C, 0x1B6B, 0x1B6D, 0x1B6E, 0x1B6F,
+    0x1B70, 0x1B71, 0x1B72, 0x1B73, 0x1CD0, 0x1CD1, 0x1CD2, 0x1CDA, 0x1CDB, 0x1CE0, 0x1DC0,
+    0x1DC1, 0x1DC3, 0x1DC4, 0x1DC5, 0x1DC6, 0x1DC7, 0x1DC8, 0x1DC9, 0x1DCB, 0x1DCC, 0x1DD1,
+    0x1DD2, 0x1DD3, 0x1DD4, 0x1DD5, 0x1DD6, 0x1DD7, 0x1DD8, 0x1DD9, 0x1DDA, 0x1DDB, 0x1DDC,
+    0x1DDD, 0x1DDE, 0x1DDF, 0x1DE0, 0x1DE1, 0x1DE2, 0x1DE3, 0x1DE4, 0x1DE5, 0x1DE6, 0x1DFE,
+    0x20D0, 0x20D1, 0x20D4, 0x20D5, 0x20D6, 0x20D7, 0x20DB, 0x20DC, 0x20E1, 0x20E7, 0x20E9,
+    0x20F0, 0x2CEF, 0x2CF0, 0x2CF1, 0x2DE0, 0x2DE1, 0x2DE2, 0x2DE3, 0x2DE4, 0x2DE5, 0x2DE6,
+    0x2DE7, 0x2DE8, 0x2DE9, 0x2DEA, 0x2DEB, 0x2DEC, 0x2DED, 0x2DEE, 0x2DEF, 0x2DF0, 0x2DF1,
+    0x2DF2, 0x2DF3, 0x2DF4, 0x2DF5, 0x2DF6, 0x2DF7, 0x2DF8, 0x2DF9, 0x2DFA, 0x2DFB, 0x2DFC,
+    0x2DFD, 0x2DFE, 0x2DFF, 0xA66F, 0xA67C, 0xA67D, 0xA6F0, 0xA6F1, 0xA8E0, 0xA8E1, 0xA8E2,
+    0xA8E3, 0xA8E4, 0xA8E5, 0xA8E6, 0xA8E7, 0xA8E8, 0xA8E9, 0xA8EA, 0xA8EB, 0xA8EC, 0xA8ED,
+    0xA8EE, 0xA8EF, 0xA8F0, 0xA8F1, 0xAAB0, 0xAAB2, 0xAAB3, 0xAAB7, 0xAAB8, 0xAABE, 0xAABF,
+    0xAAC1, 0xFE20, 0xFE21, 0xFE22, 0xFE23, 0xFE24, 0xFE25, 0xFE26, 0x10A0F, 0x10A38, 0x1D185,
+    0x1D186, 0x1D187, 0x1D188, 0x1D189, 0x1D1AA, 0x1D1AB, 0x1D1AC, 0x1D1AD, 0x1D242, 0x1D243,
+    0x1D244,
+];
+
+/// Returns the value a Kitty placeholder diacritic encodes.
+fn kitty_diacritic_value(ch: char) -> Option<u16> {
+    KITTY_DIACRITICS.binary_search(&u32::from(ch)).ok().map(|index| index as u16)
+}
 
-                self.move_cursor(row, col);
+/// Returns the 24-bit id a placeholder encodes in a color, or `None` for
+/// the default color.
+fn kitty_color_id(color: &Color) -> Option<u32> {
+    match *color {
+        Color::Default => None,
+        Color::Indexed(index) => Some(u32::from(index)),
+        Color::Rgb(r, g, b) => Some(u32::from(r) << 16 | u32::from(g) << 8 | u32::from(b)),
+    }
+}
+
+/// Resolves the Kitty placeholder cells of one screen row. Cells are read
+/// left to right because a cell with missing diacritics continues the
+/// placeholder on its left.
+fn kitty_placeholders_in_row(row: u16, cells: &[Cell]) -> Vec<KittyPlaceholder> {
+    let mut placeholders: Vec<KittyPlaceholder> = Vec::new();
+
+    for (col, cell) in cells.iter().enumerate() {
+        if cell.c != KITTY_PLACEHOLDER {
+            continue;
+        }
+        let Some(id_low) = kitty_color_id(&cell.fg) else {
+            continue;
+        };
+        let col = col as u16;
+        let placement_id = kitty_color_id(&cell.underline_color).unwrap_or(0);
+        let mut values = cell.combining.chars().filter_map(kitty_diacritic_value);
+        let (image_row, image_col, id_high) = (values.next(), values.next(), values.next());
+
+        let left = placeholders.last().filter(|left| {
+            left.col + 1 == col
+                && left.image_id & 0xFF_FFFF == id_low
+                && left.placement_id == placement_id
+        });
+        let (image_row, image_col, id_high) = match (image_row, image_col, id_high, left) {
+            (None, _, _, Some(left)) => (left.image_row, left.image_col + 1, left.image_id >> 24),
+            (Some(image_row), None, _, Some(left)) if left.image_row == image_row => {
+                (image_row, left.image_col + 1, left.image_id >> 24)
             }
-            b'A' => {
-                // CUU - Cursor Up
-                let n = params.iter().find_map(|p| p.as_integer()).unwrap_or(1) as u16;
-                self.cursor_pos.0 = self.cursor_pos.0.saturating_sub(n);
+            (Some(image_row), Some(image_col), None, Some(left))
+                if left.image_row == image_row && left.image_col + 1 == image_col =>
+            {
+                (image_row, image_col, left.image_id >> 24)
             }
-            b'B' => {
-                // CUD - Cursor Down
-                let n = params.iter().find_map(|p| p.as_integer()).unwrap_or(1) as u16;
-                self.cursor_pos.0 = (self.cursor_pos.0 + n).min(self.height - 1);
-            }
-            b'C' => {
-                // CUF - Cursor Forward
-                let n = params.iter().find_map(|p| p.as_integer()).unwrap_or(1) as u16;
-                self.cursor_pos.1 = (self.cursor_pos.1 + n).min(self.width - 1);
-            }
-            b'D' => {
-                // CUB - Cursor Back
-                let n = params.iter().find_map(|p| p.as_integer()).unwrap_or(1) as u16;
-                self.cursor_pos.1 = self.cursor_pos.1.saturating_sub(n);
-            }
-            b'm' => {
-                // SGR - Select Graphic Rendition (colors and attributes)
-                let integers: Vec<i64> = params.iter().filter_map(|p| p.as_integer()).collect();
-
-                // Handle empty params (reset)
-                if integers.is_empty() {
-                    self.current_fg = None;
-                    self.current_bg = None;
-                    self.current_bold = false;
-                    self.current_italic = false;
-                    self.current_underline = false;
-                    return;
-                }
+            (image_row, image_col, id_high, _) => (
+                image_row.unwrap_or(0),
+                image_col.unwrap_or(0),
+                u32::from(id_high.unwrap_or(0)) & 0xFF,
+            ),
+        };
 
-                let mut i = 0;
-                while i < integers.len() {
-                    match integers[i] {
-                        0 => {
-                            // Reset all attributes
-                            self.current_fg = None;
-                            self.current_bg = None;
-                            self.current_bold = false;
-                            self.current_italic = false;
-                            self.current_underline = false;
-                        }
-                        1 => self.current_bold = true,
-                        3 => self.current_italic = true,
-                        4 => self.current_underline = true,
-                        22 => self.current_bold = false,
-                        23 => self.current_italic = false,
-                        24 => self.current_underline = false,
-                        // Foreground colors (30-37: standard, 90-97: bright)
-                        30..=37 => self.current_fg = Some((integers[i] - 30) as u8),
-                        90..=97 => self.current_fg = Some((integers[i] - 90 + 8) as u8),
-                        39 => self.current_fg = None, // Default foreground
-                        // Background colors (40-47: standard, 100-107: bright)
-                        40..=47 => self.current_bg = Some((integers[i] - 40) as u8),
-                        100..=107 => self.current_bg = Some((integers[i] - 100 + 8) as u8),
-                        49 => self.current_bg = None, // Default background
-                        // 256-color mode: ESC[38;5;N or ESC[48;5;N
-                        38 | 48 => {
-                            if i + 2 < integers.len() && integers[i + 1] == 5 {
-                                let color = integers[i + 2] as u8;
-                                if integers[i] == 38 {
-                                    self.current_fg = Some(color);
-                                } else {
-                                    self.current_bg = Some(color);
-                                }
-                                i += 2; // Skip the '5' and color value
-                            }
-                        }
-                        _ => {} // Ignore unknown SGR codes
-                    }
-                    i += 1;
-                }
-            }
-            _ => {}
-        }
+        placeholders.push(KittyPlaceholder {
+            row,
+            col,
+            image_id: id_high << 24 | id_low,
+            placement_id,
+            image_row,
+            image_col,
+        });
     }
 
-    fn esc_dispatch(
-        &mut self,
-        _params: &[i64],
-        _intermediates: &[u8],
-        _ignored_excess_intermediates: bool,
-        byte: u8,
-    ) {
+    placeholders
+}
+
+/// A 94-character set that can be designated into G0-G3.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Charset {
+    /// US ASCII (`ESC ( B`)
+    #[default]
+    Ascii,
+    /// DEC Special Graphics (`ESC ( 0`): line drawing and symbols
+    DecSpecialGraphics,
+    /// United Kingdom (`ESC ( A`): `#` is the pound sign
+    Uk,
+}
+
+impl Charset {
+    /// Selects the set for the final byte of a designation sequence; sets
+    /// that are not emulated fall back to ASCII.
+    fn from_final_byte(byte: u8) -> Self {
         match byte {
-            b'D' => {
-                // IND - Index (move cursor down)
-                if self.cursor_pos.0 + 1 < self.height {
-                    self.cursor_pos.0 += 1;
-                }
-            }
-            b'E' => {
-                // NEL - Next Line
-                if self.cursor_pos.0 + 1 < self.height {
-                    self.cursor_pos.0 += 1;
-                }
-                self.cursor_pos.1 = 0;
-            }
-            _ => {}
+            b'0' => Charset::DecSpecialGraphics,
+            b'A' => Charset::Uk,
+            _ => Charset::Ascii,
         }
     }
 
-    fn osc_dispatch(&mut self, params: &[&[u8]]) {
-        // Handle OSC sequences
-        // iTerm2 inline images use OSC 1337;File=...
-        if params.is_empty() {
-            return;
+    /// Maps a printed ASCII character to the character it shows in this set.
+    fn translate(self, ch: char) -> char {
+        match (self, ch) {
+            (Charset::Uk, '#') => '£',
+            (Charset::DecSpecialGraphics, '_'..='~') => {
+                const GRAPHICS: [char; 32] = [
+                    ' ', '◆', '▒', '␉', '␌', '␍', '␊', '°', '±', '␤', '␋', '┘', '┐', '┌', '└',
+                    '┼', '⎺', '⎻', '─', '⎼', '⎽', '├', '┤', '┴', '┬', '│', '≤', '≥', 'π', '≠',
+                    '£', '·',
+                ];
+                GRAPHICS[ch as usize - '_' as usize]
+            }
+            _ => ch,
         }
+    }
+}
 
-        // Check if this is an iTerm2 inline image (OSC 1337;File=...)
-        if let Ok(first_param) = std::str::from_utf8(params[0]) {
-            if first_param.starts_with("1337;File=") || first_param == "1337" {
-                self.in_iterm2_mode = true;
-                self.current_iterm2_data.clear();
+/// Character set designations (G0-G3) and which of them is invoked.
+#[derive(Debug, Clone, Copy, Default)]
+struct CharsetState {
+    designations: [Charset; 4],
+    /// The set invoked into GL: G0 after SI, G1 after SO, G2/G3 after LS2/LS3
+    active: usize,
+    /// G2 or G3 selected for the next character only by SS2/SS3
+    single_shift: Option<usize>,
+}
 
-                // Collect all the data
-                for param in params {
-                    self.current_iterm2_data.extend_from_slice(param);
-                    self.current_iterm2_data.push(b';');
-                }
+impl CharsetState {
+    /// Translates a printed character through the active set, consuming a
+    /// pending single shift.
+    fn translate(&mut self, ch: char) -> char {
+        let set = self.single_shift.take().unwrap_or(self.active);
+        self.designations[set].translate(ch)
+    }
+}
 
-                // Parse dimensions from the parameters
-                let (width, height) = self
-                    .parse_iterm2_dimensions(&self.current_iterm2_data)
-                    .unwrap_or((0, 0));
-
-                let region = ITerm2Region {
-                    start_row: self.cursor_pos.0,
-                    start_col: self.cursor_pos.1,
-                    width,
-                    height,
-                    data: self.current_iterm2_data.clone(),
-                };
-                self.iterm2_regions.push(region);
+/// Cursor state saved by DECSC and restored by DECRC.
+#[derive(Debug, Clone, Default)]
+struct SavedCursor {
+    pos: (u16, u16),
+    pen: Cell,
+    charsets: CharsetState,
+}
 
-                self.in_iterm2_mode = false;
-                self.current_iterm2_data.clear();
-            }
+/// Terminal state tracking for vtparse parser.
+///
+/// Implements VTActor to handle escape sequences including DCS for Sixel,
+/// APC for Kitty graphics, and OSC for iTerm2 inline images.
+struct TerminalState {
+    cursor_pos: (u16, u16),
+    sixel_regions: Vec<SixelRegion>,
+    current_sixel_data: Vec<u8>,
+    current_sixel_params: Vec<i64>,
+    in_sixel_mode: bool,
+
+    // Kitty graphics protocol state
+    kitty_regions: Vec<KittyRegion>,
+    kitty_images: Vec<KittyImage>,
+    /// Placements on the active screen; those of the inactive screen buffer
+    /// are kept in `inactive_kitty_placements`.
+    kitty_placements: Vec<KittyPlacement>,
+    inactive_kitty_placements: Vec<KittyPlacement>,
+    kitty_transfer: Option<KittyTransfer>,
+
+    // iTerm2 inline images state
+    iterm2_regions: Vec<ITerm2Region>,
+
+    width: u16,
+    height: u16,
+    /// The active grid; while the alternate screen is shown this is the
+    /// alternate buffer and `inactive_rows` holds the primary one.
+    rows: Vec<Row>,
+    inactive_rows: Vec<Row>,
+    alternate_screen: bool,
+    /// Set after printing in the last column; the wrap happens on the next
+    /// printed character rather than immediately, as in xterm.
+    wrap_pending: bool,
+    /// Cursor saved by DECSC; each screen buffer has its own, and the
+    /// inactive buffer's is kept in `inactive_saved_cursor`.
+    saved_cursor: Option<SavedCursor>,
+    inactive_saved_cursor: Option<SavedCursor>,
+    cursor_style: CursorStyle,
+    /// G0-G3 character sets and shift state (SCS, SO/SI)
+    charsets: CharsetState,
+    /// Columns with a tab stop (HTS/TBC); shared by both screen buffers
+    tab_stops: BTreeSet<u16>,
+    /// DEC private and ANSI modes, including DECAWM and DECTCEM
+    modes: TerminalModes,
+    /// Queries waiting for a reply, oldest first
+    queries: VecDeque<TerminalQuery>,
+    /// Resets performed since they were last taken, oldest first
+    resets: VecDeque<TerminalReset>,
+    /// Window title and icon name (OSC 0/1/2)
+    title: String,
+    icon_name: String,
+    /// Titles saved by XTPUSHTITLE, most recent last
+    title_stack: Vec<String>,
+    /// Working directory reported with OSC 7 or OSC 1337 CurrentDir
+    cwd: Option<String>,
+    /// User variables set with OSC 1337 SetUserVar
+    user_vars: BTreeMap<String, String>,
+    /// Bells and desktop notifications, oldest first
+    notifications: Vec<Notification>,
+    /// Simulated selection buffers (OSC 52), keyed by target
+    clipboard: BTreeMap<char, String>,
+    /// OSC 52 writes, oldest first
+    clipboard_writes: Vec<ClipboardWrite>,
+    /// Colors reported to OSC 10/11/4 queries
+    palette: Palette,
+    /// Cell size in pixels, used for image sizes and XTWINOPS reports
+    cell_size: (u16, u16),
+    /// Scroll region (DECSTBM) as inclusive 0-based row bounds
+    scroll_top: u16,
+    scroll_bottom: u16,
+    /// Lines scrolled off the top of the screen, oldest first
+    scrollback: VecDeque<Row>,
+    scrollback_limit: usize,
+    /// Current colors and attributes (set by SGR); printed cells copy
+    /// everything but the text from it
+    pen: Cell,
+}
+
+impl TerminalState {
+    fn new(width: u16, height: u16) -> Self {
+        let rows = vec![Row::new(vec![Cell::default(); width as usize]); height as usize];
+
+        Self {
+            cursor_pos: (0, 0),
+            sixel_regions: Vec::new(),
+            current_sixel_data: Vec::new(),
+            current_sixel_params: Vec::new(),
+            in_sixel_mode: false,
+            kitty_regions: Vec::new(),
+            kitty_images: Vec::new(),
+            kitty_placements: Vec::new(),
+            inactive_kitty_placements: Vec::new(),
+            kitty_transfer: None,
+            iterm2_regions: Vec::new(),
+            width,
+            height,
+            inactive_rows: rows.clone(),
+            rows,
+            alternate_screen: false,
+            wrap_pending: false,
+            saved_cursor: None,
+            inactive_saved_cursor: None,
+            cursor_style: CursorStyle::Default,
+            charsets: CharsetState::default(),
+            tab_stops: default_tab_stops(width),
+            modes: TerminalModes::default(),
+            queries: VecDeque::new(),
+            resets: VecDeque::new(),
+            title: String::new(),
+            icon_name: String::new(),
+            title_stack: Vec::new(),
+            cwd: None,
+            user_vars: BTreeMap::new(),
+            notifications: Vec::new(),
+            clipboard: BTreeMap::new(),
+            clipboard_writes: Vec::new(),
+            palette: Palette::default(),
+            cell_size: DEFAULT_CELL_SIZE,
+            scroll_top: 0,
+            scroll_bottom: height.saturating_sub(1),
+            scrollback: VecDeque::new(),
+            scrollback_limit: DEFAULT_SCROLLBACK_LIMIT,
+            pen: Cell::default(),
         }
     }
 
-    fn apc_dispatch(&mut self, data: Vec<u8>) {
-        // Handle APC sequences
-        // Kitty graphics protocol uses APC with 'G' command: ESC _ G <data> ESC \
-        if data.is_empty() {
+    fn put_char(&mut self, ch: char) {
+        // ASCII always starts a new grapheme cluster
+        if !ch.is_ascii() && self.extend_cluster(ch) {
             return;
         }
 
-        // Check if this is a Kitty graphics command (starts with 'G')
-        if data[0] == b'G' {
-            self.in_kitty_mode = true;
-            self.current_kitty_data = data.clone();
+        let char_width = ch.width().unwrap_or(0) as u16;
+        if char_width == 0 {
+            // A zero-width character with no cluster to attach to
+            return;
+        }
+        let wide = char_width == 2 && self.width >= 2;
 
-            // Parse dimensions from the control data
-            let (width, height) = self
-                .parse_kitty_dimensions(&self.current_kitty_data)
-                .unwrap_or((0, 0));
+        if self.wrap_pending && self.modes.autowrap {
+            self.wrap_line();
+        }
+        self.wrap_pending = false;
+
+        // A wide character never straddles the right margin
+        if wide && self.cursor_pos.1 + 1 >= self.width {
+            if self.modes.autowrap {
+                self.rows[self.cursor_pos.0 as usize].padded = true;
+                self.wrap_line();
+            } else {
+                self.cursor_pos.1 = self.width - 2;
+            }
+        }
 
-            let region = KittyRegion {
-                start_row: self.cursor_pos.0,
-                start_col: self.cursor_pos.1,
-                width,
-                height,
-                data: self.current_kitty_data.clone(),
-            };
-            self.kitty_regions.push(region);
+        let (row, col) = self.cursor_pos;
+        let span = if wide { 2 } else { 1 };
+        if self.modes.insert_mode {
+            self.insert_chars(span);
+        }
+        self.clear_wide_overlap(row, col, col + span);
 
-            self.in_kitty_mode = false;
-            self.current_kitty_data.clear();
+        let cell = Cell {
+            c: ch,
+            width: span as u8,
+            ..self.pen.clone()
+        };
+        let line = &mut self.rows[row as usize];
+        if col + span == self.width {
+            line.padded = false;
+        }
+        let cells = &mut line.cells;
+        if wide {
+            cells[col as usize + 1] = cell.wide_spacer();
         }
+        cells[col as usize] = cell;
+        self.advance_cursor(col + span);
     }
-}
-
-/// Represents the current state of the terminal screen.
-///
-/// `ScreenState` is the core terminal emulator that tracks:
-/// - Text content at each cell position
-/// - Current cursor position
-/// - Sixel graphics regions (when rendered via DCS sequences)
-///
-/// It wraps a [`vtparse`] parser that processes VT100/ANSI escape sequences
-/// and maintains the screen state accordingly.
-///
-/// # Usage
-///
-/// The typical workflow is:
-/// 1. Create a `ScreenState` with desired dimensions
-/// 2. Feed PTY output bytes using [`feed()`](Self::feed)
-/// 3. Query the state using various accessor methods
-///
-/// # Example
-///
-/// ```rust
-/// use ratatui_testlib::ScreenState;
-///
-/// let mut screen = ScreenState::new(80, 24);
-///
-/// // Feed some terminal output
-/// screen.feed(b"\x1b[2J"); // Clear screen
-/// screen.feed(b"\x1b[5;10H"); // Move cursor to (5, 10)
-/// screen.feed(b"Hello!");
-///
-/// // Query the state
-/// assert_eq!(screen.cursor_position(), (4, 15)); // 0-indexed (row 4, col 15)
-/// assert_eq!(screen.text_at(4, 9), Some('H'));
-/// assert!(screen.contains("Hello"));
-/// ```
-pub struct ScreenState {
-    parser: VTParser,
-    state: TerminalState,
-    width: u16,
-    height: u16,
-}
 
-impl ScreenState {
-    /// Creates a new screen state with the specified dimensions.
-    ///
-    /// Initializes an empty screen filled with spaces, with the cursor at (0, 0).
-    ///
-    /// # Arguments
-    ///
-    /// * `width` - Screen width in columns
-    /// * `height` - Screen height in rows
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let screen = ScreenState::new(80, 24);
-    /// assert_eq!(screen.size(), (80, 24));
-    /// assert_eq!(screen.cursor_position(), (0, 0));
-    /// ```
-    pub fn new(width: u16, height: u16) -> Self {
-        let parser = VTParser::new();
-        let state = TerminalState::new(width, height);
+    /// Moves the cursor to `col` after printing. Past the right margin the
+    /// cursor stays in the last column until the next character arrives;
+    /// without autowrap that character overwrites the last column.
+    fn advance_cursor(&mut self, col: u16) {
+        if col < self.width {
+            self.cursor_pos.1 = col;
+        } else {
+            self.cursor_pos.1 = self.width - 1;
+            self.wrap_pending = self.modes.autowrap;
+        }
+    }
 
-        Self { parser, state, width, height }
+    /// Marks the cursor row as soft-wrapped and moves to the start of the next
+    /// line, scrolling if needed.
+    fn wrap_line(&mut self) {
+        self.rows[self.cursor_pos.0 as usize].wrapped = true;
+        self.linefeed();
+        self.cursor_pos.1 = 0;
     }
 
-    /// Feeds data from the PTY to the parser.
-    ///
-    /// This processes VT100/ANSI escape sequences and updates the screen state,
-    /// including:
-    /// - Text output
-    /// - Cursor movements
-    /// - Sixel graphics (tracked via DCS callbacks)
-    ///
-    /// This method can be called multiple times to incrementally feed data.
-    /// The parser maintains state across calls, so partial escape sequences
-    /// are handled correctly.
-    ///
-    /// # Arguments
-    ///
-    /// * `data` - Raw bytes from PTY output
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let mut screen = ScreenState::new(80, 24);
-    ///
-    /// // Feed data incrementally
-    /// screen.feed(b"Hello, ");
-    /// screen.feed(b"World!");
-    ///
-    /// assert!(screen.contains("Hello, World!"));
-    /// ```
-    pub fn feed(&mut self, data: &[u8]) {
-        self.parser.parse(data, &mut self.state);
+    /// Returns the position of the last printed cell before the cursor,
+    /// skipping the spacer of a wide character.
+    fn previous_cell(&self) -> Option<(usize, usize)> {
+        let (row, col) = (self.cursor_pos.0 as usize, self.cursor_pos.1 as usize);
+        let col = if self.wrap_pending { col } else { col.checked_sub(1)? };
+        if self.rows[row].cells[col].is_wide_continuation() {
+            Some((row, col.checked_sub(1)?))
+        } else {
+            Some((row, col))
+        }
     }
 
-    /// Returns the screen contents as a string.
-    ///
-    /// This includes all visible characters, preserving layout with newlines
-    /// between rows. Empty cells are represented as spaces.
-    ///
-    /// # Returns
-    ///
-    /// A string containing the entire screen contents, with rows separated by newlines.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let mut screen = ScreenState::new(10, 3);
-    /// screen.feed(b"Hello");
+    /// Appends `ch` to the grapheme cluster before the cursor if it continues
+    /// that cluster (combining marks, variation selectors, ZWJ sequences,
+    /// flag pairs). Returns false if `ch` starts a new cluster.
     ///
-    /// let contents = screen.contents();
-    /// // First line contains "Hello     " (padded to 10 chars)
-    /// // Second and third lines are all spaces
-    /// assert!(contents.contains("Hello"));
-    /// ```
-    pub fn contents(&self) -> String {
-        self.state
-            .cells
-            .iter()
-            .map(|row| row.iter().map(|cell| cell.c).collect::<String>())
-            .collect::<Vec<_>>()
-            .join("\n")
+    /// A cluster that becomes double-width, such as a text-style symbol
+    /// followed by U+FE0F, is widened if the cell after it is still free.
+    fn extend_cluster(&mut self, ch: char) -> bool {
+        let Some((row, col)) = self.previous_cell() else {
+            return false;
+        };
+        let cell = &mut self.rows[row].cells[col];
+        let mut cluster = cell.grapheme();
+        cluster.push(ch);
+        if cluster.graphemes(true).count() != 1 {
+            return false;
+        }
+        cell.combining.push(ch);
+
+        let can_widen = cell.width == 1
+            && !self.wrap_pending
+            && self.cursor_pos.1 as usize == col + 1
+            && col + 1 < self.width as usize;
+        if can_widen && cluster.width() >= 2 {
+            cell.width = 2;
+            let spacer = cell.wide_spacer();
+            self.rows[row].cells[col + 1] = spacer;
+            self.advance_cursor(col as u16 + 2);
+        }
+        true
     }
 
-    /// Returns the contents of a specific row.
-    ///
-    /// # Arguments
-    ///
-    /// * `row` - Row index (0-based)
-    ///
-    /// # Returns
-    ///
-    /// The row contents as a string, or empty string if row is out of bounds.
-    pub fn row_contents(&self, row: u16) -> String {
-        if row < self.height {
-            self.state.cells[row as usize]
-                .iter()
-                .map(|cell| cell.c)
-                .collect()
-        } else {
-            String::new()
+    /// Blanks the other half of any wide character that is partly covered by
+    /// the columns `start..end`, which are about to be overwritten or erased.
+    fn clear_wide_overlap(&mut self, row: u16, start: u16, end: u16) {
+        let cells = &mut self.rows[row as usize].cells;
+        let (start, end) = (start as usize, (end as usize).min(cells.len()));
+        if start >= end {
+            return;
+        }
+        if start > 0 && cells[start].is_wide_continuation() {
+            cells[start - 1].clear_text();
+        }
+        if end < cells.len() && cells[end - 1].is_wide() {
+            cells[end].clear_text();
         }
     }
 
-    /// Returns the character at a specific position.
-    ///
-    /// # Arguments
-    ///
-    /// * `row` - Row index (0-based)
-    /// * `col` - Column index (0-based)
-    ///
-    /// # Returns
-    ///
-    /// The character at the position, or None if out of bounds.
-    pub fn text_at(&self, row: u16, col: u16) -> Option<char> {
-        if row < self.height && col < self.width {
-            Some(self.state.cells[row as usize][col as usize].c)
-        } else {
-            None
+    fn move_cursor(&mut self, row: u16, col: u16) {
+        self.wrap_pending = false;
+        self.cursor_pos = (row.min(self.height - 1), col.min(self.width - 1));
+    }
+
+    /// Moves the cursor forward `n` tab stops, stopping at the right margin
+    /// (HT, CHT).
+    fn tab_forward(&mut self, n: u16) {
+        self.wrap_pending = false;
+        for _ in 0..n {
+            let col = self.cursor_pos.1;
+            self.cursor_pos.1 = match self.tab_stops.range(col + 1..).next() {
+                Some(&stop) => stop.min(self.width - 1),
+                None => self.width - 1,
+            };
         }
     }
 
-    /// Returns the complete cell (character + attributes) at a specific position.
-    ///
-    /// This method provides access to the full cell state including colors and
-    /// text attributes, enabling verification of ANSI escape sequence handling.
-    ///
-    /// # Arguments
-    ///
-    /// * `row` - Row index (0-based)
-    /// * `col` - Column index (0-based)
-    ///
-    /// # Returns
-    ///
-    /// A reference to the cell, or None if out of bounds.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let mut screen = ScreenState::new(80, 24);
-    /// screen.feed(b"\x1b[31mRed\x1b[0m");
-    ///
-    /// if let Some(cell) = screen.get_cell(0, 0) {
-    ///     assert_eq!(cell.c, 'R');
-    ///     assert_eq!(cell.fg, Some(1)); // Red = color 1
-    /// }
-    /// ```
-    pub fn get_cell(&self, row: u16, col: u16) -> Option<&Cell> {
-        if row < self.height && col < self.width {
-            Some(&self.state.cells[row as usize][col as usize])
-        } else {
-            None
+    /// Moves the cursor back `n` tab stops, stopping at the first column (CBT).
+    fn tab_backward(&mut self, n: u16) {
+        for _ in 0..n {
+            let col = self.cursor_pos.1;
+            self.cursor_pos.1 = self.tab_stops.range(..col).next_back().copied().unwrap_or(0);
         }
     }
 
-    /// Returns the current cursor position.
-    ///
-    /// # Returns
+    /// Returns a blank cell carrying the current background color.
     ///
-    /// A tuple of (row, col) with 0-based indexing.
-    pub fn cursor_position(&self) -> (u16, u16) {
-        self.state.cursor_pos
+    /// Erase and insert operations fill with this cell, matching the
+    /// background-color-erase behavior of xterm and most modern emulators.
+    fn blank_cell(&self) -> Cell {
+        Cell { bg: self.pen.bg, ..Cell::default() }
     }
 
-    /// Returns the screen dimensions.
-    ///
-    /// # Returns
-    ///
-    /// A tuple of (width, height) in columns and rows.
-    pub fn size(&self) -> (u16, u16) {
-        (self.width, self.height)
+    /// Returns an unwrapped row of [`blank_cell`](Self::blank_cell)s.
+    fn blank_row(&self) -> Row {
+        Row::new(vec![self.blank_cell(); self.width as usize])
     }
 
-    /// Returns the screen width in columns.
-    ///
-    /// This is a convenience method equivalent to `self.size().0`.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let screen = ScreenState::new(80, 24);
-    /// assert_eq!(screen.cols(), 80);
-    /// ```
-    pub fn cols(&self) -> u16 {
-        self.width
+    /// Blanks the columns `start..end` of `row`, clamped to the screen width.
+    ///
+    /// Erasing through the last column also ends any soft wrap on the row.
+    fn erase_cells(&mut self, row: u16, start: u16, end: u16) {
+        let end = end.min(self.width);
+        if row < self.height && start < end {
+            self.clear_wide_overlap(row, start, end);
+            let blank = self.blank_cell();
+            let line = &mut self.rows[row as usize];
+            line.cells[start as usize..end as usize].fill(blank);
+            if end == self.width {
+                line.wrapped = false;
+                line.padded = false;
+            }
+        }
     }
 
-    /// Returns the screen height in rows.
-    ///
-    /// This is a convenience method equivalent to `self.size().1`.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
+    /// ED - Erase in Display.
     ///
-    /// let screen = ScreenState::new(80, 24);
-    /// assert_eq!(screen.rows(), 24);
-    /// ```
-    pub fn rows(&self) -> u16 {
-        self.height
+    /// - 0: from the cursor to the end of the screen
+    /// - 1: from the start of the screen through the cursor
+    /// - 2: the entire screen
+    /// - 3: the scrollback buffer (xterm extension)
+    fn erase_in_display(&mut self, mode: i64) {
+        let (row, col) = self.cursor_pos;
+        match mode {
+            0 => {
+                self.erase_cells(row, col, self.width);
+                for r in row + 1..self.height {
+                    self.erase_cells(r, 0, self.width);
+                }
+            }
+            1 => {
+                for r in 0..row {
+                    self.erase_cells(r, 0, self.width);
+                }
+                self.erase_cells(row, 0, col + 1);
+            }
+            2 => self.clear_screen(),
+            3 => self.scrollback.clear(),
+            _ => {}
+        }
     }
 
-    /// Returns an iterator over all rows in the screen.
-    ///
-    /// Each item in the iterator is a reference to a row (a slice of cells).
+    /// EL - Erase in Line.
     ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let mut screen = ScreenState::new(80, 24);
-    /// screen.feed(b"Line 1\r\nLine 2");
-    ///
-    /// for (row_idx, row) in screen.iter_rows().enumerate() {
-    ///     println!("Row {}: {} cells", row_idx, row.len());
-    ///     for (col_idx, cell) in row.iter().enumerate() {
-    ///         if cell.c != ' ' {
-    ///             println!("  Cell ({}, {}): '{}'", row_idx, col_idx, cell.c);
-    ///         }
-    ///     }
-    /// }
-    /// ```
-    pub fn iter_rows(&self) -> impl Iterator<Item = &[Cell]> {
-        self.state.cells.iter().map(|row| row.as_slice())
+    /// - 0: from the cursor to the end of the line
+    /// - 1: from the start of the line through the cursor
+    /// - 2: the entire line
+    fn erase_in_line(&mut self, mode: i64) {
+        let (row, col) = self.cursor_pos;
+        match mode {
+            0 => self.erase_cells(row, col, self.width),
+            1 => self.erase_cells(row, 0, col + 1),
+            2 => self.erase_cells(row, 0, self.width),
+            _ => {}
+        }
     }
 
-    /// Returns an iterator over all cells in a specific row.
-    ///
-    /// Returns `None` if the row index is out of bounds.
-    ///
-    /// # Arguments
-    ///
-    /// * `row` - Row index (0-based)
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let mut screen = ScreenState::new(80, 24);
-    /// screen.feed(b"\x1b[31mRed\x1b[32mGreen\x1b[34mBlue");
+    /// ICH - inserts `n` blank cells at the cursor, shifting the rest of the
+    /// line right. Cells pushed past the right edge are lost.
+    fn insert_chars(&mut self, n: u16) {
+        let (row, col) = self.cursor_pos;
+        let blank = self.blank_cell();
+        let line = &mut self.rows[row as usize].cells[col as usize..];
+        let n = (n as usize).min(line.len());
+        line.rotate_right(n);
+        line[..n].fill(blank);
+    }
+
+    /// DCH - deletes `n` cells at the cursor, shifting the rest of the line
+    /// left and filling the vacated cells at the right edge with blanks.
+    fn delete_chars(&mut self, n: u16) {
+        let (row, col) = self.cursor_pos;
+        let blank = self.blank_cell();
+        let line = &mut self.rows[row as usize].cells[col as usize..];
+        let n = (n as usize).min(line.len());
+        line.rotate_left(n);
+        let len = line.len();
+        line[len - n..].fill(blank);
+    }
+
+    /// Returns true if the cursor row lies inside the scroll region.
+    fn cursor_in_scroll_region(&self) -> bool {
+        (self.scroll_top..=self.scroll_bottom).contains(&self.cursor_pos.0)
+    }
+
+    /// Shifts the rows `top..=bottom` up by `n`, filling the bottom with
+    /// blank lines. Returns the rows that were shifted out, top first.
+    fn shift_rows_up(&mut self, top: u16, bottom: u16, n: u16) -> Vec<Row> {
+        let (top, bottom) = (top as usize, bottom as usize + 1);
+        let n = (n as usize).min(bottom - top);
+        let blank_row = self.blank_row();
+
+        self.rows[top..bottom].rotate_left(n);
+        self.shift_kitty_placements(top, bottom, -(n as isize));
+        self.rows
+            .splice(bottom - n..bottom, std::iter::repeat(blank_row).take(n))
+            .collect()
+    }
+
+    /// Shifts the rows `top..=bottom` down by `n`, filling the top with
+    /// blank lines. Rows pushed past `bottom` are lost.
+    fn shift_rows_down(&mut self, top: u16, bottom: u16, n: u16) {
+        let (top, bottom) = (top as usize, bottom as usize + 1);
+        let n = (n as usize).min(bottom - top);
+        let blank_row = self.blank_row();
+
+        self.rows[top..bottom].rotate_right(n);
+        self.rows[top..top + n].fill(blank_row);
+        self.shift_kitty_placements(top, bottom, n as isize);
+    }
+
+    /// Moves the Kitty placements whose top row lies in the rows `top..bottom`
+    /// by `delta` rows along with the text, removing those moved out of that
+    /// range. Virtual placements are not on the grid and stay put.
+    fn shift_kitty_placements(&mut self, top: usize, bottom: usize, delta: isize) {
+        self.kitty_placements.retain_mut(|placement| {
+            let row = placement.row as usize;
+            if placement.virtual_placement || !(top..bottom).contains(&row) {
+                return true;
+            }
+            match row.checked_add_signed(delta) {
+                Some(row) if (top..bottom).contains(&row) => {
+                    placement.row = row as u16;
+                    true
+                }
+                _ => false,
+            }
+        });
+    }
+
+    /// SU - scrolls the scroll region up by `n` lines.
     ///
-    /// // Use the iterator immediately to avoid lifetime issues
-    /// let colored_cells: Vec<_> = screen
-    ///     .iter_row(0)
-    ///     .unwrap()
-    ///     .enumerate()
-    ///     .filter(|(_, cell)| cell.fg.is_some())
-    ///     .collect();
+    /// Lines leaving the top of the screen are appended to the scrollback
+    /// buffer; lines leaving a region that starts below the top row, or the
+    /// alternate screen, are lost.
+    fn scroll_up(&mut self, n: u16) {
+        let lines = self.shift_rows_up(self.scroll_top, self.scroll_bottom, n);
+        if self.scroll_top == 0 && !self.alternate_screen {
+            self.push_scrollback(lines);
+        }
+    }
+
+    /// SD - scrolls the scroll region down by `n` lines.
+    fn scroll_down(&mut self, n: u16) {
+        self.shift_rows_down(self.scroll_top, self.scroll_bottom, n);
+    }
+
+    /// Appends lines to the scrollback, dropping the oldest beyond the limit.
+    fn push_scrollback(&mut self, lines: Vec<Row>) {
+        self.scrollback.extend(lines);
+        let excess = self.scrollback.len().saturating_sub(self.scrollback_limit);
+        self.scrollback.drain(..excess);
+    }
+
+    /// LF / IND - moves the cursor down, scrolling the region when the cursor
+    /// is on its bottom margin.
+    fn linefeed(&mut self) {
+        self.wrap_pending = false;
+        if self.cursor_pos.0 == self.scroll_bottom {
+            self.scroll_up(1);
+        } else if self.cursor_pos.0 + 1 < self.height {
+            self.cursor_pos.0 += 1;
+        }
+    }
+
+    /// RI - moves the cursor up, scrolling the region down when the cursor is
+    /// on its top margin.
+    fn reverse_index(&mut self) {
+        self.wrap_pending = false;
+        if self.cursor_pos.0 == self.scroll_top {
+            self.scroll_down(1);
+        } else if self.cursor_pos.0 > 0 {
+            self.cursor_pos.0 -= 1;
+        }
+    }
+
+    /// DECSC - saves the cursor position and text attributes.
+    fn save_cursor(&mut self) {
+        self.saved_cursor = Some(SavedCursor {
+            pos: self.cursor_pos,
+            pen: self.pen.clone(),
+            charsets: self.charsets,
+        });
+    }
+
+    /// Resizes both screen buffers, keeping their contents.
     ///
-    /// assert!(colored_cells.len() >= 3, "Should have colored cells");
-    /// ```
-    pub fn iter_row(&self, row: u16) -> Option<impl Iterator<Item = &Cell>> {
-        if row < self.height {
-            Some(self.state.cells[row as usize].iter())
+    /// The primary buffer is fitted to the new width together with its
+    /// scrollback, following `mode`; the alternate buffer is always
+    /// truncated, since applications redraw it. Rows that no longer fit above
+    /// the cursor go to the scrollback. The scroll region and tab stops are
+    /// reset.
+    fn resize(&mut self, width: u16, height: u16, mode: ResizeMode) {
+        let (primary, alternate) = if self.alternate_screen {
+            (std::mem::take(&mut self.inactive_rows), std::mem::take(&mut self.rows))
         } else {
-            None
+            (std::mem::take(&mut self.rows), std::mem::take(&mut self.inactive_rows))
+        };
+        // While the alternate screen is shown, the primary cursor is the one
+        // saved on entering it (mode 1049)
+        let (primary_cursor, alternate_cursor) = if self.alternate_screen {
+            (self.inactive_saved_cursor.as_ref().map(|saved| saved.pos), Some(self.cursor_pos))
+        } else {
+            (Some(self.cursor_pos), None)
+        };
+
+        let mut lines: Vec<Row> = std::mem::take(&mut self.scrollback).into();
+        let cursor = primary_cursor.map(|(row, col)| (lines.len() + row as usize, col));
+        lines.extend(primary);
+        let (lines, cursor) = match mode {
+            ResizeMode::Reflow => reflow_rows(lines, width as usize, cursor),
+            ResizeMode::Truncate => {
+                let mut lines = lines;
+                lines.iter_mut().for_each(|row| row.truncate(width as usize));
+                (lines, cursor)
+            }
+        };
+        let (scrollback, primary, primary_cursor) = fit_rows(lines, width, height, cursor);
+
+        let mut alternate = alternate;
+        alternate.iter_mut().for_each(|row| row.truncate(width as usize));
+        let alternate_cursor = alternate_cursor.map(|(row, col)| (row as usize, col));
+        let (_, alternate, alternate_cursor) = fit_rows(alternate, width, height, alternate_cursor);
+
+        self.width = width;
+        self.height = height;
+        self.push_scrollback(scrollback);
+        let clamp = |(row, col): (u16, u16)| (row.min(height - 1), col.min(width - 1));
+        let saved_cursors = [&mut self.saved_cursor, &mut self.inactive_saved_cursor];
+        for saved in saved_cursors.into_iter().flatten() {
+            saved.pos = clamp(saved.pos);
+        }
+        if self.alternate_screen {
+            self.rows = alternate;
+            self.inactive_rows = primary;
+            self.cursor_pos = alternate_cursor.unwrap_or_default();
+            if let (Some(saved), Some(pos)) = (&mut self.inactive_saved_cursor, primary_cursor) {
+                saved.pos = pos;
+            }
+        } else {
+            self.rows = primary;
+            self.inactive_rows = alternate;
+            self.cursor_pos = primary_cursor.unwrap_or_default();
         }
+        self.wrap_pending = false;
+        self.scroll_top = 0;
+        self.scroll_bottom = height - 1;
+        self.tab_stops = default_tab_stops(width);
     }
 
-    /// Returns a structured snapshot of the entire screen grid.
-    ///
-    /// This method provides a complete copy of the screen state suitable for
-    /// deep comparison with other terminal emulators or for serialization.
-    ///
-    /// # Returns
-    ///
-    /// A `GridSnapshot` containing:
-    /// - Grid dimensions (width, height)
-    /// - Complete cell data (2D vector of cells)
-    /// - Current cursor position
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let mut screen = ScreenState::new(80, 24);
-    /// screen.feed(b"\x1b[31mHello");
-    ///
-    /// let snapshot = screen.snapshot();
-    /// assert_eq!(snapshot.width, 80);
-    /// assert_eq!(snapshot.height, 24);
-    /// assert_eq!(snapshot.cells[0][0].c, 'H');
-    /// assert_eq!(snapshot.cells[0][0].fg, Some(1)); // Red
-    /// assert_eq!(snapshot.cursor, (0, 5));
-    /// ```
-    pub fn snapshot(&self) -> GridSnapshot {
-        GridSnapshot {
-            width: self.width,
-            height: self.height,
-            cells: self.state.cells.clone(),
-            cursor: self.state.cursor_pos,
+    /// RIS - returns the terminal to its power-on state: both screen buffers
+    /// are cleared, and the cursor, modes, character sets, tab stops, scroll
+    /// region and graphics regions are reset.
+    ///
+    /// Scrollback, titles, the working directory, the clipboard, notifications
+    /// and pending queries are kept: they describe the session rather than the
+    /// display.
+    fn full_reset(&mut self) {
+        *self = TerminalState {
+            queries: std::mem::take(&mut self.queries),
+            resets: std::mem::take(&mut self.resets),
+            title: std::mem::take(&mut self.title),
+            icon_name: std::mem::take(&mut self.icon_name),
+            cwd: self.cwd.take(),
+            user_vars: std::mem::take(&mut self.user_vars),
+            notifications: std::mem::take(&mut self.notifications),
+            clipboard: std::mem::take(&mut self.clipboard),
+            clipboard_writes: std::mem::take(&mut self.clipboard_writes),
+            palette: std::mem::take(&mut self.palette),
+            cell_size: self.cell_size,
+            scrollback: std::mem::take(&mut self.scrollback),
+            scrollback_limit: self.scrollback_limit,
+            ..TerminalState::new(self.width, self.height)
+        };
+    }
+
+    /// DECSTR - resets modes, attributes, character sets, the scroll region
+    /// and the saved cursor as a VT510 does, leaving the screen contents and
+    /// cursor position alone.
+    fn soft_reset(&mut self) {
+        self.set_dec_mode(1, false);
+        self.set_dec_mode(7, true);
+        self.set_dec_mode(25, true);
+        self.set_ansi_mode(4, false);
+        self.modes.application_keypad = false;
+        self.scroll_top = 0;
+        self.scroll_bottom = self.height - 1;
+        self.charsets = CharsetState::default();
+        self.reset_attributes();
+        self.saved_cursor = None;
+    }
+
+    /// Records a reset for [`ScreenState::take_resets`].
+    fn push_reset(&mut self, reset: TerminalReset) {
+        if self.resets.len() == MAX_PENDING_RESETS {
+            self.resets.pop_front();
         }
+        self.resets.push_back(reset);
     }
 
-    /// Returns all Sixel graphics regions currently on screen.
-    ///
-    /// This method provides access to all Sixel graphics that have been rendered
-    /// via DCS (Device Control String) sequences. Each region includes position
-    /// and dimension information.
-    ///
-    /// This is essential for verifying Sixel positioning in tests, particularly
-    /// for ensuring that graphics appear within designated preview areas.
-    ///
-    /// # Returns
-    ///
-    /// A slice of [`SixelRegion`] containing all detected Sixel graphics.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let mut screen = ScreenState::new(80, 24);
-    /// // ... render some Sixel graphics ...
-    ///
-    /// let regions = screen.sixel_regions();
-    /// for (i, region) in regions.iter().enumerate() {
-    ///     println!(
-    ///         "Region {}: position ({}, {}), size {}x{}",
-    ///         i, region.start_row, region.start_col, region.width, region.height
-    ///     );
-    /// }
-    /// ```
-    pub fn sixel_regions(&self) -> &[SixelRegion] {
-        &self.state.sixel_regions
+    /// Records a bell or notification for [`ScreenState::notifications`].
+    fn push_notification(&mut self, notification: Notification) {
+        if self.notifications.len() == MAX_NOTIFICATIONS {
+            self.notifications.remove(0);
+        }
+        self.notifications.push(notification);
     }
 
-    /// Returns a mutable reference to the Sixel regions.
+    /// DECRC - restores the state saved by [`save_cursor`](Self::save_cursor),
+    /// or homes the cursor and resets attributes if nothing was saved.
+    fn restore_cursor(&mut self) {
+        let saved = self.saved_cursor.clone().unwrap_or_default();
+        self.move_cursor(saved.pos.0, saved.pos.1);
+        // The open hyperlink is not part of the saved state
+        self.pen = Cell { hyperlink: self.pen.hyperlink.take(), ..saved.pen };
+        self.charsets = saved.charsets;
+    }
+
+    /// Switches between the primary and alternate screen buffers.
     ///
-    /// This is primarily intended for testing purposes, allowing tests to
-    /// inject mock Sixel data for memory profiling and other tests.
+    /// The cursor position is shared between the two buffers, but each has
+    /// its own DECSC saved cursor.
+    fn set_alternate_screen(&mut self, enabled: bool) {
+        if self.alternate_screen != enabled {
+            std::mem::swap(&mut self.rows, &mut self.inactive_rows);
+            std::mem::swap(&mut self.saved_cursor, &mut self.inactive_saved_cursor);
+            std::mem::swap(&mut self.kitty_placements, &mut self.inactive_kitty_placements);
+            self.alternate_screen = enabled;
+        }
+    }
+
+    /// Clears the whole active grid with the current background, together
+    /// with the Kitty images placed on it.
+    fn clear_screen(&mut self) {
+        for r in 0..self.height {
+            self.erase_cells(r, 0, self.width);
+            self.rows[r as usize].marks.clear();
+        }
+        self.kitty_placements.retain(|placement| placement.virtual_placement);
+    }
+
+    /// Sets or resets a DEC private mode (`CSI ? Pm h` / `CSI ? Pm l`).
+    fn set_dec_mode(&mut self, mode: i64, enabled: bool) {
+        let Ok(mode) = u16::try_from(mode) else {
+            return;
+        };
+        self.modes.dec.insert(mode, enabled);
+
+        match mode {
+            1 => self.modes.application_cursor_keys = enabled,
+            7 => {
+                self.modes.autowrap = enabled;
+                self.wrap_pending = false;
+            }
+            25 => self.modes.cursor_visible = enabled,
+            // Enabling a tracking mode replaces the previous one; disabling
+            // any of them turns mouse reports off, as in xterm
+            9 | 1000 | 1002 | 1003 => {
+                self.modes.mouse_tracking = match (enabled, mode) {
+                    (false, _) => MouseTracking::None,
+                    (true, 9) => MouseTracking::X10,
+                    (true, 1000) => MouseTracking::Normal,
+                    (true, 1002) => MouseTracking::ButtonEvent,
+                    (true, _) => MouseTracking::AnyEvent,
+                };
+            }
+            1005 | 1006 | 1015 | 1016 => {
+                let encoding = match mode {
+                    1005 => MouseEncoding::Utf8,
+                    1006 => MouseEncoding::Sgr,
+                    1015 => MouseEncoding::Urxvt,
+                    _ => MouseEncoding::SgrPixels,
+                };
+                if enabled {
+                    self.modes.mouse_encoding = encoding;
+                } else if self.modes.mouse_encoding == encoding {
+                    self.modes.mouse_encoding = MouseEncoding::Default;
+                }
+            }
+            1004 => self.modes.focus_events = enabled,
+            2004 => self.modes.bracketed_paste = enabled,
+            2026 => self.modes.synchronized_output = enabled,
+            47 => self.set_alternate_screen(enabled),
+            1047 => {
+                // Like 47, but the alternate screen is cleared on the way out
+                if !enabled && self.alternate_screen {
+                    self.clear_screen();
+                }
+                self.set_alternate_screen(enabled);
+            }
+            1049 => {
+                // Save the cursor and switch to a cleared alternate screen;
+                // on the way out switch back and restore the cursor
+                if enabled {
+                    if !self.alternate_screen {
+                        self.save_cursor();
+                        self.set_alternate_screen(true);
+                        self.clear_screen();
+                    }
+                } else if self.alternate_screen {
+                    self.set_alternate_screen(false);
+                    self.restore_cursor();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns whether DEC private mode `mode` is set, or `None` if this
+    /// emulator does not implement it.
+    fn dec_mode_state(&self, mode: u16) -> Option<bool> {
+        let modes = &self.modes;
+        match mode {
+            1 => Some(modes.application_cursor_keys),
+            7 => Some(modes.autowrap),
+            25 => Some(modes.cursor_visible),
+            9 => Some(modes.mouse_tracking == MouseTracking::X10),
+            1000 => Some(modes.mouse_tracking == MouseTracking::Normal),
+            1002 => Some(modes.mouse_tracking == MouseTracking::ButtonEvent),
+            1003 => Some(modes.mouse_tracking == MouseTracking::AnyEvent),
+            1004 => Some(modes.focus_events),
+            1005 => Some(modes.mouse_encoding == MouseEncoding::Utf8),
+            1006 => Some(modes.mouse_encoding == MouseEncoding::Sgr),
+            1015 => Some(modes.mouse_encoding == MouseEncoding::Urxvt),
+            1016 => Some(modes.mouse_encoding == MouseEncoding::SgrPixels),
+            47 | 1047 | 1049 => Some(self.alternate_screen),
+            2004 => Some(modes.bracketed_paste),
+            2026 => Some(modes.synchronized_output),
+            _ => None,
+        }
+    }
+
+    /// Queues a query for the harness to answer.
+    fn push_query(&mut self, query: TerminalQuery) {
+        if self.queries.len() == MAX_PENDING_QUERIES {
+            self.queries.pop_front();
+        }
+        self.queries.push_back(query);
+    }
+
+    /// Queues the queries handled by `csi_dispatch`; returns true if the
+    /// sequence was one of them.
+    fn dispatch_query(&mut self, args: &CsiArgs, byte: u8) -> bool {
+        let query = match (args.private, args.intermediates.as_slice(), byte) {
+            (None, [], b'c') if args.get_or(0, 0) == 0 => TerminalQuery::PrimaryDeviceAttributes,
+            (Some(b'>'), [], b'c') if args.get_or(0, 0) == 0 => {
+                TerminalQuery::SecondaryDeviceAttributes
+            }
+            (None, [], b'n') if args.get(0) == Some(5) => TerminalQuery::DeviceStatus,
+            (None | Some(b'?'), [], b'n') if args.get(0) == Some(6) => {
+                TerminalQuery::CursorPosition {
+                    row: self.cursor_pos.0,
+                    col: self.cursor_pos.1,
+                    extended: args.private.is_some(),
+                }
+            }
+            (Some(b'>'), [], b'q') if args.get_or(0, 0) == 0 => TerminalQuery::TerminalVersion,
+            (None, [], b't') if matches!(args.get(0), Some(14 | 16 | 18)) => {
+                let (cell_width, cell_height) = self.cell_size;
+                match args.get(0) {
+                    Some(14) => TerminalQuery::TextAreaPixels {
+                        width: u32::from(self.width) * u32::from(cell_width),
+                        height: u32::from(self.height) * u32::from(cell_height),
+                    },
+                    Some(16) => {
+                        TerminalQuery::CellPixels { width: cell_width, height: cell_height }
+                    }
+                    _ => TerminalQuery::TextAreaCells { rows: self.height, cols: self.width },
+                }
+            }
+            (None | Some(b'?'), [b'$'], b'p') => {
+                let Some(mode) = args.get(0).and_then(|mode| u16::try_from(mode).ok()) else {
+                    return true;
+                };
+                let private = args.private.is_some();
+                let state = if private {
+                    self.dec_mode_state(mode)
+                } else {
+                    match mode {
+                        4 => Some(self.modes.insert_mode),
+                        20 => Some(self.modes.linefeed_newline),
+                        _ => None,
+                    }
+                };
+                TerminalQuery::Mode { mode, private, state }
+            }
+            _ => return false,
+        };
+        self.push_query(query);
+        true
+    }
+
+    /// XTPUSHTITLE/XTPOPTITLE (`CSI 22 ; Ps t` / `CSI 23 ; Ps t`). Only the
+    /// window title is saved; `Ps` = 1 (icon name only) is ignored.
+    fn window_op(&mut self, args: &CsiArgs) {
+        let target = args.get_or(1, 0);
+        if target == 1 {
+            return;
+        }
+        match args.get_or(0, 0) {
+            22 => {
+                if self.title_stack.len() == MAX_TITLE_STACK {
+                    self.title_stack.remove(0);
+                }
+                self.title_stack.push(self.title.clone());
+            }
+            23 => {
+                if let Some(title) = self.title_stack.pop() {
+                    self.title = title;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Handles the OSC sequences for titles, hyperlinks, the working
+    /// directory, notifications, color queries, the clipboard and shell
+    /// integration. `params` is the OSC split at `;`.
+    fn text_osc(&mut self, params: &[&[u8]]) {
+        // Everything after the command number, with its `;` separators restored
+        let text = |from: usize| -> String {
+            let parts: Vec<_> = params.get(from..).unwrap_or_default().to_vec();
+            String::from_utf8_lossy(&parts.join(&b';')).into_owned()
+        };
+
+        match params[0] {
+            b"0" => {
+                self.title = text(1);
+                self.icon_name = text(1);
+            }
+            b"1" => self.icon_name = text(1),
+            b"2" => self.title = text(1),
+            b"8" if params.len() >= 3 => {
+                // OSC 8 ; id=...:key=value ; URI - an empty URI closes the link
+                let uri = text(2);
+                self.pen.hyperlink = (!uri.is_empty()).then(|| {
+                    let id = params[1]
+                        .split(|&b| b == b':')
+                        .find_map(|param| param.strip_prefix(b"id="))
+                        .map(|id| String::from_utf8_lossy(id).into_owned());
+                    Arc::new(Hyperlink { id, uri })
+                });
+            }
+            b"7" => self.cwd = Some(cwd_from_uri(&text(1))),
+            // ConEmu reuses OSC 9 with a numeric subcommand (progress, cwd, ...)
+            b"9" if params.len() > 1 && !params[1].iter().all(u8::is_ascii_digit) => {
+                self.push_notification(Notification::Desktop { title: None, body: text(1) });
+            }
+            b"777" if params.get(1) == Some(&&b"notify"[..]) => {
+                let title = params.get(2).map(|t| String::from_utf8_lossy(t).into_owned());
+                self.push_notification(Notification::Desktop { title, body: text(3) });
+            }
+            b"4" => {
+                // OSC 4 ; index ; spec [; index ; spec ...]
+                for pair in params[1..].chunks(2) {
+                    let index = std::str::from_utf8(pair[0]).ok().and_then(|i| i.parse().ok());
+                    if let (Some(index), Some(b"?")) = (index, pair.get(1).copied()) {
+                        let rgb = self.palette.colors[index as usize];
+                        self.push_query(TerminalQuery::PaletteColor { index, rgb });
+                    }
+                }
+            }
+            b"10" | b"11" => {
+                // Each further parameter addresses the next color (OSC 10 ; ? ; ? asks
+                // for both foreground and background)
+                let first = if params[0] == b"10" { 0 } else { 1 };
+                let Palette { foreground, background, .. } = self.palette;
+                for (slot, spec) in params[1..].iter().enumerate() {
+                    let query = match (first + slot, *spec) {
+                        (0, b"?") => TerminalQuery::ForegroundColor { rgb: foreground },
+                        (1, b"?") => TerminalQuery::BackgroundColor { rgb: background },
+                        _ => continue,
+                    };
+                    self.push_query(query);
+                }
+            }
+            b"52" if params.len() >= 3 => self.clipboard_osc(params[1], params[2]),
+            b"133" if params.len() >= 2 => self.prompt_osc(params),
+            b"1337" if params.len() >= 2 => self.iterm2_osc(params),
+            _ => {}
+        }
+    }
+
+    /// Handles OSC 133 shell integration: marks the cursor's row with the
+    /// prompt, command or output boundary. `D` may carry the command's exit
+    /// code as its first parameter.
+    fn prompt_osc(&mut self, params: &[&[u8]]) {
+        let marker_type = match params[1] {
+            b"A" => PromptMarkerType::PromptStart,
+            b"B" => PromptMarkerType::CommandStart,
+            b"C" => PromptMarkerType::CommandExecuted,
+            b"D" => PromptMarkerType::CommandFinished,
+            _ => return,
+        };
+        let exit_code = match marker_type {
+            PromptMarkerType::CommandFinished => params
+                .get(2)
+                .and_then(|code| std::str::from_utf8(code).ok())
+                .and_then(|code| code.parse().ok()),
+            _ => None,
+        };
+
+        let (row, col) = self.cursor_pos;
+        self.rows[row as usize].marks.push(PromptMark { col, marker_type, exit_code });
+    }
+
+    /// Handles OSC 52: stores base64 `data` in the `targets` selections, or
+    /// queues a read query when `data` is `?`. Data that is not valid base64
+    /// clears the selections, as in xterm.
+    fn clipboard_osc(&mut self, targets: &[u8], data: &[u8]) {
+        let mut selection: String = targets
+            .iter()
+            .map(|&b| b as char)
+            .filter(|c| CLIPBOARD_TARGETS.contains(*c))
+            .collect();
+        if selection.is_empty() {
+            selection.push('c');
+        }
+
+        if data == b"?" {
+            let first = selection.chars().next().unwrap_or('c');
+            let contents = self.clipboard.get(&first).cloned().unwrap_or_default();
+            self.push_query(TerminalQuery::Clipboard { selection, contents });
+            return;
+        }
+
+        let contents = BASE64
+            .decode(data)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_default();
+        for target in selection.chars() {
+            self.clipboard.insert(target, contents.clone());
+        }
+        if self.clipboard_writes.len() == MAX_CLIPBOARD_WRITES {
+            self.clipboard_writes.remove(0);
+        }
+        self.clipboard_writes.push(ClipboardWrite { selection, contents });
+    }
+
+    /// Sets or resets an ANSI mode (`CSI Pm h` / `CSI Pm l`).
+    fn set_ansi_mode(&mut self, mode: i64, enabled: bool) {
+        let Ok(mode) = u16::try_from(mode) else {
+            return;
+        };
+        self.modes.ansi.insert(mode, enabled);
+
+        match mode {
+            4 => self.modes.insert_mode = enabled,
+            20 => self.modes.linefeed_newline = enabled,
+            _ => {}
+        }
+    }
+
+    /// Resets all text attributes to their defaults (SGR 0).
+    fn reset_attributes(&mut self) {
+        // SGR 0 does not close an open hyperlink
+        self.pen = Cell { hyperlink: self.pen.hyperlink.take(), ..Cell::default() };
+    }
+
+    /// Sets the underline style, keeping the `underline` flag in step.
+    fn set_underline(&mut self, style: UnderlineStyle) {
+        self.pen.underline_style = style;
+        self.pen.underline = style != UnderlineStyle::None;
+    }
+
+    /// SGR - applies each parameter in turn; an empty parameter list resets.
+    fn select_graphic_rendition(&mut self, args: &CsiArgs) {
+        if args.params.is_empty() {
+            self.reset_attributes();
+            return;
+        }
+
+        let mut i = 0;
+        while i < args.params.len() {
+            let param = &args.params[i];
+            match param[0].unwrap_or(0) {
+                0 => self.reset_attributes(),
+                1 => self.pen.bold = true,
+                2 => self.pen.dim = true,
+                3 => self.pen.italic = true,
+                // 4:N selects the underline style
+                4 => {
+                    let style = match param.get(1).copied().flatten() {
+                        None | Some(1) => UnderlineStyle::Single,
+                        Some(0) => UnderlineStyle::None,
+                        Some(2) => UnderlineStyle::Double,
+                        Some(3) => UnderlineStyle::Curly,
+                        Some(4) => UnderlineStyle::Dotted,
+                        Some(5) => UnderlineStyle::Dashed,
+                        Some(_) => self.pen.underline_style,
+                    };
+                    self.set_underline(style);
+                }
+                5 | 6 => self.pen.blink = true,
+                7 => self.pen.reverse = true,
+                8 => self.pen.hidden = true,
+                9 => self.pen.strikethrough = true,
+                21 => self.set_underline(UnderlineStyle::Double),
+                22 => {
+                    self.pen.bold = false;
+                    self.pen.dim = false;
+                }
+                23 => self.pen.italic = false,
+                24 => self.set_underline(UnderlineStyle::None),
+                25 => self.pen.blink = false,
+                27 => self.pen.reverse = false,
+                28 => self.pen.hidden = false,
+                29 => self.pen.strikethrough = false,
+                53 => self.pen.overline = true,
+                55 => self.pen.overline = false,
+                // Foreground colors (30-37: standard, 90-97: bright)
+                code @ 30..=37 => self.pen.fg = Color::Indexed(code as u8 - 30),
+                code @ 90..=97 => self.pen.fg = Color::Indexed(code as u8 - 90 + 8),
+                39 => self.pen.fg = Color::Default,
+                // Background colors (40-47: standard, 100-107: bright)
+                code @ 40..=47 => self.pen.bg = Color::Indexed(code as u8 - 40),
+                code @ 100..=107 => self.pen.bg = Color::Indexed(code as u8 - 100 + 8),
+                49 => self.pen.bg = Color::Default,
+                59 => self.pen.underline_color = Color::Default,
+                // Extended colors: 38/48/58 followed by 5;N or 2;R;G;B, either
+                // as separate parameters or as colon sub-parameters
+                code @ (38 | 48 | 58) => {
+                    let color = if param.len() > 1 {
+                        extended_color(&param[1..], true).0
+                    } else {
+                        let rest: Vec<Option<i64>> =
+                            args.params[i + 1..].iter().map(|p| p[0]).collect();
+                        let (color, consumed) = extended_color(&rest, false);
+                        i += consumed;
+                        color
+                    };
+                    if let Some(color) = color {
+                        match code {
+                            38 => self.pen.fg = color,
+                            48 => self.pen.bg = color,
+                            _ => self.pen.underline_color = color,
+                        }
+                    }
+                }
+                _ => {} // Ignore unknown SGR codes
+            }
+            i += 1;
+        }
+    }
+
+    /// DECSTBM - sets the scroll region from 1-based `top` and `bottom` rows
+    /// and homes the cursor. Invalid regions are ignored.
+    fn set_scroll_region(&mut self, top: u16, bottom: u16) {
+        let top = top.max(1) - 1;
+        let bottom = bottom.min(self.height) - 1;
+        if top < bottom {
+            self.scroll_top = top;
+            self.scroll_bottom = bottom;
+            self.move_cursor(0, 0);
+        }
+    }
+
+    /// IL - inserts `n` blank lines at the cursor row, pushing the lines below
+    /// it down. Lines pushed past the bottom of the scroll region are lost.
+    fn insert_lines(&mut self, n: u16) {
+        if self.cursor_in_scroll_region() {
+            self.shift_rows_down(self.cursor_pos.0, self.scroll_bottom, n);
+            self.cursor_pos.1 = 0;
+        }
+    }
+
+    /// DL - deletes `n` lines starting at the cursor row, pulling the lines
+    /// below it up and filling the bottom of the scroll region with blank lines.
+    fn delete_lines(&mut self, n: u16) {
+        if self.cursor_in_scroll_region() {
+            self.shift_rows_up(self.cursor_pos.0, self.scroll_bottom, n);
+            self.cursor_pos.1 = 0;
+        }
+    }
+
+    /// Parse raster attributes from sixel data.
+    ///
+    /// Sixel raster attributes follow the format: "Pan;Pad;Ph;Pv
+    /// Where:
+    /// - Pan: Pixel aspect ratio numerator (typically 1)
+    /// - Pad: Pixel aspect ratio denominator (typically 1)
+    /// - Ph: Horizontal pixel dimension (width)
+    /// - Pv: Vertical pixel dimension (height)
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Raw sixel data bytes containing raster attributes
     ///
     /// # Returns
     ///
-    /// A mutable reference to the vector of Sixel regions.
+    /// `Some((width, height))` in pixels if raster attributes are found and valid,
+    /// `None` otherwise.
     ///
-    /// # Example
+    /// # Examples
     ///
-    /// ```rust
-    /// use ratatui_testlib::{screen::SixelRegion, ScreenState};
+    /// - "1;1;100;50" → Some((100, 50))
+    /// - "100;50" → Some((100, 50)) (missing aspect ratio parameters)
+    /// - "" → None (no raster attributes)
+    fn parse_raster_attributes(&self, data: &[u8]) -> Option<(u32, u32)> {
+        let data_str = std::str::from_utf8(data).ok()?;
+
+        // Find the raster attributes command starting with '"'
+        let raster_start = data_str.find('"')?;
+        let after_quote = &data_str[raster_start + 1..];
+
+        // Find where the raster attributes end (terminated by non-digit, non-semicolon)
+        let end_pos = after_quote
+            .find(|c: char| !c.is_ascii_digit() && c != ';')
+            .unwrap_or(after_quote.len());
+
+        let raster_part = &after_quote[..end_pos];
+
+        // Parse semicolon-separated numeric parameters
+        // Format: Pa;Pb;Ph;Pv where we need Ph (index 2) and Pv (index 3)
+        let parts: Vec<&str> = raster_part.split(';').filter(|s| !s.is_empty()).collect();
+
+        // Handle different parameter counts:
+        // - 4 params: Pan;Pad;Ph;Pv (full format)
+        // - 2 params: Ph;Pv (abbreviated format, aspect ratio omitted)
+        match parts.len() {
+            4 => {
+                // Full format: Pan;Pad;Ph;Pv
+                let width = parts[2].parse::<u32>().ok()?;
+                let height = parts[3].parse::<u32>().ok()?;
+                if width > 0 && height > 0 {
+                    Some((width, height))
+                } else {
+                    None
+                }
+            }
+            2 => {
+                // Abbreviated format: Ph;Pv
+                let width = parts[0].parse::<u32>().ok()?;
+                let height = parts[1].parse::<u32>().ok()?;
+                if width > 0 && height > 0 {
+                    Some((width, height))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts pixel dimensions to terminal cell dimensions, using the cell
+    /// size (8x6 pixels unless set with [`ScreenState::with_cell_size`]).
+    /// Fractional cells are rounded up.
     ///
-    /// let mut screen = ScreenState::new(80, 24);
+    /// # Examples
     ///
-    /// // Add a mock Sixel region for testing
-    /// screen.sixel_regions_mut().push(SixelRegion {
-    ///     start_row: 5,
-    ///     start_col: 10,
-    ///     width: 100,
-    ///     height: 50,
-    ///     data: vec![0u8; 1000],
-    /// });
+    /// With 8x6 pixel cells:
+    /// - (80, 60) pixels → (10, 10) cells
+    /// - (100, 50) pixels → (13, 9) cells (rounded up)
+    /// - (0, 0) pixels → (0, 0) cells
+    fn pixels_to_cells(&self, width_px: u32, height_px: u32) -> (u16, u16) {
+        let (cell_width, cell_height) = self.cell_pixels();
+        let cols = width_px.div_ceil(cell_width).min(u32::from(u16::MAX)) as u16;
+        let rows = height_px.div_ceil(cell_height).min(u32::from(u16::MAX)) as u16;
+        (cols, rows)
+    }
+
+    /// Returns the cell size in pixels as `(width, height)`.
+    fn cell_pixels(&self) -> (u32, u32) {
+        let (width, height) = self.cell_size;
+        (u32::from(width), u32::from(height))
+    }
+
+    /// Handles a Kitty graphics command (the APC data after `G`).
     ///
-    /// assert_eq!(screen.sixel_regions().len(), 1);
-    /// ```
-    pub fn sixel_regions_mut(&mut self) -> &mut Vec<SixelRegion> {
-        &mut self.state.sixel_regions
+    /// Chunked transmissions (`m=1`) are collected until the final chunk and
+    /// then run as one command, so an image uploaded in pieces is stored once.
+    fn kitty_command(&mut self, data: &[u8]) {
+        let command = KittyCommand::parse(data);
+
+        if let Some(mut transfer) = self.kitty_transfer.take() {
+            transfer.command.payload.extend_from_slice(&command.payload);
+            transfer.chunks += 1;
+            if command.num('m') == 1 {
+                self.kitty_transfer = Some(transfer);
+            } else {
+                self.run_kitty_command(&transfer.command, transfer.chunks);
+            }
+            return;
+        }
+
+        if command.num('m') == 1 {
+            self.kitty_transfer = Some(KittyTransfer { command, chunks: 1 });
+        } else {
+            self.run_kitty_command(&command, 1);
+        }
+    }
+
+    fn run_kitty_command(&mut self, command: &KittyCommand, chunks: usize) {
+        let (image_id, status) = match command.char('a', 't') {
+            // Queries are answered without storing anything. Pixel data is
+            // not validated, so every query succeeds.
+            'q' => (command.num('i'), "OK".to_string()),
+            't' => (self.store_kitty_image(command, chunks), "OK".to_string()),
+            'T' => {
+                let id = self.store_kitty_image(command, chunks);
+                self.place_kitty_image(id, command);
+                (id, "OK".to_string())
+            }
+            'p' => match self.kitty_image_id(command) {
+                Some(id) => {
+                    self.place_kitty_image(id, command);
+                    (id, "OK".to_string())
+                }
+                None => (
+                    command.num('i'),
+                    format!(
+                        "ENOENT:Put command refers to non-existent image with id: {} and \
+                         number: {}",
+                        command.num('i'),
+                        command.num('I')
+                    ),
+                ),
+            },
+            'd' => {
+                self.delete_kitty_placements(command);
+                return;
+            }
+            // Animation frames do not change the stored images
+            _ => return,
+        };
+
+        self.queue_kitty_response(command, image_id, status);
+    }
+
+    /// Queues the response to a Kitty command. Terminals only answer commands
+    /// that carry an image id or number; `q=1` suppresses `OK` responses and
+    /// `q=2` all of them.
+    fn queue_kitty_response(&mut self, command: &KittyCommand, image_id: u32, status: String) {
+        if command.num('i') == 0 && command.num('I') == 0 {
+            return;
+        }
+        match command.num('q') {
+            0 => {}
+            1 if status != "OK" => {}
+            _ => return,
+        }
+
+        self.push_query(TerminalQuery::KittyGraphics {
+            image_id,
+            image_number: command.num('I'),
+            placement_id: command.num('p'),
+            status,
+        });
+    }
+
+    /// Stores a transmitted image, replacing any image with the same id along
+    /// with its placements. Returns the image id.
+    fn store_kitty_image(&mut self, command: &KittyCommand, chunks: usize) -> u32 {
+        let number = command.num('I');
+        let id = match command.num('i') {
+            0 => {
+                let highest = self.kitty_images.iter().map(|image| image.id).max();
+                highest.unwrap_or(0).saturating_add(1)
+            }
+            id => id,
+        };
+
+        self.kitty_images.retain(|image| image.id != id);
+        self.kitty_placements.retain(|placement| placement.image_id != id);
+        self.kitty_images.push(KittyImage {
+            id,
+            number: (number != 0).then_some(number),
+            format: match command.num('f') {
+                0 => 32,
+                format => format,
+            },
+            width: command.num('s'),
+            height: command.num('v'),
+            medium: command.char('t', 'd'),
+            data: BASE64.decode(&command.payload).unwrap_or_default(),
+            chunks,
+        });
+
+        id
+    }
+
+    /// Resolves the image a command refers to by id (`i=`) or by number
+    /// (`I=`, the newest image with that number).
+    fn kitty_image_id(&self, command: &KittyCommand) -> Option<u32> {
+        match (command.num('i'), command.num('I')) {
+            (0, 0) => None,
+            (0, number) => self.kitty_image_by_number(number),
+            (id, _) => self.kitty_images.iter().any(|image| image.id == id).then_some(id),
+        }
+    }
+
+    /// Returns the id of the newest image with the given number.
+    fn kitty_image_by_number(&self, number: u32) -> Option<u32> {
+        self.kitty_images
+            .iter()
+            .rev()
+            .find(|image| image.number == Some(number))
+            .map(|image| image.id)
+    }
+
+    /// Places an image at the cursor. A placement with the same image and
+    /// non-zero placement id is replaced.
+    fn place_kitty_image(&mut self, image_id: u32, command: &KittyCommand) {
+        let Some(image) = self.kitty_images.iter().find(|image| image.id == image_id) else {
+            return;
+        };
+
+        // The displayed part of the image: the source rectangle if given
+        let source_width = match command.num('w') {
+            0 => image.width.saturating_sub(command.num('x')),
+            width => width,
+        };
+        let source_height = match command.num('h') {
+            0 => image.height.saturating_sub(command.num('y')),
+            height => height,
+        };
+        let (auto_cols, auto_rows) = self.pixels_to_cells(
+            source_width.saturating_add(command.num('X')),
+            source_height.saturating_add(command.num('Y')),
+        );
+        let cols = match command.num('c') {
+            0 => auto_cols,
+            cols => cols.min(u32::from(u16::MAX)) as u16,
+        };
+        let rows = match command.num('r') {
+            0 => auto_rows,
+            rows => rows.min(u32::from(u16::MAX)) as u16,
+        };
+
+        let placement_id = command.num('p');
+        if placement_id != 0 {
+            self.kitty_placements.retain(|placement| {
+                placement.image_id != image_id || placement.placement_id != placement_id
+            });
+        }
+
+        let virtual_placement = command.num('U') == 1;
+        let (row, col) = if virtual_placement { (0, 0) } else { self.cursor_pos };
+        self.kitty_placements.push(KittyPlacement {
+            image_id,
+            placement_id,
+            row,
+            col,
+            cols,
+            rows,
+            z_index: command.int('z'),
+            virtual_placement,
+        });
+        if virtual_placement {
+            return;
+        }
+
+        // Also recorded in `kitty_regions()`, whose sizes are in pixels
+        let (width, height) = if command.num('c') != 0 || command.num('r') != 0 {
+            let (cell_width, cell_height) = self.cell_pixels();
+            (u32::from(cols) * cell_width, u32::from(rows) * cell_height)
+        } else {
+            (source_width, source_height)
+        };
+        self.kitty_regions.push(KittyRegion {
+            start_row: row,
+            start_col: col,
+            width,
+            height,
+            data: command.raw(),
+        });
+    }
+
+    /// Handles `a=d`. Lowercase `d=` values remove placements only; uppercase
+    /// ones also free the images that are left without placements.
+    fn delete_kitty_placements(&mut self, command: &KittyCommand) {
+        let what = command.char('d', 'a');
+        let image_id = match what.to_ascii_lowercase() {
+            'i' => Some(command.num('i')),
+            'n' => self.kitty_image_by_number(command.num('I')),
+            _ => None,
+        };
+        let placement_id = command.num('p');
+        // `x=` and `y=` are 1-based cell coordinates, except for `d=r` where
+        // they are the image id range
+        let (x, y, z) = (command.num('x'), command.num('y'), command.int('z'));
+        let (cell_row, cell_col) = (
+            y.saturating_sub(1).min(u32::from(u16::MAX)) as u16,
+            x.saturating_sub(1).min(u32::from(u16::MAX)) as u16,
+        );
+        let cursor = self.cursor_pos;
+        let spans =
+            |start: u16, len: u16, at: u16| (start..start.saturating_add(len)).contains(&at);
+
+        // Virtual placements are not on screen, so only id-based targets reach them
+        let id_based = matches!(what.to_ascii_lowercase(), 'i' | 'n' | 'r');
+        let targets = |placement: &KittyPlacement| match what.to_ascii_lowercase() {
+            'a' => true,
+            'i' | 'n' => {
+                Some(placement.image_id) == image_id
+                    && (placement_id == 0 || placement.placement_id == placement_id)
+            }
+            'c' => placement.contains(cursor.0, cursor.1),
+            'p' => placement.contains(cell_row, cell_col),
+            'q' => placement.contains(cell_row, cell_col) && placement.z_index == z,
+            'x' => spans(placement.col, placement.cols, cell_col),
+            'y' => spans(placement.row, placement.rows, cell_row),
+            'z' => placement.z_index == z,
+            'r' => (x..=y).contains(&placement.image_id),
+            _ => false,
+        };
+        let deletes = |placement: &KittyPlacement| {
+            (id_based || !placement.virtual_placement) && targets(placement)
+        };
+
+        let mut affected = BTreeSet::new();
+        self.kitty_placements.retain(|placement| {
+            let delete = deletes(placement);
+            if delete {
+                affected.insert(placement.image_id);
+            }
+            !delete
+        });
+
+        if what.is_ascii_uppercase() {
+            let placements = &self.kitty_placements;
+            self.kitty_images.retain(|image| {
+                let targeted = affected.contains(&image.id)
+                    || Some(image.id) == image_id
+                    || (what == 'R' && (x..=y).contains(&image.id));
+                !targeted || placements.iter().any(|placement| placement.image_id == image.id)
+            });
+        }
+    }
+
+    /// Handles `OSC 1337` iTerm2 commands: inline files (`File=`), user
+    /// variables (`SetUserVar=`) and the working directory (`CurrentDir=`).
+    fn iterm2_osc(&mut self, params: &[&[u8]]) {
+        let body = params.join(&b';');
+        let Ok(text) = std::str::from_utf8(&body) else {
+            return;
+        };
+        let Some((command, value)) = text.strip_prefix("1337;").and_then(|c| c.split_once('='))
+        else {
+            return;
+        };
+
+        match command {
+            "File" => self.iterm2_file(&body, value),
+            "SetUserVar" => {
+                // SetUserVar=name=base64
+                if let Some((name, encoded)) = value.split_once('=') {
+                    if let Ok(decoded) = BASE64.decode(encoded) {
+                        let decoded = String::from_utf8_lossy(&decoded).into_owned();
+                        self.user_vars.insert(name.to_string(), decoded);
+                    }
+                }
+            }
+            "CurrentDir" => self.cwd = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    /// Records an inline image from `OSC 1337 ; File=args : base64`. Files
+    /// sent without `inline=1` are downloads and are not displayed.
+    fn iterm2_file(&mut self, body: &[u8], file: &str) {
+        let (args, payload) = file.split_once(':').unwrap_or((file, ""));
+        let mut inline = false;
+        let mut preserve_aspect_ratio = true;
+        let (mut width, mut height) = (ITerm2Size::Auto, ITerm2Size::Auto);
+        for (key, value) in iterm2_file_args(args) {
+            match key {
+                "inline" => inline = value == "1",
+                "preserveAspectRatio" => preserve_aspect_ratio = value != "0",
+                "width" => width = ITerm2Size::parse(value),
+                "height" => height = ITerm2Size::parse(value),
+                _ => {}
+            }
+        }
+        if !inline {
+            return;
+        }
+
+        let (cell_width, cell_height) = self.cell_pixels();
+        let width_px = width.to_pixels(cell_width, self.width);
+        let height_px = height.to_pixels(cell_height, self.height);
+        let image_size = if width_px.is_none() || height_px.is_none() {
+            BASE64.decode(payload).ok().and_then(|data| image_file_dimensions(&data))
+        } else {
+            None
+        };
+
+        let (width_px, height_px) = match (width_px, height_px, image_size) {
+            (Some(width), Some(height), _) => (width, height),
+            (None, None, Some(size)) => size,
+            (Some(width), None, Some((image_width, image_height))) => {
+                let height = if preserve_aspect_ratio && image_width > 0 {
+                    (u64::from(width) * u64::from(image_height) / u64::from(image_width)) as u32
+                } else {
+                    image_height
+                };
+                (width, height)
+            }
+            (None, Some(height), Some((image_width, image_height))) => {
+                let width = if preserve_aspect_ratio && image_height > 0 {
+                    (u64::from(height) * u64::from(image_width) / u64::from(image_height)) as u32
+                } else {
+                    image_width
+                };
+                (width, height)
+            }
+            (width, height, None) => (width.unwrap_or(0), height.unwrap_or(0)),
+        };
+        let (width, height) = self.pixels_to_cells(width_px, height_px);
+
+        self.iterm2_regions.push(ITerm2Region {
+            start_row: self.cursor_pos.0,
+            start_col: self.cursor_pos.1,
+            width: u32::from(width),
+            height: u32::from(height),
+            data: body.to_vec(),
+        });
+    }
+}
+
+impl VTActor for TerminalState {
+    fn print(&mut self, ch: char) {
+        let ch = self.charsets.translate(ch);
+        self.put_char(ch);
+    }
+
+    fn execute_c0_or_c1(&mut self, control: u8) {
+        match control {
+            b'\r' => {
+                // Carriage return
+                self.wrap_pending = false;
+                self.cursor_pos.1 = 0;
+            }
+            b'\n' | 0x0b | 0x0c => {
+                // Line feed (VT and FF are treated as LF)
+                self.linefeed();
+                if self.modes.linefeed_newline {
+                    self.cursor_pos.1 = 0;
+                }
+            }
+            0x07 => {
+                // BEL
+                self.push_notification(Notification::Bell);
+            }
+            0x0e => {
+                // SO - Shift Out: invoke G1
+                self.charsets.active = 1;
+            }
+            0x0f => {
+                // SI - Shift In: invoke G0
+                self.charsets.active = 0;
+            }
+            b'\t' => {
+                // HT - advance to the next tab stop
+                self.tab_forward(1);
+            }
+            _ => {}
+        }
+    }
+
+    fn dcs_hook(
+        &mut self,
+        mode: u8,
+        params: &[i64],
+        _intermediates: &[u8],
+        _ignored_excess_intermediates: bool,
+    ) {
+        // Sixel sequences are identified by mode byte 'q' (0x71)
+        if mode == b'q' {
+            self.in_sixel_mode = true;
+            self.current_sixel_data.clear();
+            self.current_sixel_params = params.to_vec();
+        }
+    }
+
+    fn dcs_put(&mut self, byte: u8) {
+        if self.in_sixel_mode {
+            self.current_sixel_data.push(byte);
+        }
+    }
+
+    fn dcs_unhook(&mut self) {
+        if self.in_sixel_mode {
+            // Parse dimensions from raster attributes if present
+            let (width, height) = self
+                .parse_raster_attributes(&self.current_sixel_data)
+                .unwrap_or((0, 0));
+
+            let region = SixelRegion {
+                start_row: self.cursor_pos.0,
+                start_col: self.cursor_pos.1,
+                width,
+                height,
+                data: self.current_sixel_data.clone(),
+                params: self.current_sixel_params.clone(),
+            };
+            self.sixel_regions.push(region);
+
+            self.in_sixel_mode = false;
+            self.current_sixel_data.clear();
+            self.current_sixel_params.clear();
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &[CsiParam], _truncated: bool, byte: u8) {
+        let args = CsiArgs::parse(params);
+
+        // SM/RM for DEC private modes: CSI ? Pm h / CSI ? Pm l
+        if args.private == Some(b'?') && args.intermediates.is_empty() && matches!(byte, b'h' | b'l')
+        {
+            for index in 0..args.params.len() {
+                if let Some(mode) = args.get(index) {
+                    self.set_dec_mode(mode, byte == b'h');
+                }
+            }
+            return;
+        }
+
+        // DECSCUSR - Set Cursor Style: CSI Ps SP q
+        if args.private.is_none() && args.intermediates == [b' '] && byte == b'q' {
+            self.cursor_style = match args.get_or(0, 0) {
+                0 => CursorStyle::Default,
+                1 => CursorStyle::BlinkingBlock,
+                2 => CursorStyle::SteadyBlock,
+                3 => CursorStyle::BlinkingUnderline,
+                4 => CursorStyle::SteadyUnderline,
+                5 => CursorStyle::BlinkingBar,
+                6 => CursorStyle::SteadyBar,
+                _ => self.cursor_style,
+            };
+            return;
+        }
+
+        // DECSTR - Soft Terminal Reset: CSI ! p
+        if args.private.is_none() && args.intermediates == [b'!'] && byte == b'p' {
+            self.soft_reset();
+            self.push_reset(TerminalReset::Soft);
+            return;
+        }
+
+        if self.dispatch_query(&args, byte) {
+            return;
+        }
+
+        // Other private-marker and intermediate-qualified sequences (DA3,
+        // XTMODKEYS, ...) share final bytes with the standard sequences below
+        // and must not be mistaken for them.
+        if args.private.is_some() || !args.intermediates.is_empty() {
+            return;
+        }
+
+        // Everything but SGR moves the cursor or edits around it, which
+        // cancels a pending wrap.
+        if byte != b'm' {
+            self.wrap_pending = false;
+        }
+
+        match byte {
+            b'H' | b'f' => {
+                // CUP - Cursor Position ESC [ row ; col H
+                // CSI uses 1-based indexing, convert to 0-based
+                let row = args.count(0) - 1;
+                let col = args.count(1) - 1;
+
+                self.move_cursor(row, col);
+            }
+            b'A' => {
+                // CUU - Cursor Up
+                let n = args.count(0);
+                self.cursor_pos.0 = self.cursor_pos.0.saturating_sub(n);
+            }
+            b'B' => {
+                // CUD - Cursor Down
+                let n = args.count(0);
+                self.cursor_pos.0 = self.cursor_pos.0.saturating_add(n).min(self.height - 1);
+            }
+            b'C' => {
+                // CUF - Cursor Forward
+                let n = args.count(0);
+                self.cursor_pos.1 = self.cursor_pos.1.saturating_add(n).min(self.width - 1);
+            }
+            b'D' => {
+                // CUB - Cursor Back
+                let n = args.count(0);
+                self.cursor_pos.1 = self.cursor_pos.1.saturating_sub(n);
+            }
+            b'J' => {
+                // ED - Erase in Display
+                self.erase_in_display(args.get_or(0, 0));
+            }
+            b'K' => {
+                // EL - Erase in Line
+                self.erase_in_line(args.get_or(0, 0));
+            }
+            b'X' => {
+                // ECH - Erase Character (cursor does not move)
+                let (row, col) = self.cursor_pos;
+                self.erase_cells(row, col, col.saturating_add(args.count(0)));
+            }
+            b'@' => {
+                // ICH - Insert Character
+                self.insert_chars(args.count(0));
+            }
+            b'P' => {
+                // DCH - Delete Character
+                self.delete_chars(args.count(0));
+            }
+            b'L' => {
+                // IL - Insert Line
+                self.insert_lines(args.count(0));
+            }
+            b'M' => {
+                // DL - Delete Line
+                self.delete_lines(args.count(0));
+            }
+            b'S' => {
+                // SU - Scroll Up
+                self.scroll_up(args.count(0));
+            }
+            // SD - Scroll Down (the five-parameter form is mouse tracking)
+            b'T' if args.params.len() <= 1 => self.scroll_down(args.count(0)),
+            b'r' => {
+                // DECSTBM - Set Top and Bottom Margins
+                let top = args.count(0);
+                let bottom = match args.get(1) {
+                    Some(n) if n > 0 => n.min(u16::MAX as i64) as u16,
+                    _ => self.height,
+                };
+                self.set_scroll_region(top, bottom);
+            }
+            b't' => {
+                // Window manipulation (XTWINOPS); size reports are queries, and
+                // only the title stack is tracked
+                self.window_op(&args);
+            }
+            b'I' => {
+                // CHT - Cursor Forward Tabulation
+                self.tab_forward(args.count(0));
+            }
+            b'Z' => {
+                // CBT - Cursor Backward Tabulation
+                self.tab_backward(args.count(0));
+            }
+            b'g' => {
+                // TBC - Tab Clear: 0 clears the stop at the cursor, 3 clears all
+                match args.get_or(0, 0) {
+                    0 => {
+                        self.tab_stops.remove(&self.cursor_pos.1);
+                    }
+                    3 => self.tab_stops.clear(),
+                    _ => {}
+                }
+            }
+            b'h' | b'l' => {
+                // SM/RM - Set/Reset Mode for ANSI modes
+                for index in 0..args.params.len() {
+                    if let Some(mode) = args.get(index) {
+                        self.set_ansi_mode(mode, byte == b'h');
+                    }
+                }
+            }
+            b's' => {
+                // SCOSC - Save Cursor (same as DECSC)
+                self.save_cursor();
+            }
+            b'u' => {
+                // SCORC - Restore Cursor (same as DECRC)
+                self.restore_cursor();
+            }
+            b'm' => {
+                // SGR - Select Graphic Rendition (colors and attributes)
+                self.select_graphic_rendition(&args);
+            }
+            _ => {}
+        }
+    }
+
+    fn esc_dispatch(
+        &mut self,
+        _params: &[i64],
+        intermediates: &[u8],
+        _ignored_excess_intermediates: bool,
+        byte: u8,
+    ) {
+        // SCS - Designate a 94-character set into G0-G3: ESC ( F, ESC ) F, ESC * F, ESC + F
+        if let [intermediate @ b'('..=b'+'] = intermediates {
+            let set = (intermediate - b'(') as usize;
+            self.charsets.designations[set] = Charset::from_final_byte(byte);
+            return;
+        }
+
+        // ESC # and the 96-character designations reuse the final bytes below
+        if !intermediates.is_empty() {
+            return;
+        }
+
+        match byte {
+            b'7' => {
+                // DECSC - Save Cursor
+                self.save_cursor();
+            }
+            b'8' => {
+                // DECRC - Restore Cursor
+                self.restore_cursor();
+            }
+            b'=' => {
+                // DECKPAM - Application Keypad
+                self.modes.application_keypad = true;
+            }
+            b'>' => {
+                // DECKPNM - Normal Keypad
+                self.modes.application_keypad = false;
+            }
+            b'D' => {
+                // IND - Index (move cursor down, scrolling at the bottom margin)
+                self.linefeed();
+            }
+            b'E' => {
+                // NEL - Next Line
+                self.linefeed();
+                self.cursor_pos.1 = 0;
+            }
+            b'M' => {
+                // RI - Reverse Index (move cursor up, scrolling at the top margin)
+                self.reverse_index();
+            }
+            b'c' => {
+                // RIS - Reset to Initial State
+                self.full_reset();
+                self.push_reset(TerminalReset::Full);
+            }
+            b'H' => {
+                // HTS - Horizontal Tab Set at the cursor column
+                self.tab_stops.insert(self.cursor_pos.1);
+            }
+            b'n' | b'o' => {
+                // LS2 / LS3 - Locking Shift: invoke G2 / G3
+                self.charsets.active = if byte == b'n' { 2 } else { 3 };
+            }
+            b'N' | b'O' => {
+                // SS2 / SS3 - Single Shift: G2 / G3 for the next character
+                self.charsets.single_shift = Some(if byte == b'N' { 2 } else { 3 });
+            }
+            _ => {}
+        }
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]]) {
+        if params.is_empty() {
+            return;
+        }
+
+        self.text_osc(params);
+    }
+
+    fn apc_dispatch(&mut self, data: Vec<u8>) {
+        // Handle APC sequences
+        // Kitty graphics protocol uses APC with 'G' command: ESC _ G <data> ESC \
+        if data.is_empty() {
+            return;
+        }
+
+        // Check if this is a Kitty graphics command (starts with 'G')
+        if data[0] == b'G' {
+            self.kitty_command(&data[1..]);
+        }
+    }
+}
+
+/// Renders a grid as text, one line per row.
+fn grid_contents(rows: &[Row]) -> String {
+    rows.iter().map(Row::text).collect::<Vec<_>>().join("\n")
+}
+
+/// Represents the current state of the terminal screen.
+///
+/// `ScreenState` is the core terminal emulator that tracks:
+/// - Text content at each cell position
+/// - Current cursor position
+/// - Sixel graphics regions (when rendered via DCS sequences)
+///
+/// It wraps a [`vtparse`] parser that processes VT100/ANSI escape sequences
+/// and maintains the screen state accordingly.
+///
+/// # Usage
+///
+/// The typical workflow is:
+/// 1. Create a `ScreenState` with desired dimensions
+/// 2. Feed PTY output bytes using [`feed()`](Self::feed)
+/// 3. Query the state using various accessor methods
+///
+/// # Example
+///
+/// ```rust
+/// use ratatui_testlib::ScreenState;
+///
+/// let mut screen = ScreenState::new(80, 24);
+///
+/// // Feed some terminal output
+/// screen.feed(b"\x1b[2J"); // Clear screen
+/// screen.feed(b"\x1b[5;10H"); // Move cursor to (5, 10)
+/// screen.feed(b"Hello!");
+///
+/// // Query the state
+/// assert_eq!(screen.cursor_position(), (4, 15)); // 0-indexed (row 4, col 15)
+/// assert_eq!(screen.text_at(4, 9).as_deref(), Some("H"));
+/// assert!(screen.contains("Hello"));
+/// ```
+pub struct ScreenState {
+    parser: VTParser,
+    state: TerminalState,
+    width: u16,
+    height: u16,
+    resize_mode: ResizeMode,
+}
+
+impl ScreenState {
+    /// Creates a new screen state with the specified dimensions.
+    ///
+    /// Initializes an empty screen filled with spaces, with the cursor at (0, 0).
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Screen width in columns
+    /// * `height` - Screen height in rows
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let screen = ScreenState::new(80, 24);
+    /// assert_eq!(screen.size(), (80, 24));
+    /// assert_eq!(screen.cursor_position(), (0, 0));
+    /// ```
+    pub fn new(width: u16, height: u16) -> Self {
+        let parser = VTParser::new();
+        let state = TerminalState::new(width, height);
+
+        Self { parser, state, width, height, resize_mode: ResizeMode::default() }
+    }
+
+    /// Sets the maximum number of lines kept in the scrollback buffer.
+    ///
+    /// Defaults to 1000 lines. A limit of 0 disables
+    /// scrollback entirely.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(10, 2).with_scrollback_limit(1);
+    /// screen.feed(b"one\r\ntwo\r\nthree\r\nfour");
+    ///
+    /// assert_eq!(screen.scrollback(), vec!["two       "]);
+    /// ```
+    pub fn with_scrollback_limit(mut self, limit: usize) -> Self {
+        self.state.scrollback_limit = limit;
+        self.state.push_scrollback(Vec::new());
+        self
+    }
+
+    /// Sets how [`resize`](Self::resize) fits existing content to a new
+    /// width. Defaults to [`ResizeMode::Reflow`].
+    pub fn with_resize_mode(mut self, mode: ResizeMode) -> Self {
+        self.resize_mode = mode;
+        self
+    }
+
+    /// Resizes the screen, keeping its contents.
+    ///
+    /// With [`ResizeMode::Reflow`] rows that were soft-wrapped by autowrap are
+    /// joined and wrapped again at the new width, including the scrollback,
+    /// as modern emulators do; with [`ResizeMode::Truncate`] rows are cut or
+    /// padded. The cursor stays on the same character. Rows that no longer
+    /// fit above the cursor move to the scrollback, and a taller screen
+    /// brings lines back from it. The alternate screen is always truncated.
+    ///
+    /// Like a real terminal, this does not redraw the application: feed the
+    /// output it produces after the resize as usual.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(10, 3);
+    /// screen.feed(b"$ echo hello world\r\n$ ");
+    ///
+    /// screen.resize(20, 3);
+    /// assert_eq!(screen.row_contents(0).trim_end(), "$ echo hello world");
+    /// assert_eq!(screen.row_contents(1).trim_end(), "$");
+    /// assert_eq!(screen.cursor_position(), (1, 2));
+    /// ```
+    pub fn resize(&mut self, width: u16, height: u16) {
+        let (width, height) = (width.max(1), height.max(1));
+        self.state.resize(width, height, self.resize_mode);
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Sets the size of a cell in pixels. Defaults to 8x6, the cell of a
+    /// VT340-style Sixel terminal.
+    ///
+    /// The cell size converts image sizes in pixels to cells (Sixel, Kitty
+    /// and iTerm2 regions, and `GraphicsRegion` bounds with the `sixel`
+    /// feature) and is reported to XTWINOPS queries (`CSI 14 t`, `CSI 16 t`).
+    /// Zero is treated as 1.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let screen = ScreenState::new(80, 24).with_cell_size(10, 20);
+    /// assert_eq!(screen.cell_size(), (10, 20));
+    /// assert_eq!(screen.pixels_to_cells(100, 50), (10, 3));
+    /// ```
+    pub fn with_cell_size(mut self, width_px: u16, height_px: u16) -> Self {
+        self.state.cell_size = (width_px.max(1), height_px.max(1));
+        self
+    }
+
+    /// Returns the cell size in pixels as `(width, height)`.
+    pub fn cell_size(&self) -> (u16, u16) {
+        self.state.cell_size
+    }
+
+    /// Converts a size in pixels to cells with the screen's cell size,
+    /// rounding partial cells up.
+    pub fn pixels_to_cells(&self, width_px: u32, height_px: u32) -> (u16, u16) {
+        self.state.pixels_to_cells(width_px, height_px)
+    }
+
+    /// Sets the colors reported to OSC 10, 11 and 4 queries and used by
+    /// [`resolve_rgb`](Self::resolve_rgb). Defaults to [`Palette::dark`].
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.state.palette = palette;
+        self
+    }
+
+    /// Feeds data from the PTY to the parser.
+    ///
+    /// This processes VT100/ANSI escape sequences and updates the screen state,
+    /// including:
+    /// - Text output
+    /// - Cursor movements
+    /// - Sixel graphics (tracked via DCS callbacks)
+    ///
+    /// This method can be called multiple times to incrementally feed data.
+    /// The parser maintains state across calls, so partial escape sequences
+    /// are handled correctly.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Raw bytes from PTY output
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    ///
+    /// // Feed data incrementally
+    /// screen.feed(b"Hello, ");
+    /// screen.feed(b"World!");
+    ///
+    /// assert!(screen.contains("Hello, World!"));
+    /// ```
+    pub fn feed(&mut self, data: &[u8]) {
+        self.parser.parse(data, &mut self.state);
+    }
+
+    /// Returns the screen contents as a string.
+    ///
+    /// This includes all visible characters, preserving layout with newlines
+    /// between rows. Empty cells are represented as spaces.
+    ///
+    /// # Returns
+    ///
+    /// A string containing the entire screen contents, with rows separated by newlines.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(10, 3);
+    /// screen.feed(b"Hello");
+    ///
+    /// let contents = screen.contents();
+    /// // First line contains "Hello     " (padded to 10 chars)
+    /// // Second and third lines are all spaces
+    /// assert!(contents.contains("Hello"));
+    /// ```
+    pub fn contents(&self) -> String {
+        grid_contents(&self.state.rows)
+    }
+
+    /// Returns true while the application has switched to the alternate
+    /// screen buffer (`CSI ? 1049 h`, `CSI ? 1047 h` or `CSI ? 47 h`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// assert!(!screen.is_alternate_screen());
+    ///
+    /// screen.feed(b"\x1b[?1049h");
+    /// assert!(screen.is_alternate_screen());
+    ///
+    /// screen.feed(b"\x1b[?1049l");
+    /// assert!(!screen.is_alternate_screen());
+    /// ```
+    pub fn is_alternate_screen(&self) -> bool {
+        self.state.alternate_screen
+    }
+
+    /// Returns the contents of the primary screen buffer.
+    ///
+    /// While the alternate screen is active, [`contents`](Self::contents)
+    /// shows what the application drew and this method shows the primary
+    /// buffer hidden behind it. Otherwise both return the same text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(10, 2);
+    /// screen.feed(b"$ my-app\r\n");
+    /// screen.feed(b"\x1b[?1049h\x1b[HApp UI");
+    ///
+    /// assert!(screen.contains("App UI"));
+    /// assert!(screen.primary_contents().contains("$ my-app"));
+    ///
+    /// // Leaving the alternate screen restores the shell output
+    /// screen.feed(b"\x1b[?1049l");
+    /// assert!(screen.contains("$ my-app"));
+    /// assert!(!screen.contains("App UI"));
+    /// ```
+    pub fn primary_contents(&self) -> String {
+        if self.state.alternate_screen {
+            grid_contents(&self.state.inactive_rows)
+        } else {
+            self.contents()
+        }
+    }
+
+    /// Returns the lines that have scrolled off the top of the screen.
+    ///
+    /// Lines are ordered oldest first and padded to the screen width, like
+    /// [`row_contents`](Self::row_contents). Only lines leaving a scroll region
+    /// that starts at the top row are kept; the buffer holds at most 1000
+    /// lines unless changed with
+    /// [`with_scrollback_limit`](Self::with_scrollback_limit), and is cleared
+    /// by `CSI 3 J`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(10, 2);
+    /// screen.feed(b"one\r\ntwo\r\nthree");
+    ///
+    /// assert_eq!(screen.scrollback(), vec!["one       "]);
+    /// assert_eq!(screen.row_contents(0), "two       ");
+    /// ```
+    pub fn scrollback(&self) -> Vec<String> {
+        self.state
+            .scrollback
+            .iter()
+            .map(Row::text)
+            .collect()
+    }
+
+    /// Returns the scrollback followed by the visible screen as a string.
+    ///
+    /// This is the same as [`contents`](Self::contents) with the lines from
+    /// [`scrollback`](Self::scrollback) prepended, so assertions can cover
+    /// output that is no longer visible.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(5, 2);
+    /// screen.feed(b"1\r\n2\r\n3\r\n4");
+    ///
+    /// assert!(!screen.contains("1"));
+    /// assert_eq!(screen.contents_with_scrollback(), "1    \n2    \n3    \n4    ");
+    /// ```
+    pub fn contents_with_scrollback(&self) -> String {
+        self.scrollback()
+            .into_iter()
+            .chain(std::iter::once(self.contents()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Returns the visible screen as logical lines, joining rows that were
+    /// soft-wrapped by autowrap back into the text that was printed.
+    ///
+    /// Trailing spaces are trimmed from each line. Rows broken by an explicit
+    /// line feed stay separate lines.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(6, 3);
+    /// screen.feed(b"hello world\r\nok");
+    ///
+    /// assert_eq!(screen.row_contents(0), "hello ");
+    /// assert!(screen.is_wrapped(0));
+    /// assert_eq!(screen.logical_lines(), vec!["hello world", "ok"]);
+    /// ```
+    pub fn logical_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut current = String::new();
+        for row in &self.state.rows {
+            current.extend(row.text_cells().iter().map(Cell::grapheme));
+            if !row.wrapped {
+                lines.push(current.trim_end().to_string());
+                current.clear();
+            }
+        }
+        if !current.is_empty() {
+            lines.push(current.trim_end().to_string());
+        }
+        lines
+    }
+
+    /// Returns true if autowrap continued `row` onto the next row.
+    ///
+    /// Out-of-bounds rows are reported as not wrapped.
+    pub fn is_wrapped(&self, row: u16) -> bool {
+        self.state
+            .rows
+            .get(row as usize)
+            .is_some_and(|row| row.wrapped)
+    }
+
+    /// Returns the contents of a specific row.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row index (0-based)
+    ///
+    /// # Returns
+    ///
+    /// The row contents as a string, or empty string if row is out of bounds.
+    /// Each grapheme cluster appears once, so a row holding wide characters
+    /// has fewer characters than the screen has columns.
+    pub fn row_contents(&self, row: u16) -> String {
+        if row < self.height {
+            self.state.rows[row as usize].text()
+        } else {
+            String::new()
+        }
+    }
+
+    /// Returns the grapheme cluster at a specific position.
+    ///
+    /// Combining marks and ZWJ sequences are returned together with their base
+    /// character. The spacer cell covered by a wide character returns an
+    /// empty string.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row index (0-based)
+    /// * `col` - Column index (0-based)
+    ///
+    /// # Returns
+    ///
+    /// The text at the position, or None if out of bounds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(10, 1);
+    /// screen.feed("日本".as_bytes());
+    ///
+    /// assert_eq!(screen.text_at(0, 0).as_deref(), Some("日"));
+    /// assert_eq!(screen.text_at(0, 1).as_deref(), Some(""));
+    /// assert_eq!(screen.text_at(0, 2).as_deref(), Some("本"));
+    /// ```
+    pub fn text_at(&self, row: u16, col: u16) -> Option<String> {
+        self.get_cell(row, col).map(Cell::grapheme)
+    }
+
+    /// Returns the text displayed in columns `start..end` of `row`.
+    ///
+    /// A spacer cell at `start` (the right half of a wide character) reads as
+    /// a space, so text never appears to begin in the middle of a character.
+    pub(crate) fn text_in_columns(&self, row: u16, start: u16, end: u16) -> String {
+        let Some(cells) = self.state.rows.get(row as usize).map(|row| &row.cells) else {
+            return String::new();
+        };
+        let end = (end as usize).min(cells.len());
+        let start = (start as usize).min(end);
+
+        let mut text = String::new();
+        for (offset, cell) in cells[start..end].iter().enumerate() {
+            if offset == 0 && cell.is_wide_continuation() {
+                text.push(' ');
+            } else {
+                text.push_str(&cell.grapheme());
+            }
+        }
+        text
+    }
+
+    /// Returns the OSC 8 hyperlinks on screen, one entry per run of cells on a
+    /// row, in reading order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(40, 5);
+    /// screen.feed(b"See \x1b]8;;https://ratatui.rs\x1b\\the docs\x1b]8;;\x1b\\.");
+    ///
+    /// let links = screen.hyperlinks();
+    /// assert_eq!(links.len(), 1);
+    /// assert_eq!(links[0].uri, "https://ratatui.rs");
+    /// assert_eq!(links[0].text, "the docs");
+    /// assert_eq!((links[0].row, links[0].start_col, links[0].end_col), (0, 4, 12));
+    /// ```
+    pub fn hyperlinks(&self) -> Vec<HyperlinkSpan> {
+        let mut spans = Vec::new();
+
+        for (row, cells) in self.iter_rows().enumerate() {
+            let mut start = 0;
+            while start < cells.len() {
+                let link = &cells[start].hyperlink;
+                let len = cells[start..]
+                    .iter()
+                    .take_while(|cell| cell.hyperlink == *link)
+                    .count();
+
+                if let Some(link) = link {
+                    let (row, start_col, end_col) =
+                        (row as u16, start as u16, (start + len) as u16);
+                    spans.push(HyperlinkSpan {
+                        row,
+                        start_col,
+                        end_col,
+                        id: link.id.clone(),
+                        uri: link.uri.clone(),
+                        text: self.text_in_columns(row, start_col, end_col),
+                    });
+                }
+                start += len;
+            }
+        }
+
+        spans
+    }
+
+    /// Returns the hyperlink of the cell at `row`, `col`, if any.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(40, 5);
+    /// screen.feed(b"\x1b]8;id=help;https://example.com/help\x07Help\x1b]8;;\x07");
+    ///
+    /// let link = screen.link_at(0, 2).unwrap();
+    /// assert_eq!(link.uri, "https://example.com/help");
+    /// assert_eq!(link.id.as_deref(), Some("help"));
+    /// assert!(screen.link_at(0, 4).is_none());
+    /// ```
+    pub fn link_at(&self, row: u16, col: u16) -> Option<&Hyperlink> {
+        self.get_cell(row, col)?.hyperlink.as_deref()
+    }
+
+    /// Returns the complete cell (character + attributes) at a specific position.
+    ///
+    /// This method provides access to the full cell state including colors and
+    /// text attributes, enabling verification of ANSI escape sequence handling.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row index (0-based)
+    /// * `col` - Column index (0-based)
+    ///
+    /// # Returns
+    ///
+    /// A reference to the cell, or None if out of bounds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::{Color, ScreenState};
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x1b[31mRed\x1b[0m");
+    ///
+    /// if let Some(cell) = screen.get_cell(0, 0) {
+    ///     assert_eq!(cell.c, 'R');
+    ///     assert_eq!(cell.fg, Color::Indexed(1)); // Red = color 1
+    /// }
+    /// ```
+    pub fn get_cell(&self, row: u16, col: u16) -> Option<&Cell> {
+        if row < self.height && col < self.width {
+            Some(&self.state.rows[row as usize].cells[col as usize])
+        } else {
+            None
+        }
+    }
+
+    /// Returns the RGB values the cell at (row, col) is displayed with.
+    ///
+    /// Default and indexed colors are looked up in the [`Palette`]; reverse
+    /// video swaps the two colors and hidden text takes the background color.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::{Palette, ResolvedColors, ScreenState};
+    ///
+    /// let mut screen = ScreenState::new(80, 24).with_palette(Palette::light());
+    /// screen.feed(b"\x1b[31mA\x1b[7mB");
+    ///
+    /// assert_eq!(
+    ///     screen.resolve_rgb(0, 0),
+    ///     Some(ResolvedColors { fg: (205, 0, 0), bg: (255, 255, 255) })
+    /// );
+    /// assert_eq!(screen.resolve_rgb(0, 1).unwrap().bg, (205, 0, 0));
+    /// ```
+    pub fn resolve_rgb(&self, row: u16, col: u16) -> Option<ResolvedColors> {
+        let cell = self.get_cell(row, col)?;
+        let palette = &self.state.palette;
+        let mut fg = palette.rgb(cell.fg, palette.foreground);
+        let mut bg = palette.rgb(cell.bg, palette.background);
+        if cell.reverse {
+            std::mem::swap(&mut fg, &mut bg);
+        }
+        if cell.hidden {
+            fg = bg;
+        }
+        Some(ResolvedColors { fg, bg })
+    }
+
+    /// Returns the palette used for color queries and [`resolve_rgb`](Self::resolve_rgb).
+    pub fn palette(&self) -> &Palette {
+        &self.state.palette
+    }
+
+    /// Returns the current cursor position.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of (row, col) with 0-based indexing.
+    pub fn cursor_position(&self) -> (u16, u16) {
+        self.state.cursor_pos
+    }
+
+    /// Returns whether the cursor is visible.
+    ///
+    /// Applications hide the cursor with DECTCEM (`CSI ? 25 l`) and show it
+    /// again with `CSI ? 25 h`. The cursor is visible initially.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x1b[?25l");
+    /// assert!(!screen.cursor_visible());
+    /// ```
+    pub fn cursor_visible(&self) -> bool {
+        self.state.modes.cursor_visible
+    }
+
+    /// Returns the cursor style last selected with DECSCUSR (`CSI Ps SP q`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::{CursorStyle, ScreenState};
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x1b[6 q");
+    /// assert_eq!(screen.cursor_style(), CursorStyle::SteadyBar);
+    /// ```
+    pub fn cursor_style(&self) -> CursorStyle {
+        self.state.cursor_style
+    }
+
+    /// Returns the 0-based columns that have a tab stop, in ascending order.
+    ///
+    /// Stops start every eight columns; applications set them with HTS
+    /// (`ESC H`) and clear them with TBC (`CSI g`, `CSI 3 g`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(30, 2);
+    /// assert_eq!(screen.tab_stops(), vec![8, 16, 24]);
+    ///
+    /// screen.feed(b"\x1b[3g\x1b[1;5H\x1bH\x1b[1;13H\x1bH\r\tA\tB");
+    /// assert_eq!(screen.tab_stops(), vec![4, 12]);
+    /// assert_eq!(screen.text_at(0, 4).as_deref(), Some("A"));
+    /// assert_eq!(screen.text_at(0, 12).as_deref(), Some("B"));
+    /// ```
+    pub fn tab_stops(&self) -> Vec<u16> {
+        self.state.tab_stops.iter().copied().collect()
+    }
+
+    /// Returns the DEC private and ANSI modes the application has set.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x1b[?1000h\x1b[?1004h");
+    /// assert!(screen.modes().mouse_enabled());
+    /// assert!(screen.modes().focus_events);
+    ///
+    /// screen.feed(b"\x1b[?1000l");
+    /// assert!(!screen.modes().mouse_enabled());
+    /// ```
+    pub fn modes(&self) -> &TerminalModes {
+        &self.state.modes
+    }
+
+    /// Returns the window title set with OSC 0 or OSC 2.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x1b]2;Files - ~/projects\x07");
+    /// assert_eq!(screen.title(), "Files - ~/projects");
+    /// ```
+    pub fn title(&self) -> &str {
+        &self.state.title
+    }
+
+    /// Returns the icon name set with OSC 0 or OSC 1.
+    pub fn icon_name(&self) -> &str {
+        &self.state.icon_name
+    }
+
+    /// Returns the titles saved with XTPUSHTITLE (`CSI 22 t`), oldest first.
+    ///
+    /// XTPOPTITLE (`CSI 23 t`) restores the last one. At most 10 titles are
+    /// kept.
+    pub fn title_stack(&self) -> &[String] {
+        &self.state.title_stack
+    }
+
+    /// Returns the working directory the application reported with OSC 7 or
+    /// iTerm2's `OSC 1337 ; CurrentDir=`.
+    ///
+    /// An OSC 7 path is taken from the `file://host/path` URI and
+    /// percent-decoded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x1b]7;file://host/home/user/My%20Documents\x1b\\");
+    /// assert_eq!(screen.cwd(), Some("/home/user/My Documents"));
+    /// ```
+    pub fn cwd(&self) -> Option<&str> {
+        self.state.cwd.as_deref()
+    }
+
+    /// Returns the user variables set with iTerm2's
+    /// `OSC 1337 ; SetUserVar=name=base64`, with decoded values.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// // "bWFpbg==" is base64 for "main"
+    /// screen.feed(b"\x1b]1337;SetUserVar=branch=bWFpbg==\x07");
+    /// assert_eq!(screen.user_var("branch"), Some("main"));
+    /// assert_eq!(screen.user_vars().len(), 1);
+    /// ```
+    pub fn user_vars(&self) -> &BTreeMap<String, String> {
+        &self.state.user_vars
+    }
+
+    /// Returns the value of one user variable set with `OSC 1337 ; SetUserVar`.
+    pub fn user_var(&self, name: &str) -> Option<&str> {
+        self.state.user_vars.get(name).map(String::as_str)
+    }
+
+    /// Returns the bells and desktop notifications raised so far, oldest first.
+    ///
+    /// Only the most recent 256 are kept.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::{Notification, ScreenState};
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x07\x1b]777;notify;Copy;Done\x07");
+    /// assert_eq!(
+    ///     screen.notifications(),
+    ///     [
+    ///         Notification::Bell,
+    ///         Notification::Desktop { title: Some("Copy".into()), body: "Done".into() },
+    ///     ]
+    /// );
+    /// ```
+    pub fn notifications(&self) -> &[Notification] {
+        &self.state.notifications
+    }
+
+    /// Returns the OSC 133 shell integration markers on the primary screen
+    /// and its scrollback, in screen order.
+    ///
+    /// A marker's `line` counts from the oldest scrollback line, as in
+    /// [`contents_with_scrollback`](Self::contents_with_scrollback), so it
+    /// keeps pointing at its prompt while output scrolls it off the screen.
+    /// Markers go away with their lines when the scrollback is trimmed or
+    /// cleared, and when the screen is erased with `CSI 2 J`.
+    ///
+    /// A [`CommandStart`](PromptMarkerType::CommandStart) marker carries the
+    /// command typed after it, up to the next marker or the end of the line,
+    /// and a [`CommandFinished`](PromptMarkerType::CommandFinished) marker
+    /// carries the exit code reported with it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::{navigation::PromptMarkerType, ScreenState};
+    ///
+    /// let mut screen = ScreenState::new(20, 2);
+    /// screen.feed(b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07");
+    /// screen.feed(b"a.txt\r\n\x1b]133;D;0\x07");
+    ///
+    /// let markers = screen.prompt_markers();
+    /// assert_eq!(markers.len(), 4);
+    /// assert_eq!(markers[1].marker_type, PromptMarkerType::CommandStart);
+    /// assert_eq!(markers[1].command.as_deref(), Some("ls"));
+    /// // The prompt has scrolled into the scrollback
+    /// assert_eq!(markers[0].line, 0);
+    /// assert_eq!(markers[3].line, 2);
+    /// assert_eq!(markers[3].exit_code, Some(0));
+    /// ```
+    pub fn prompt_markers(&self) -> Vec<PromptMarker> {
+        let rows: Vec<&Row> = self.state.scrollback.iter().chain(self.primary_rows()).collect();
+        let mut marks: Vec<(usize, PromptMark)> = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(line, row)| row.marks.iter().map(move |&mark| (line, mark)))
+            .collect();
+        marks.sort_by_key(|&(line, mark)| (line, mark.col));
+
+        let mut markers = Vec::with_capacity(marks.len());
+        for (index, &(line, mark)) in marks.iter().enumerate() {
+            let command = (mark.marker_type == PromptMarkerType::CommandStart)
+                .then(|| {
+                    // Without a next marker, the command runs to the end of
+                    // its (possibly wrapped) line
+                    let end = marks.get(index + 1).map(|&(line, next)| (line, next.col));
+                    let end = end.unwrap_or((logical_line_end(&rows, line), self.state.width));
+                    text_between(&rows, (line, mark.col), end).trim().to_string()
+                })
+                .filter(|command| !command.is_empty());
+            markers.push(PromptMarker {
+                line,
+                marker_type: mark.marker_type,
+                command,
+                exit_code: mark.exit_code,
+            });
+        }
+        markers
+    }
+
+    /// Returns the line of the primary screen's cursor, counted from the
+    /// oldest scrollback line like [`PromptMarker::line`].
+    pub(crate) fn primary_cursor_line(&self) -> usize {
+        let row = if self.state.alternate_screen {
+            self.state
+                .inactive_saved_cursor
+                .as_ref()
+                .map_or(0, |saved| saved.pos.0)
+        } else {
+            self.state.cursor_pos.0
+        };
+        self.state.scrollback.len() + row as usize
+    }
+
+    /// Returns the primary screen's text between two positions given as line
+    /// (counted like [`PromptMarker::line`]) and column; see [`text_between`].
+    pub(crate) fn text_between(&self, start: (usize, u16), end: (usize, u16)) -> String {
+        let rows: Vec<&Row> = self.state.scrollback.iter().chain(self.primary_rows()).collect();
+        text_between(&rows, start, end)
+    }
+
+    /// Returns the last line of the logical line starting at `line` on the
+    /// primary screen, following rows continued by autowrap.
+    pub(crate) fn logical_line_end(&self, line: usize) -> usize {
+        let rows: Vec<&Row> = self.state.scrollback.iter().chain(self.primary_rows()).collect();
+        logical_line_end(&rows, line)
+    }
+
+    /// Returns the rows of the primary screen, whichever buffer is shown.
+    fn primary_rows(&self) -> &[Row] {
+        if self.state.alternate_screen {
+            &self.state.inactive_rows
+        } else {
+            &self.state.rows
+        }
+    }
+
+    /// Returns the contents of the simulated clipboard (OSC 52 target `c`),
+    /// or `None` if nothing has been copied to it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x1b]52;c;aGVsbG8=\x07");
+    /// assert_eq!(screen.clipboard(), Some("hello"));
+    /// ```
+    pub fn clipboard(&self) -> Option<&str> {
+        self.selection('c')
+    }
+
+    /// Returns the contents of an OSC 52 selection target (`c`, `p`, `q`,
+    /// `s` or `0`-`7`), or `None` if it was never set.
+    pub fn selection(&self, target: char) -> Option<&str> {
+        self.state.clipboard.get(&target).map(String::as_str)
+    }
+
+    /// Sets the simulated clipboard (target `c`), which is what the
+    /// application receives when it reads the clipboard with `OSC 52 ; c ; ?`.
+    pub fn set_clipboard(&mut self, contents: impl Into<String>) {
+        self.state.clipboard.insert('c', contents.into());
+    }
+
+    /// Returns the OSC 52 clipboard writes made so far, oldest first.
+    ///
+    /// Only the most recent 256 are kept.
+    pub fn clipboard_writes(&self) -> &[ClipboardWrite] {
+        &self.state.clipboard_writes
+    }
+
+    /// Takes the queries received since the last call, oldest first.
+    ///
+    /// Applications ask the terminal for device attributes, the cursor
+    /// position and similar state and wait for the reply. [`TuiTestHarness`]
+    /// answers these automatically; when feeding output directly, use this to
+    /// see what the application asked. Only the most recent 64 unanswered
+    /// queries are kept.
+    ///
+    /// [`TuiTestHarness`]: crate::TuiTestHarness
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::{ScreenState, TerminalQuery};
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x1b[3;5H\x1b[6n\x1b[c");
+    ///
+    /// assert_eq!(
+    ///     screen.take_queries(),
+    ///     vec![
+    ///         TerminalQuery::CursorPosition { row: 2, col: 4, extended: false },
+    ///         TerminalQuery::PrimaryDeviceAttributes,
+    ///     ]
+    /// );
+    /// assert!(screen.take_queries().is_empty());
+    /// ```
+    pub fn take_queries(&mut self) -> Vec<TerminalQuery> {
+        self.state.queries.drain(..).collect()
+    }
+
+    /// Takes the resets (RIS, DECSTR) performed since the last call, oldest
+    /// first.
+    ///
+    /// [`TuiTestHarness`] takes these to add them to its recording.
+    ///
+    /// [`TuiTestHarness`]: crate::TuiTestHarness
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::{ScreenState, TerminalReset};
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"stale\x1bc\x1b[!p");
+    ///
+    /// assert_eq!(screen.contents().trim(), "");
+    /// assert_eq!(screen.take_resets(), vec![TerminalReset::Full, TerminalReset::Soft]);
+    /// ```
+    pub fn take_resets(&mut self) -> Vec<TerminalReset> {
+        self.state.resets.drain(..).collect()
+    }
+
+    /// Returns the screen dimensions.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of (width, height) in columns and rows.
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Returns the screen width in columns.
+    ///
+    /// This is a convenience method equivalent to `self.size().0`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let screen = ScreenState::new(80, 24);
+    /// assert_eq!(screen.cols(), 80);
+    /// ```
+    pub fn cols(&self) -> u16 {
+        self.width
+    }
+
+    /// Returns the screen height in rows.
+    ///
+    /// This is a convenience method equivalent to `self.size().1`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let screen = ScreenState::new(80, 24);
+    /// assert_eq!(screen.rows(), 24);
+    /// ```
+    pub fn rows(&self) -> u16 {
+        self.height
+    }
+
+    /// Returns an iterator over all rows in the screen.
+    ///
+    /// Each item in the iterator is a reference to a row (a slice of cells).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"Line 1\r\nLine 2");
+    ///
+    /// for (row_idx, row) in screen.iter_rows().enumerate() {
+    ///     println!("Row {}: {} cells", row_idx, row.len());
+    ///     for (col_idx, cell) in row.iter().enumerate() {
+    ///         if cell.c != ' ' {
+    ///             println!("  Cell ({}, {}): '{}'", row_idx, col_idx, cell.c);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.state.rows.iter().map(|row| row.cells.as_slice())
+    }
+
+    /// Returns an iterator over all cells in a specific row.
+    ///
+    /// Returns `None` if the row index is out of bounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row index (0-based)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x1b[31mRed\x1b[32mGreen\x1b[34mBlue");
+    ///
+    /// // Use the iterator immediately to avoid lifetime issues
+    /// let colored_cells: Vec<_> = screen
+    ///     .iter_row(0)
+    ///     .unwrap()
+    ///     .enumerate()
+    ///     .filter(|(_, cell)| !cell.fg.is_default())
+    ///     .collect();
+    ///
+    /// assert!(colored_cells.len() >= 3, "Should have colored cells");
+    /// ```
+    pub fn iter_row(&self, row: u16) -> Option<impl Iterator<Item = &Cell>> {
+        if row < self.height {
+            Some(self.state.rows[row as usize].cells.iter())
+        } else {
+            None
+        }
+    }
+
+    /// Returns a structured snapshot of the entire screen grid.
+    ///
+    /// This method provides a complete copy of the screen state suitable for
+    /// deep comparison with other terminal emulators or for serialization.
+    ///
+    /// # Returns
+    ///
+    /// A `GridSnapshot` containing:
+    /// - Grid dimensions (width, height)
+    /// - Complete cell data (2D vector of cells)
+    /// - Current cursor position
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::{Color, ScreenState};
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x1b[31mHello");
+    ///
+    /// let snapshot = screen.snapshot();
+    /// assert_eq!(snapshot.width, 80);
+    /// assert_eq!(snapshot.height, 24);
+    /// assert_eq!(snapshot.cells[0][0].c, 'H');
+    /// assert_eq!(snapshot.cells[0][0].fg, Color::Indexed(1)); // Red
+    /// assert_eq!(snapshot.cursor, (0, 5));
+    /// ```
+    pub fn snapshot(&self) -> GridSnapshot {
+        GridSnapshot {
+            width: self.width,
+            height: self.height,
+            cells: self.state.rows.iter().map(|row| row.cells.clone()).collect(),
+            cursor: self.state.cursor_pos,
+        }
+    }
+
+    /// Returns all Sixel graphics regions currently on screen.
+    ///
+    /// This method provides access to all Sixel graphics that have been rendered
+    /// via DCS (Device Control String) sequences. Each region includes position
+    /// and dimension information.
+    ///
+    /// This is essential for verifying Sixel positioning in tests, particularly
+    /// for ensuring that graphics appear within designated preview areas.
+    ///
+    /// # Returns
+    ///
+    /// A slice of [`SixelRegion`] containing all detected Sixel graphics.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// // ... render some Sixel graphics ...
+    ///
+    /// let regions = screen.sixel_regions();
+    /// for (i, region) in regions.iter().enumerate() {
+    ///     println!(
+    ///         "Region {}: position ({}, {}), size {}x{}",
+    ///         i, region.start_row, region.start_col, region.width, region.height
+    ///     );
+    /// }
+    /// ```
+    pub fn sixel_regions(&self) -> &[SixelRegion] {
+        &self.state.sixel_regions
+    }
+
+    /// Returns a mutable reference to the Sixel regions.
+    ///
+    /// This is primarily intended for testing purposes, allowing tests to
+    /// inject mock Sixel data for memory profiling and other tests.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the vector of Sixel regions.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::{screen::SixelRegion, ScreenState};
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    ///
+    /// // Add a mock Sixel region for testing
+    /// screen.sixel_regions_mut().push(SixelRegion {
+    ///     start_row: 5,
+    ///     start_col: 10,
+    ///     width: 100,
+    ///     height: 50,
+    ///     data: vec![0u8; 1000],
+    ///     params: vec![],
+    /// });
+    ///
+    /// assert_eq!(screen.sixel_regions().len(), 1);
+    /// ```
+    pub fn sixel_regions_mut(&mut self) -> &mut Vec<SixelRegion> {
+        &mut self.state.sixel_regions
+    }
+
+    /// Checks if a Sixel region exists at the given position.
+    ///
+    /// This method checks if any Sixel region has its starting position
+    /// at the exact (row, col) coordinates provided.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row to check (0-indexed)
+    /// * `col` - Column to check (0-indexed)
+    ///
+    /// # Returns
+    ///
+    /// `true` if a Sixel region starts at the given position, `false` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    ///
+    /// // Render a Sixel at position (5, 10) - ESC[5;10H moves to row 5, col 10 (1-based)
+    /// screen.feed(b"\x1b[5;10H"); // Move cursor
+    /// screen.feed(b"\x1bPq"); // Start Sixel
+    /// screen.feed(b"\"1;1;100;50#0~"); // Raster + data
+    /// screen.feed(b"\x1b\\"); // End Sixel
+    ///
+    /// // Check for Sixel at 0-based coordinates (4, 9)
+    /// assert!(screen.has_sixel_at(4, 9));
+    /// assert!(!screen.has_sixel_at(0, 0));
+    /// ```
+    pub fn has_sixel_at(&self, row: u16, col: u16) -> bool {
+        self.state
+            .sixel_regions
+            .iter()
+            .any(|region| region.start_row == row && region.start_col == col)
+    }
+
+    /// Returns all Kitty graphics regions currently on screen.
+    ///
+    /// This method provides access to all Kitty graphics that have been rendered
+    /// via APC (Application Program Command) sequences. Each region includes position
+    /// and dimension information.
+    ///
+    /// # Returns
+    ///
+    /// A slice of [`KittyRegion`] containing all detected Kitty graphics.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// // ... render some Kitty graphics ...
+    ///
+    /// let regions = screen.kitty_regions();
+    /// for (i, region) in regions.iter().enumerate() {
+    ///     println!(
+    ///         "Kitty region {}: position ({}, {}), size {}x{}",
+    ///         i, region.start_row, region.start_col, region.width, region.height
+    ///     );
+    /// }
+    /// ```
+    pub fn kitty_regions(&self) -> &[KittyRegion] {
+        &self.state.kitty_regions
+    }
+
+    /// Returns a mutable reference to the Kitty regions.
+    ///
+    /// This is primarily intended for testing purposes.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the vector of Kitty regions.
+    pub fn kitty_regions_mut(&mut self) -> &mut Vec<KittyRegion> {
+        &mut self.state.kitty_regions
+    }
+
+    /// Returns the images stored with the Kitty graphics protocol, oldest
+    /// first.
+    ///
+    /// An image appears once however many chunks it was sent in, and is
+    /// removed when it is replaced by an image with the same id or freed by
+    /// an uppercase delete command (e.g. `a=d,d=I`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x1b_Ga=t,i=1,f=100;iVBORw0KGgo=\x1b\\");
+    ///
+    /// assert_eq!(screen.kitty_images().len(), 1);
+    /// assert_eq!(screen.kitty_images()[0].format, 100);
+    /// assert!(screen.kitty_placements().is_empty());
+    /// ```
+    pub fn kitty_images(&self) -> &[KittyImage] {
+        &self.state.kitty_images
+    }
+
+    /// Returns the Kitty image with the given id, if it is stored.
+    pub fn kitty_image(&self, id: u32) -> Option<&KittyImage> {
+        self.state.kitty_images.iter().find(|image| image.id == id)
+    }
+
+    /// Returns the Kitty image placements currently on screen, oldest first.
+    ///
+    /// Placements move with the text when the screen scrolls and are removed
+    /// once their top row scrolls off the scroll region, or when the screen
+    /// is cleared. The primary and alternate screens each have their own.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"\x1b_Ga=t,i=3,f=100;iVBORw0KGgo=\x1b\\");
+    ///
+    /// // Show the image in a 20x10 cell preview at row 2, column 40
+    /// screen.feed(b"\x1b[3;41H\x1b_Ga=p,i=3,p=1,c=20,r=10,z=-1\x1b\\");
+    /// let placement = &screen.kitty_placements()[0];
+    /// assert_eq!(placement.bounds(), (2, 40, 20, 10));
+    /// assert_eq!(placement.z_index, -1);
+    ///
+    /// // Close the preview
+    /// screen.feed(b"\x1b_Ga=d,d=i,i=3\x1b\\");
+    /// assert!(screen.kitty_placements().is_empty());
+    /// assert!(screen.kitty_image(3).is_some());
+    /// ```
+    pub fn kitty_placements(&self) -> &[KittyPlacement] {
+        &self.state.kitty_placements
+    }
+
+    /// Returns the Kitty Unicode placeholder cells on screen, row by row,
+    /// resolved to the image and placement they show.
+    ///
+    /// Together with a virtual placement in [`kitty_placements`], this tells
+    /// where an application drew an image with placeholders. See
+    /// [`KittyPlaceholder`] for an example.
+    ///
+    /// [`kitty_placements`]: Self::kitty_placements
+    pub fn kitty_placeholders(&self) -> Vec<KittyPlaceholder> {
+        self.state
+            .rows
+            .iter()
+            .enumerate()
+            .flat_map(|(row, cells)| kitty_placeholders_in_row(row as u16, &cells.cells))
+            .collect()
+    }
+
+    /// Returns the Kitty Unicode placeholder at (row, col), if that cell is one.
+    pub fn kitty_placeholder_at(&self, row: u16, col: u16) -> Option<KittyPlaceholder> {
+        let cells = &self.state.rows.get(row as usize)?.cells;
+        kitty_placeholders_in_row(row, cells)
+            .into_iter()
+            .find(|placeholder| placeholder.col == col)
+    }
+
+    /// Returns all iTerm2 inline image regions currently on screen.
+    ///
+    /// This method provides access to all iTerm2 inline images that have been rendered
+    /// via OSC 1337 sequences. Each region includes position and dimension information.
+    ///
+    /// # Returns
+    ///
+    /// A slice of [`ITerm2Region`] containing all detected iTerm2 inline images.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// // ... render some iTerm2 inline images ...
+    ///
+    /// let regions = screen.iterm2_regions();
+    /// for (i, region) in regions.iter().enumerate() {
+    ///     println!(
+    ///         "iTerm2 region {}: position ({}, {}), size {}x{}",
+    ///         i, region.start_row, region.start_col, region.width, region.height
+    ///     );
+    /// }
+    /// ```
+    pub fn iterm2_regions(&self) -> &[ITerm2Region] {
+        &self.state.iterm2_regions
+    }
+
+    /// Returns a mutable reference to the iTerm2 regions.
+    ///
+    /// This is primarily intended for testing purposes.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the vector of iTerm2 regions.
+    pub fn iterm2_regions_mut(&mut self) -> &mut Vec<ITerm2Region> {
+        &mut self.state.iterm2_regions
+    }
+
+    /// Returns the screen contents for debugging purposes.
+    ///
+    /// This is currently an alias for [`contents()`](Self::contents), but may
+    /// include additional debug information in the future.
+    ///
+    /// # Returns
+    ///
+    /// A string containing the screen contents.
+    pub fn debug_contents(&self) -> String {
+        self.contents()
+    }
+
+    /// Checks if the screen contains the specified text.
+    ///
+    /// This is a convenience method that searches the entire screen contents
+    /// for the given substring. It's useful for simple text-based assertions
+    /// in tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text to search for
+    ///
+    /// # Returns
+    ///
+    /// `true` if the text appears anywhere on the screen, `false` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// let mut screen = ScreenState::new(80, 24);
+    /// screen.feed(b"Welcome to the application");
+    ///
+    /// assert!(screen.contains("Welcome"));
+    /// assert!(screen.contains("application"));
+    /// assert!(!screen.contains("goodbye"));
+    /// ```
+    pub fn contains(&self, text: &str) -> bool {
+        self.contents().contains(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_screen() {
+        let screen = ScreenState::new(80, 24);
+        assert_eq!(screen.size(), (80, 24));
+    }
+
+    #[test]
+    fn test_feed_simple_text() {
+        let mut screen = ScreenState::new(80, 24);
+        screen.feed(b"Hello, World!");
+        assert!(screen.contents().contains("Hello, World!"));
+    }
+
+    #[test]
+    fn test_cursor_position() {
+        let mut screen = ScreenState::new(80, 24);
+
+        // Initial position
+        assert_eq!(screen.cursor_position(), (0, 0));
+
+        // Move cursor using CSI sequence (ESC [ 5 ; 10 H = row 5, col 10)
+        screen.feed(b"\x1b[5;10H");
+        let (row, col) = screen.cursor_position();
+
+        // CSI uses 1-based, we convert to 0-based
+        assert_eq!(row, 4); // 5-1 = 4
+        assert_eq!(col, 9); // 10-1 = 9
+    }
+
+    #[test]
+    fn test_text_at() {
+        let mut screen = ScreenState::new(80, 24);
+        screen.feed(b"Test");
+
+        assert_eq!(screen.text_at(0, 0).as_deref(), Some("T"));
+        assert_eq!(screen.text_at(0, 1).as_deref(), Some("e"));
+        assert_eq!(screen.text_at(0, 2).as_deref(), Some("s"));
+        assert_eq!(screen.text_at(0, 3).as_deref(), Some("t"));
+        assert_eq!(screen.text_at(0, 4).as_deref(), Some(" "));
+        assert_eq!(screen.text_at(100, 100), None);
+    }
+
+    #[test]
+    fn test_cursor_position_with_omitted_params() {
+        let mut screen = ScreenState::new(80, 24);
+
+        // Omitted row defaults to 1 while the column is still honoured
+        screen.feed(b"\x1b[;5H");
+        assert_eq!(screen.cursor_position(), (0, 4));
+
+        // Zero is treated the same as an omitted parameter
+        screen.feed(b"\x1b[0;0H");
+        assert_eq!(screen.cursor_position(), (0, 0));
+    }
+
+    #[test]
+    fn test_erase_in_display() {
+        let mut screen = ScreenState::new(5, 3);
+        screen.feed(b"aaaaa\x1b[2;1Hbbbbb\x1b[3;1Hccccc");
+
+        // ED 0: from the cursor to the end of the screen
+        screen.feed(b"\x1b[2;3H\x1b[J");
+        assert_eq!(screen.contents(), "aaaaa\nbb   \n     ");
+
+        // ED 1: from the start of the screen through the cursor
+        screen.feed(b"\x1b[1;1Haaaaa\x1b[2;1Hbbbbb\x1b[2;3H\x1b[1J");
+        assert_eq!(screen.contents(), "     \n   bb\n     ");
+
+        // ED 2: the entire screen, cursor stays put
+        screen.feed(b"\x1b[2J");
+        assert_eq!(screen.contents(), "     \n     \n     ");
+        assert_eq!(screen.cursor_position(), (1, 2));
+    }
+
+    #[test]
+    fn test_erase_in_line() {
+        let mut screen = ScreenState::new(6, 1);
+
+        screen.feed(b"abcdef\x1b[1;3H\x1b[K");
+        assert_eq!(screen.row_contents(0), "ab    ");
+
+        screen.feed(b"\x1b[1;1Habcdef\x1b[1;3H\x1b[1K");
+        assert_eq!(screen.row_contents(0), "   def");
+
+        screen.feed(b"\x1b[2K");
+        assert_eq!(screen.row_contents(0), "      ");
+    }
+
+    #[test]
+    fn test_erase_applies_current_background() {
+        let mut screen = ScreenState::new(10, 2);
+        screen.feed(b"stale text\x1b[44m\x1b[2J");
+
+        let cell = screen.get_cell(1, 5).unwrap();
+        assert_eq!(cell.c, ' ');
+        assert_eq!(cell.bg, Color::Indexed(4), "Erased cells take the current background");
+        assert_eq!(cell.fg, Color::Default, "Erased cells do not take the foreground");
+        assert!(!screen.contains("stale"));
+    }
+
+    #[test]
+    fn test_erase_characters() {
+        let mut screen = ScreenState::new(8, 1);
+        screen.feed(b"abcdefgh\x1b[1;3H\x1b[3X");
+
+        assert_eq!(screen.row_contents(0), "ab   fgh");
+        assert_eq!(screen.cursor_position(), (0, 2), "ECH does not move the cursor");
+
+        // Count is clamped to the end of the line
+        screen.feed(b"\x1b[1;7H\x1b[99X");
+        assert_eq!(screen.row_contents(0), "ab   f  ");
+    }
+
+    #[test]
+    fn test_insert_and_delete_characters() {
+        let mut screen = ScreenState::new(8, 1);
+        screen.feed(b"abcdefgh\x1b[1;3H\x1b[2@");
+        assert_eq!(screen.row_contents(0), "ab  cdef");
+
+        screen.feed(b"\x1b[3P");
+        assert_eq!(screen.row_contents(0), "abdef   ");
+
+        // Default count is 1
+        screen.feed(b"\x1b[P");
+        assert_eq!(screen.row_contents(0), "abef    ");
+    }
+
+    #[test]
+    fn test_insert_and_delete_lines() {
+        let mut screen = ScreenState::new(3, 4);
+        screen.feed(b"111\x1b[2;1H222\x1b[3;1H333\x1b[4;1H444");
+
+        screen.feed(b"\x1b[2;2H\x1b[L");
+        assert_eq!(screen.contents(), "111\n   \n222\n333");
+        assert_eq!(screen.cursor_position(), (1, 0), "IL moves to the left margin");
+
+        screen.feed(b"\x1b[2M");
+        assert_eq!(screen.contents(), "111\n333\n   \n   ");
+
+        // Counts larger than the remaining lines clear to the bottom
+        screen.feed(b"\x1b[1;1H\x1b[99L");
+        assert_eq!(screen.contents(), "   \n   \n   \n   ");
+    }
+
+    #[test]
+    fn test_linefeed_scrolls_at_bottom() {
+        let mut screen = ScreenState::new(5, 3);
+        screen.feed(b"a\r\nb\r\nc\r\nd");
+
+        assert_eq!(screen.contents(), "b    \nc    \nd    ");
+        assert_eq!(screen.cursor_position(), (2, 1));
+        assert_eq!(screen.scrollback(), vec!["a    "]);
+    }
+
+    #[test]
+    fn test_scroll_region() {
+        let mut screen = ScreenState::new(3, 5);
+        screen.feed(b"hdr\x1b[5;1Hftr");
+
+        // DECSTBM homes the cursor
+        screen.feed(b"\x1b[2;4r");
+        assert_eq!(screen.cursor_position(), (0, 0));
+
+        screen.feed(b"\x1b[2;1H111\r\n222\r\n333\r\n444");
+        assert_eq!(screen.contents(), "hdr\n222\n333\n444\nftr");
+        assert!(
+            screen.scrollback().is_empty(),
+            "Lines leaving a region below the top row are not kept"
+        );
+
+        // IL/DL only affect the region
+        screen.feed(b"\x1b[3;1H\x1b[L");
+        assert_eq!(screen.contents(), "hdr\n222\n   \n333\nftr");
+        screen.feed(b"\x1b[2M");
+        assert_eq!(screen.contents(), "hdr\n222\n   \n   \nftr");
+
+        // IL outside the region is ignored
+        screen.feed(b"\x1b[5;1H\x1b[L");
+        assert_eq!(screen.row_contents(4), "ftr");
+
+        // Reset to the full screen
+        screen.feed(b"\x1b[r\x1b[5;1H\n");
+        assert_eq!(screen.scrollback(), vec!["hdr"]);
+    }
+
+    #[test]
+    fn test_invalid_scroll_region_is_ignored() {
+        let mut screen = ScreenState::new(3, 4);
+        screen.feed(b"\x1b[2;2H\x1b[3;2r");
+        assert_eq!(screen.cursor_position(), (1, 1));
+
+        screen.feed(b"\x1b[4;1Hx\n");
+        assert_eq!(screen.row_contents(2), "x  ", "Full-screen scrolling still applies");
+    }
+
+    #[test]
+    fn test_scroll_up_and_down() {
+        let mut screen = ScreenState::new(1, 4);
+        screen.feed(b"1\r\n2\r\n3\r\n4");
+
+        screen.feed(b"\x1b[2S");
+        assert_eq!(screen.contents(), "3\n4\n \n ");
+        assert_eq!(screen.cursor_position(), (3, 0), "SU does not move the cursor");
+
+        screen.feed(b"\x1b[T");
+        assert_eq!(screen.contents(), " \n3\n4\n ");
+        assert_eq!(screen.scrollback(), vec!["1", "2"]);
+    }
+
+    #[test]
+    fn test_reverse_index() {
+        let mut screen = ScreenState::new(1, 3);
+        screen.feed(b"1\r\n2\r\n3");
+
+        screen.feed(b"\x1b[2;1H\x1bM");
+        assert_eq!(screen.cursor_position(), (0, 0));
+        assert_eq!(screen.contents(), "1\n2\n3");
+
+        // At the top margin RI scrolls the region down
+        screen.feed(b"\x1bM");
+        assert_eq!(screen.contents(), " \n1\n2");
+    }
+
+    #[test]
+    fn test_scrollback_limit_and_clear() {
+        let mut screen = ScreenState::new(2, 1).with_scrollback_limit(2);
+        screen.feed(b"a\r\nb\r\nc\r\nd");
+
+        assert_eq!(screen.scrollback(), vec!["b ", "c "]);
+        assert_eq!(screen.contents_with_scrollback(), "b \nc \nd ");
+
+        // ED 3 clears the scrollback but leaves the screen alone
+        screen.feed(b"\x1b[3J");
+        assert!(screen.scrollback().is_empty());
+        assert_eq!(screen.contents(), "d ");
+    }
+
+    #[test]
+    fn test_alternate_screen_1049() {
+        let mut screen = ScreenState::new(6, 2);
+        screen.feed(b"shell\x1b[2;3H\x1b[31m");
+
+        screen.feed(b"\x1b[?1049h");
+        assert!(screen.is_alternate_screen());
+        assert_eq!(screen.contents(), "      \n      ", "1049 clears the alternate screen");
+        assert_eq!(screen.primary_contents(), "shell \n      ");
+
+        screen.feed(b"\x1b[0m\x1b[HApp");
+        assert_eq!(screen.row_contents(0), "App   ");
+
+        // Leaving restores the primary grid, cursor and attributes
+        screen.feed(b"\x1b[?1049l");
+        assert!(!screen.is_alternate_screen());
+        assert_eq!(screen.contents(), "shell \n      ");
+        assert_eq!(screen.cursor_position(), (1, 2));
+        screen.feed(b"x");
+        assert_eq!(screen.get_cell(1, 2).unwrap().fg, Color::Indexed(1));
+    }
+
+    #[test]
+    fn test_alternate_screen_47_and_1047() {
+        let mut screen = ScreenState::new(4, 1);
+        screen.feed(b"main");
+
+        // 47 neither clears nor saves the cursor, and keeps the alternate contents
+        screen.feed(b"\x1b[?47h\x1b[Halt");
+        screen.feed(b"\x1b[?47l");
+        assert_eq!(screen.contents(), "main");
+        assert_eq!(screen.cursor_position(), (0, 3));
+        screen.feed(b"\x1b[?47h");
+        assert_eq!(screen.contents(), "alt ");
+
+        // 1047 clears the alternate screen on the way out
+        screen.feed(b"\x1b[?47l\x1b[?1047h");
+        assert_eq!(screen.contents(), "alt ");
+        screen.feed(b"\x1b[?1047l\x1b[?1047h");
+        assert_eq!(screen.contents(), "    ");
+        assert_eq!(screen.primary_contents(), "main");
+    }
+
+    #[test]
+    fn test_alternate_screen_does_not_fill_scrollback() {
+        let mut screen = ScreenState::new(3, 2);
+        screen.feed(b"\x1b[?1049h1\r\n2\r\n3\r\n4");
+        assert!(screen.scrollback().is_empty());
+
+        screen.feed(b"\x1b[?1049l");
+        assert_eq!(screen.contents(), "   \n   ");
+    }
+
+    #[test]
+    fn test_autowrap_is_deferred() {
+        let mut screen = ScreenState::new(3, 2);
+        screen.feed(b"abc");
+
+        // The cursor waits in the last column until the next character
+        assert_eq!(screen.cursor_position(), (0, 2));
+        assert_eq!(screen.row_contents(1), "   ");
+
+        screen.feed(b"d");
+        assert_eq!(screen.contents(), "abc\nd  ");
+        assert_eq!(screen.cursor_position(), (1, 1));
+        assert!(screen.is_wrapped(0));
+        assert!(!screen.is_wrapped(1));
+    }
+
+    #[test]
+    fn test_pending_wrap_cancelled_by_cursor_movement() {
+        let mut screen = ScreenState::new(3, 2);
+
+        // CR LF right after filling a row must not produce a blank line
+        screen.feed(b"abc\r\nd");
+        assert_eq!(screen.contents(), "abc\nd  ");
+        assert!(!screen.is_wrapped(0));
+
+        // SGR does not cancel the pending wrap
+        screen.feed(b"\x1b[Hxyz\x1b[1mw");
+        assert_eq!(screen.contents(), "xyz\nw  ");
+        assert!(screen.get_cell(1, 0).unwrap().bold);
+
+        // Cursor positioning does
+        screen.feed(b"\x1b[1;1Habc\x1b[1;3HZ");
+        assert_eq!(screen.contents(), "abZ\nw  ");
+    }
+
+    #[test]
+    fn test_autowrap_scrolls_at_bottom() {
+        let mut screen = ScreenState::new(2, 2);
+        screen.feed(b"abcdef");
+
+        assert_eq!(screen.contents(), "cd\nef");
+        assert_eq!(screen.scrollback(), vec!["ab"]);
+        assert!(screen.is_wrapped(0));
+        assert_eq!(screen.logical_lines(), vec!["cdef"]);
+    }
+
+    #[test]
+    fn test_autowrap_disabled() {
+        let mut screen = ScreenState::new(3, 2);
+        screen.feed(b"\x1b[?7labcdef");
+        assert_eq!(screen.contents(), "abf\n   ");
+        assert_eq!(screen.cursor_position(), (0, 2));
+        assert!(!screen.is_wrapped(0));
+
+        screen.feed(b"\x1b[?7h\x1b[2;1Hxyz!");
+        assert_eq!(screen.contents(), "xyz\n!  ");
+    }
+
+    #[test]
+    fn test_logical_lines() {
+        let mut screen = ScreenState::new(5, 5);
+        screen.feed(b"one two three\r\nfour");
+
+        assert_eq!(screen.row_contents(1), "wo th");
+        assert_eq!(screen.logical_lines(), vec!["one two three", "four", ""]);
+    }
+
+    #[test]
+    fn test_erase_to_end_of_line_clears_wrap() {
+        let mut screen = ScreenState::new(3, 2);
+        screen.feed(b"abcd");
+        assert!(screen.is_wrapped(0));
+
+        screen.feed(b"\x1b[1;2H\x1b[K");
+        assert!(!screen.is_wrapped(0));
+        assert_eq!(screen.logical_lines(), vec!["a", "d"]);
+    }
+
+    #[test]
+    fn test_wide_characters() {
+        let mut screen = ScreenState::new(6, 1);
+        screen.feed("日本x".as_bytes());
+
+        assert_eq!(screen.cursor_position(), (0, 5));
+        assert_eq!(screen.row_contents(0), "日本x ");
+        assert!(screen.get_cell(0, 0).unwrap().is_wide());
+        assert!(screen.get_cell(0, 1).unwrap().is_wide_continuation());
+        assert_eq!(screen.text_at(0, 2).as_deref(), Some("本"));
+        assert_eq!(screen.text_at(0, 4).as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn test_wide_character_wraps_instead_of_straddling() {
+        let mut screen = ScreenState::new(3, 2);
+        screen.feed("ab日".as_bytes());
+
+        assert_eq!(screen.row_contents(0), "ab ");
+        assert_eq!(screen.row_contents(1), "日 ");
+        assert!(screen.is_wrapped(0));
+
+        // Without autowrap the character is drawn in the last two columns
+        let mut screen = ScreenState::new(3, 1);
+        screen.feed("\x1b[?7lab日".as_bytes());
+        assert_eq!(screen.row_contents(0), "a日");
+    }
+
+    #[test]
+    fn test_wrapped_wide_character_gap_is_not_text() {
+        let mut screen = ScreenState::new(5, 2);
+        screen.feed("abcd日本".as_bytes());
+
+        assert_eq!(screen.row_contents(0), "abcd ");
+        assert_eq!(screen.logical_lines(), vec!["abcd日本"]);
+        assert_eq!(screen.text_between((0, 0), (1, 4)), "abcd日本");
+
+        // Printing into the gap makes it part of the text again
+        screen.feed(b"\x1b[1;5He");
+        assert_eq!(screen.logical_lines(), vec!["abcde日本"]);
+    }
+
+    #[test]
+    fn test_overwriting_half_of_wide_character() {
+        let mut screen = ScreenState::new(4, 1);
+        screen.feed("日本".as_bytes());
+
+        // Writing over the spacer blanks the character it belongs to
+        screen.feed(b"\x1b[1;2Hx");
+        assert_eq!(screen.row_contents(0), " x本");
+
+        // Erasing the left half blanks the spacer too
+        screen.feed(b"\x1b[1;3H\x1b[X");
+        assert_eq!(screen.row_contents(0), " x  ");
+        assert!(!screen.get_cell(0, 3).unwrap().is_wide_continuation());
+    }
+
+    #[test]
+    fn test_combining_marks_join_base_cell() {
+        let mut screen = ScreenState::new(5, 1);
+        screen.feed("cafe\u{301}!".as_bytes());
+
+        assert_eq!(screen.cursor_position(), (0, 4), "The accent takes no column");
+        assert_eq!(screen.row_contents(0), "cafe\u{301}!");
+        assert_eq!(screen.text_at(0, 3).as_deref(), Some("e\u{301}"));
+        assert_eq!(screen.get_cell(0, 3).unwrap().c, 'e');
+        assert_eq!(screen.text_at(0, 4).as_deref(), Some("!"));
+    }
+
+    #[test]
+    fn test_zwj_sequence_and_flags_form_one_cluster() {
+        let mut screen = ScreenState::new(8, 1);
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        screen.feed(format!("{}\u{1F1FA}\u{1F1F8}x", family).as_bytes());
+
+        assert_eq!(screen.text_at(0, 0).as_deref(), Some(family));
+        assert!(screen.get_cell(0, 0).unwrap().is_wide());
+        assert_eq!(screen.text_at(0, 2).as_deref(), Some("\u{1F1FA}\u{1F1F8}"));
+        assert_eq!(screen.text_at(0, 4).as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn test_emoji_presentation_selector_widens_cell() {
+        let mut screen = ScreenState::new(4, 1);
+        screen.feed("\u{2764}\u{FE0F}x".as_bytes());
+
+        assert!(screen.get_cell(0, 0).unwrap().is_wide());
+        assert_eq!(screen.text_at(0, 0).as_deref(), Some("\u{2764}\u{FE0F}"));
+        assert_eq!(screen.text_at(0, 2).as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn test_sgr_truecolor_semicolon_form() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"\x1b[38;2;255;128;0;48;2;0;0;64mA\x1b[39mB\x1b[1;38;5;196mC");
+
+        let a = screen.get_cell(0, 0).unwrap();
+        assert_eq!(a.fg, Color::Rgb(255, 128, 0));
+        assert_eq!(a.bg, Color::Rgb(0, 0, 64));
+
+        let b = screen.get_cell(0, 1).unwrap();
+        assert_eq!(b.fg, Color::Default);
+        assert_eq!(b.bg, Color::Rgb(0, 0, 64));
+
+        // Parameters after an extended color are still applied
+        let c = screen.get_cell(0, 2).unwrap();
+        assert_eq!(c.fg, Color::Indexed(196));
+        assert!(c.bold);
+    }
+
+    #[test]
+    fn test_sgr_colon_sub_parameters() {
+        let mut screen = ScreenState::new(10, 1);
+
+        // ITU T.416 form with an empty color space id
+        screen.feed(b"\x1b[38:2::10:20:30mA");
+        assert_eq!(screen.get_cell(0, 0).unwrap().fg, Color::Rgb(10, 20, 30));
+
+        // Common form without the color space id
+        screen.feed(b"\x1b[48:2:1:2:3mB");
+        assert_eq!(screen.get_cell(0, 1).unwrap().bg, Color::Rgb(1, 2, 3));
+
+        // Colon sub-parameters do not swallow the following parameter
+        screen.feed(b"\x1b[38:5:42;4mC");
+        let c = screen.get_cell(0, 2).unwrap();
+        assert_eq!(c.fg, Color::Indexed(42));
+        assert!(c.underline);
+    }
+
+    #[test]
+    fn test_sgr_incomplete_extended_color_is_ignored() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"\x1b[31m\x1b[38;2;255mA\x1b[38;5mB");
+
+        assert_eq!(screen.get_cell(0, 0).unwrap().fg, Color::Indexed(1));
+        assert_eq!(screen.get_cell(0, 1).unwrap().fg, Color::Indexed(1));
+    }
+
+    #[test]
+    fn test_color_text_format_roundtrip() {
+        for color in [Color::Default, Color::Indexed(7), Color::Rgb(0xab, 0x01, 0xff)] {
+            assert_eq!(color.to_string().parse::<Color>(), Ok(color));
+        }
+        assert_eq!(Color::Rgb(255, 128, 0).to_string(), "#ff8000");
+        assert!("#12345".parse::<Color>().is_err());
+        assert!("256".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn test_private_sgr_is_not_applied() {
+        let mut screen = ScreenState::new(10, 1);
+
+        // xterm modifyOtherKeys (CSI > 4 ; 1 m) must not be read as SGR 4 (underline)
+        screen.feed(b"\x1b[>4;1mX");
+        assert!(!screen.get_cell(0, 0).unwrap().underline);
+    }
+
+    #[test]
+    fn test_sgr_attributes_set_and_reset() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"\x1b[2;5;7;8;9;53mA\x1b[22;25;27;28;29;55mB");
+
+        let a = screen.get_cell(0, 0).unwrap();
+        assert!(a.dim && a.blink && a.reverse && a.hidden && a.strikethrough && a.overline);
+
+        let b = screen.get_cell(0, 1).unwrap();
+        assert_eq!(*b, Cell { c: 'B', ..Cell::default() });
+    }
+
+    #[test]
+    fn test_sgr_22_clears_bold_and_dim() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"\x1b[1;2mA\x1b[22mB");
+
+        assert!(screen.get_cell(0, 0).unwrap().bold);
+        let b = screen.get_cell(0, 1).unwrap();
+        assert!(!b.bold && !b.dim);
+    }
+
+    #[test]
+    fn test_sgr_underline_styles() {
+        let mut screen = ScreenState::new(10, 1);
+        screen
+            .feed(b"\x1b[4mA\x1b[21mB\x1b[4:3mC\x1b[4:4mD\x1b[4:5mE\x1b[4:0mF\x1b[4:2mG\x1b[24mH");
+
+        let styles: Vec<UnderlineStyle> = screen
+            .iter_row(0)
+            .unwrap()
+            .take(8)
+            .map(|cell| cell.underline_style)
+            .collect();
+        assert_eq!(
+            styles,
+            [
+                UnderlineStyle::Single,
+                UnderlineStyle::Double,
+                UnderlineStyle::Curly,
+                UnderlineStyle::Dotted,
+                UnderlineStyle::Dashed,
+                UnderlineStyle::None,
+                UnderlineStyle::Double,
+                UnderlineStyle::None,
+            ]
+        );
+
+        // The underline flag follows the style
+        assert!(screen.get_cell(0, 2).unwrap().underline);
+        assert!(!screen.get_cell(0, 5).unwrap().underline);
+    }
+
+    #[test]
+    fn test_sgr_underline_color() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"\x1b[4:3;58;5;9mA\x1b[58:2::255:0:0mB\x1b[59mC");
+
+        let a = screen.get_cell(0, 0).unwrap();
+        assert_eq!(a.underline_color, Color::Indexed(9));
+        assert_eq!(a.fg, Color::Default);
+        assert_eq!(screen.get_cell(0, 1).unwrap().underline_color, Color::Rgb(255, 0, 0));
+        assert_eq!(screen.get_cell(0, 2).unwrap().underline_color, Color::Default);
+        assert_eq!(screen.get_cell(0, 2).unwrap().underline_style, UnderlineStyle::Curly);
+    }
+
+    #[test]
+    fn test_alternate_screen_exit_restores_all_attributes() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"\x1b[7;9;4:3;58;5;2m\x1b[?1049h\x1b[0m\x1b[?1049lA");
+
+        let a = screen.get_cell(0, 0).unwrap();
+        assert!(a.reverse && a.strikethrough);
+        assert_eq!(a.underline_style, UnderlineStyle::Curly);
+        assert_eq!(a.underline_color, Color::Indexed(2));
+    }
+
+    #[test]
+    fn test_decsc_decrc_restore_position_and_attributes() {
+        let mut screen = ScreenState::new(20, 5);
+        screen.feed(b"\x1b[2;3H\x1b[1;31m\x1b7\x1b[0m\x1b[5;10HX\x1b8Y");
+
+        assert_eq!(screen.text_at(1, 2).as_deref(), Some("Y"));
+        let y = screen.get_cell(1, 2).unwrap();
+        assert!(y.bold);
+        assert_eq!(y.fg, Color::Indexed(1));
+        assert!(!screen.get_cell(4, 9).unwrap().bold);
+    }
+
+    #[test]
+    fn test_csi_s_u_save_and_restore_cursor() {
+        let mut screen = ScreenState::new(20, 5);
+        screen.feed(b"\x1b[3;4H\x1b[s\x1b[HAB\x1b[u");
+
+        assert_eq!(screen.cursor_position(), (2, 3));
+    }
+
+    #[test]
+    fn test_decrc_without_save_homes_cursor() {
+        let mut screen = ScreenState::new(20, 5);
+        screen.feed(b"\x1b[7m\x1b[3;4H\x1b8");
+
+        assert_eq!(screen.cursor_position(), (0, 0));
+        screen.feed(b"A");
+        assert!(!screen.get_cell(0, 0).unwrap().reverse);
+    }
+
+    #[test]
+    fn test_saved_cursor_is_per_screen_buffer() {
+        let mut screen = ScreenState::new(20, 5);
+        screen.feed(b"\x1b[2;2H\x1b7\x1b[?47h\x1b[4;4H\x1b7\x1b[?47l\x1b8");
+
+        assert_eq!(screen.cursor_position(), (1, 1));
+    }
+
+    #[test]
+    fn test_esc_hash_8_is_not_decrc() {
+        let mut screen = ScreenState::new(20, 5);
+        screen.feed(b"\x1b[3;3H\x1b7\x1b[5;5H\x1b#8");
+
+        assert_eq!(screen.cursor_position(), (4, 4));
+    }
+
+    #[test]
+    fn test_dectcem_cursor_visibility() {
+        let mut screen = ScreenState::new(20, 5);
+        assert!(screen.cursor_visible());
+
+        screen.feed(b"\x1b[?25l");
+        assert!(!screen.cursor_visible());
+
+        screen.feed(b"\x1b[?25h");
+        assert!(screen.cursor_visible());
+    }
+
+    #[test]
+    fn test_decscusr_cursor_style() {
+        let mut screen = ScreenState::new(20, 5);
+        assert_eq!(screen.cursor_style(), CursorStyle::Default);
+
+        screen.feed(b"\x1b[4 q");
+        assert_eq!(screen.cursor_style(), CursorStyle::SteadyUnderline);
+
+        // Unknown styles are ignored
+        screen.feed(b"\x1b[9 q");
+        assert_eq!(screen.cursor_style(), CursorStyle::SteadyUnderline);
+
+        screen.feed(b"\x1b[ q");
+        assert_eq!(screen.cursor_style(), CursorStyle::Default);
+
+        // DECSCUSR is not DECSTBM or anything else ending in q
+        assert_eq!(screen.cursor_position(), (0, 0));
+    }
+
+    #[test]
+    fn test_modes_default() {
+        let screen = ScreenState::new(20, 5);
+        let modes = screen.modes();
+
+        assert!(modes.autowrap);
+        assert!(modes.cursor_visible);
+        assert!(!modes.mouse_enabled());
+        assert_eq!(modes.mouse_encoding, MouseEncoding::Default);
+        assert_eq!(modes.dec_mode(1000), None);
+    }
+
+    #[test]
+    fn test_mouse_tracking_modes() {
+        let mut screen = ScreenState::new(20, 5);
+
+        screen.feed(b"\x1b[?1000;1006h");
+        assert_eq!(screen.modes().mouse_tracking, MouseTracking::Normal);
+        assert_eq!(screen.modes().mouse_encoding, MouseEncoding::Sgr);
+
+        screen.feed(b"\x1b[?1002h");
+        assert_eq!(screen.modes().mouse_tracking, MouseTracking::ButtonEvent);
+
+        // Resetting an encoding that is not active leaves it alone
+        screen.feed(b"\x1b[?1015l");
+        assert_eq!(screen.modes().mouse_encoding, MouseEncoding::Sgr);
+
+        screen.feed(b"\x1b[?1006l\x1b[?1000l");
+        assert_eq!(screen.modes().mouse_tracking, MouseTracking::None);
+        assert_eq!(screen.modes().mouse_encoding, MouseEncoding::Default);
+        assert_eq!(screen.modes().dec_mode(1002), Some(true));
+        assert_eq!(screen.modes().dec_mode(1000), Some(false));
+    }
+
+    #[test]
+    fn test_dec_modes_tracked() {
+        let mut screen = ScreenState::new(20, 5);
+        screen.feed(b"\x1b[?1h\x1b[?1004h\x1b[?2004h\x1b[?2026h\x1b[?12h\x1b=");
+
+        let modes = screen.modes();
+        assert!(modes.application_cursor_keys);
+        assert!(modes.application_keypad);
+        assert!(modes.focus_events);
+        assert!(modes.bracketed_paste);
+        assert!(modes.synchronized_output);
+        // Modes without a typed field are still recorded
+        assert_eq!(modes.dec_mode(12), Some(true));
+
+        screen.feed(b"\x1b[?7l\x1b>");
+        assert!(!screen.modes().autowrap);
+        assert!(!screen.modes().application_keypad);
+    }
+
+    #[test]
+    fn test_insert_mode() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"world\x1b[H\x1b[4hhello \x1b[4lX");
+
+        assert!(!screen.modes().insert_mode);
+        assert_eq!(screen.modes().ansi_mode(4), Some(false));
+        assert_eq!(screen.row_contents(0), "hello Xorl");
+    }
+
+    #[test]
+    fn test_linefeed_newline_mode() {
+        let mut screen = ScreenState::new(10, 3);
+        screen.feed(b"\x1b[20hab\ncd");
+
+        assert!(screen.modes().linefeed_newline);
+        assert_eq!(screen.row_contents(1), "cd        ");
+    }
+
+    #[test]
+    fn test_queries_are_collected() {
+        let mut screen = ScreenState::new(20, 5);
+        screen.feed(b"\x1b[c\x1b[0c\x1b[>c\x1b[5n\x1b[2;3H\x1b[6n\x1b[?6n\x1b[>q");
+
+        assert_eq!(
+            screen.take_queries(),
+            vec![
+                TerminalQuery::PrimaryDeviceAttributes,
+                TerminalQuery::PrimaryDeviceAttributes,
+                TerminalQuery::SecondaryDeviceAttributes,
+                TerminalQuery::DeviceStatus,
+                TerminalQuery::CursorPosition { row: 1, col: 2, extended: false },
+                TerminalQuery::CursorPosition { row: 1, col: 2, extended: true },
+                TerminalQuery::TerminalVersion,
+            ]
+        );
+
+        // DA3 and XTMODKEYS-style sequences are not queries we answer
+        screen.feed(b"\x1b[=c\x1b[>4;1m");
+        assert!(screen.take_queries().is_empty());
+    }
+
+    #[test]
+    fn test_decrqm_captures_mode_state() {
+        let mut screen = ScreenState::new(20, 5);
+        screen.feed(b"\x1b[?2004h\x1b[?2004$p\x1b[?1049$p\x1b[?12345$p\x1b[4$p");
+
+        assert_eq!(
+            screen.take_queries(),
+            vec![
+                TerminalQuery::Mode { mode: 2004, private: true, state: Some(true) },
+                TerminalQuery::Mode { mode: 1049, private: true, state: Some(false) },
+                TerminalQuery::Mode { mode: 12345, private: true, state: None },
+                TerminalQuery::Mode { mode: 4, private: false, state: Some(false) },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pending_queries_are_bounded() {
+        let mut screen = ScreenState::new(20, 5);
+        for _ in 0..MAX_PENDING_QUERIES + 10 {
+            screen.feed(b"\x1b[5n");
+        }
+
+        assert_eq!(screen.take_queries().len(), MAX_PENDING_QUERIES);
+    }
+
+    #[test]
+    fn test_xtwinops_size_reports() {
+        let mut screen = ScreenState::new(80, 24).with_cell_size(10, 20);
+        screen.feed(b"\x1b[14t\x1b[16t\x1b[18t\x1b[22t");
+
+        let queries = screen.take_queries();
+        assert_eq!(
+            queries,
+            vec![
+                TerminalQuery::TextAreaPixels { width: 800, height: 480 },
+                TerminalQuery::CellPixels { width: 10, height: 20 },
+                TerminalQuery::TextAreaCells { rows: 24, cols: 80 },
+            ]
+        );
+        let replies: Vec<_> =
+            queries.iter().filter_map(|query| query.reply(TerminalProfile::Xterm256)).collect();
+        assert_eq!(replies, [&b"\x1b[4;480;800t"[..], b"\x1b[6;20;10t", b"\x1b[8;24;80t"]);
+    }
+
+    #[test]
+    fn test_zero_cell_size_is_clamped() {
+        let mut screen = ScreenState::new(80, 24).with_cell_size(0, 0);
+        assert_eq!(screen.cell_size(), (1, 1));
+
+        screen.feed(b"\x1b[14t\x1b[16t");
+        let replies: Vec<_> = screen
+            .take_queries()
+            .iter()
+            .filter_map(|query| query.reply(TerminalProfile::Xterm256))
+            .collect();
+        assert_eq!(replies, [&b"\x1b[4;24;80t"[..], b"\x1b[6;1;1t"]);
+    }
+
+    #[test]
+    fn test_query_replies() {
+        let profile = TerminalProfile::Xterm256;
+        let reply = |query: TerminalQuery| query.reply(profile).map(String::from_utf8);
+
+        assert_eq!(reply(TerminalQuery::DeviceStatus), Some(Ok("\x1b[0n".to_string())));
+        assert_eq!(
+            reply(TerminalQuery::CursorPosition { row: 0, col: 0, extended: true }),
+            Some(Ok("\x1b[?1;1;1R".to_string()))
+        );
+        assert_eq!(
+            reply(TerminalQuery::TerminalVersion),
+            Some(Ok("\x1bP>|XTerm(390)\x1b\\".to_string()))
+        );
+        assert_eq!(
+            reply(TerminalQuery::Mode { mode: 2004, private: true, state: Some(false) }),
+            Some(Ok("\x1b[?2004;2$y".to_string()))
+        );
+        assert_eq!(
+            reply(TerminalQuery::Mode { mode: 4, private: false, state: None }),
+            Some(Ok("\x1b[4;0$y".to_string()))
+        );
+
+        // Terminals without the feature do not recognize its mode
+        let query = TerminalQuery::Mode { mode: 2026, private: true, state: Some(false) };
+        assert_eq!(query.reply(TerminalProfile::VT100), Some(b"\x1b[?2026;0$y".to_vec()));
+        assert_eq!(TerminalQuery::TerminalVersion.reply(TerminalProfile::VT100), None);
+    }
+
+    #[test]
+    fn test_osc_title_and_icon_name() {
+        let mut screen = ScreenState::new(20, 5);
+        assert_eq!(screen.title(), "");
+
+        screen.feed(b"\x1b]0;vim; main.rs\x07");
+        assert_eq!(screen.title(), "vim; main.rs");
+        assert_eq!(screen.icon_name(), "vim; main.rs");
+
+        screen.feed(b"\x1b]1;icon\x1b\\\x1b]2;title\x1b\\");
+        assert_eq!(screen.title(), "title");
+        assert_eq!(screen.icon_name(), "icon");
+
+        // Title sequences do not print anything
+        assert_eq!(screen.cursor_position(), (0, 0));
+    }
+
+    #[test]
+    fn test_title_stack_push_and_pop() {
+        let mut screen = ScreenState::new(20, 5);
+        screen.feed(b"\x1b]2;shell\x07\x1b[22;0t\x1b]2;editor\x07");
+        assert_eq!(screen.title_stack(), ["shell"]);
+        assert_eq!(screen.title(), "editor");
+
+        screen.feed(b"\x1b[23;0t");
+        assert_eq!(screen.title(), "shell");
+        assert!(screen.title_stack().is_empty());
+
+        // Popping an empty stack keeps the title
+        screen.feed(b"\x1b[23t");
+        assert_eq!(screen.title(), "shell");
+    }
+
+    #[test]
+    fn test_title_stack_is_bounded() {
+        let mut screen = ScreenState::new(20, 5);
+        for i in 0..MAX_TITLE_STACK + 2 {
+            screen.feed(format!("\x1b]2;{}\x07\x1b[22t", i).as_bytes());
+        }
+
+        assert_eq!(screen.title_stack().len(), MAX_TITLE_STACK);
+        assert_eq!(screen.title_stack()[0], "2");
+    }
+
+    #[test]
+    fn test_osc_7_cwd() {
+        let mut screen = ScreenState::new(20, 5);
+        assert_eq!(screen.cwd(), None);
+
+        screen.feed(b"\x1b]7;file://laptop/tmp/a%20b\x07");
+        assert_eq!(screen.cwd(), Some("/tmp/a b"));
+
+        screen.feed(b"\x1b]7;/srv\x07");
+        assert_eq!(screen.cwd(), Some("/srv"));
+    }
+
+    #[test]
+    fn test_iterm2_user_vars_and_current_dir() {
+        let mut screen = ScreenState::new(20, 5);
+        screen.feed(b"\x1b]1337;CurrentDir=/home/me\x07");
+        assert_eq!(screen.cwd(), Some("/home/me"));
+
+        // "dmFs" is base64 for "val"; invalid base64 is ignored
+        screen.feed(b"\x1b]1337;SetUserVar=key=dmFs\x07\x1b]1337;SetUserVar=bad=!!\x07");
+        assert_eq!(screen.user_var("key"), Some("val"));
+        assert_eq!(screen.user_var("bad"), None);
+
+        // Setting a variable again replaces it; RIS keeps the session state
+        screen.feed(b"\x1b]1337;SetUserVar=key=\x07\x1bc");
+        assert_eq!(screen.user_var("key"), Some(""));
+        assert_eq!(screen.cwd(), Some("/home/me"));
+        assert!(screen.iterm2_regions().is_empty());
+    }
+
+    #[test]
+    fn test_bell_and_notifications() {
+        let mut screen = ScreenState::new(20, 5);
+        screen.feed(b"a\x07b\x1b]9;Build finished\x07\x1b]9;4;1;50\x07");
+
+        assert_eq!(screen.row_contents(0).trim_end(), "ab");
+        assert_eq!(
+            screen.notifications(),
+            [
+                Notification::Bell,
+                Notification::Desktop { title: None, body: "Build finished".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_notifications_are_bounded() {
+        let mut screen = ScreenState::new(20, 5);
+        screen.feed(b"\x1b]9;first\x07");
+        for _ in 0..MAX_NOTIFICATIONS {
+            screen.feed(b"\x07");
+        }
+
+        assert_eq!(screen.notifications().len(), MAX_NOTIFICATIONS);
+        assert!(screen.notifications().iter().all(|n| *n == Notification::Bell));
+    }
+
+    #[test]
+    fn test_osc_8_hyperlink_cells() {
+        let mut screen = ScreenState::new(20, 2);
+        screen.feed(b"a\x1b]8;id=x:foo=bar;https://a.example/?q=1;2\x07bc\x1b]8;;\x07d");
+
+        assert!(screen.get_cell(0, 0).unwrap().hyperlink.is_none());
+        let link = screen.link_at(0, 1).unwrap();
+        assert_eq!(link.id.as_deref(), Some("x"));
+        assert_eq!(link.uri, "https://a.example/?q=1;2");
+        assert_eq!(screen.link_at(0, 2), Some(link));
+        assert!(screen.link_at(0, 3).is_none());
+    }
+
+    #[test]
+    fn test_hyperlink_survives_sgr_reset_and_wraps() {
+        let mut screen = ScreenState::new(5, 2);
+        screen.feed(b"\x1b]8;;https://x.y\x07\x1b[1mab\x1b[0mcdefg\x1b]8;;\x07");
+
+        let spans = screen.hyperlinks();
+        assert_eq!(spans.len(), 2);
+        assert_eq!((spans[0].row, spans[0].start_col, spans[0].end_col), (0, 0, 5));
+        assert_eq!(spans[0].text, "abcde");
+        assert_eq!((spans[1].row, spans[1].start_col, spans[1].end_col), (1, 0, 2));
+        assert_eq!(spans[1].text, "fg");
+        assert!(spans.iter().all(|span| span.uri == "https://x.y"));
+    }
+
+    #[test]
+    fn test_erasing_removes_hyperlink() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"\x1b]8;;https://x.y\x07link\x1b]8;;\x07\x1b[H\x1b[2X");
+
+        assert!(screen.link_at(0, 0).is_none());
+        assert_eq!(screen.hyperlinks()[0].text, "nk");
+    }
+
+    #[test]
+    fn test_osc_52_clipboard_writes() {
+        let mut screen = ScreenState::new(10, 1);
+        // "yank" to clipboard and primary, then an empty target, then a clear
+        screen.feed(b"\x1b]52;pc;eWFuaw==\x1b\\\x1b]52;;bmV4dA==\x07\x1b]52;p;!\x07");
+
+        assert_eq!(screen.clipboard(), Some("next"));
+        assert_eq!(screen.selection('p'), Some(""));
+        let selections: Vec<_> =
+            screen.clipboard_writes().iter().map(|w| w.selection.as_str()).collect();
+        assert_eq!(selections, ["pc", "c", "p"]);
+        assert_eq!(screen.clipboard_writes()[0].contents, "yank");
+        assert!(screen.take_queries().is_empty());
+    }
+
+    #[test]
+    fn test_clipboard_writes_are_bounded() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"\x1b]52;p;Zmlyc3Q=\x07");
+        for _ in 0..MAX_CLIPBOARD_WRITES {
+            screen.feed(b"\x1b]52;c;eWFuaw==\x07");
+        }
+
+        assert_eq!(screen.clipboard_writes().len(), MAX_CLIPBOARD_WRITES);
+        assert!(screen.clipboard_writes().iter().all(|w| w.selection == "c"));
+    }
+
+    #[test]
+    fn test_osc_52_clipboard_read_reply() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.set_clipboard("hi");
+        screen.feed(b"\x1b]52;c;?\x07");
+
+        let query = screen.take_queries().remove(0);
+        assert_eq!(
+            query,
+            TerminalQuery::Clipboard { selection: "c".into(), contents: "hi".into() }
+        );
+        assert_eq!(query.reply(TerminalProfile::Kitty), Some(b"\x1b]52;c;aGk=\x1b\\".to_vec()));
+        assert_eq!(query.reply(TerminalProfile::WezTerm), None);
+    }
+
+    #[test]
+    fn test_color_queries_report_palette() {
+        let palette = Palette::light().with_color(4, (0x12, 0x34, 0x56));
+        let mut screen = ScreenState::new(10, 1).with_palette(palette);
+        screen.feed(b"\x1b]10;?;?\x07\x1b]4;4;?;300;?;5;rgb:0/0/0\x07\x1b]11;?\x1b\\");
+
+        let queries = screen.take_queries();
+        assert_eq!(
+            queries,
+            vec![
+                TerminalQuery::ForegroundColor { rgb: (0, 0, 0) },
+                TerminalQuery::BackgroundColor { rgb: (255, 255, 255) },
+                TerminalQuery::PaletteColor { index: 4, rgb: (0x12, 0x34, 0x56) },
+                TerminalQuery::BackgroundColor { rgb: (255, 255, 255) },
+            ]
+        );
+        assert_eq!(
+            queries[2].reply(TerminalProfile::Xterm256),
+            Some(b"\x1b]4;4;rgb:1212/3434/5656\x1b\\".to_vec())
+        );
+        assert_eq!(queries[1].reply(TerminalProfile::VT100), None);
+    }
+
+    #[test]
+    fn test_default_palette_matches_xterm() {
+        let palette = Palette::default();
+        assert_eq!(palette.colors[9], (255, 0, 0));
+        assert_eq!(palette.colors[16], (0, 0, 0));
+        assert_eq!(palette.colors[67], (95, 135, 175));
+        assert_eq!(palette.colors[231], (255, 255, 255));
+        assert_eq!(palette.colors[244], (128, 128, 128));
+    }
+
+    #[test]
+    fn test_resolve_rgb() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"a\x1b[38;5;46;48;2;1;2;3mb\x1b[0;8mc");
+
+        let default = screen.resolve_rgb(0, 0).unwrap();
+        assert_eq!((default.fg, default.bg), ((229, 229, 229), (0, 0, 0)));
+        let colored = screen.resolve_rgb(0, 1).unwrap();
+        assert_eq!((colored.fg, colored.bg), ((0, 255, 0), (1, 2, 3)));
+        assert_eq!(screen.resolve_rgb(0, 2).unwrap().fg, (0, 0, 0));
+        assert_eq!(screen.resolve_rgb(1, 0), None);
     }
 
-    /// Checks if a Sixel region exists at the given position.
-    ///
-    /// This method checks if any Sixel region has its starting position
-    /// at the exact (row, col) coordinates provided.
-    ///
-    /// # Arguments
-    ///
-    /// * `row` - Row to check (0-indexed)
-    /// * `col` - Column to check (0-indexed)
-    ///
-    /// # Returns
-    ///
-    /// `true` if a Sixel region starts at the given position, `false` otherwise.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let mut screen = ScreenState::new(80, 24);
-    ///
-    /// // Render a Sixel at position (5, 10) - ESC[5;10H moves to row 5, col 10 (1-based)
-    /// screen.feed(b"\x1b[5;10H"); // Move cursor
-    /// screen.feed(b"\x1bPq"); // Start Sixel
-    /// screen.feed(b"\"1;1;100;50#0~"); // Raster + data
-    /// screen.feed(b"\x1b\\"); // End Sixel
-    ///
-    /// // Check for Sixel at 0-based coordinates (4, 9)
-    /// assert!(screen.has_sixel_at(4, 9));
-    /// assert!(!screen.has_sixel_at(0, 0));
-    /// ```
-    pub fn has_sixel_at(&self, row: u16, col: u16) -> bool {
-        self.state
-            .sixel_regions
-            .iter()
-            .any(|region| region.start_row == row && region.start_col == col)
+    #[test]
+    fn test_tab_stops_default_and_margin() {
+        let mut screen = ScreenState::new(20, 1);
+        assert_eq!(screen.tab_stops(), vec![8, 16]);
+
+        screen.feed(b"\t\t\t");
+        assert_eq!(screen.cursor_position(), (0, 19));
+        screen.feed(b"\x1b[Z");
+        assert_eq!(screen.cursor_position(), (0, 16));
+        screen.feed(b"\x1b[5Z");
+        assert_eq!(screen.cursor_position(), (0, 0));
     }
 
-    /// Returns all Kitty graphics regions currently on screen.
-    ///
-    /// This method provides access to all Kitty graphics that have been rendered
-    /// via APC (Application Program Command) sequences. Each region includes position
-    /// and dimension information.
-    ///
-    /// # Returns
-    ///
-    /// A slice of [`KittyRegion`] containing all detected Kitty graphics.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let mut screen = ScreenState::new(80, 24);
-    /// // ... render some Kitty graphics ...
-    ///
-    /// let regions = screen.kitty_regions();
-    /// for (i, region) in regions.iter().enumerate() {
-    ///     println!(
-    ///         "Kitty region {}: position ({}, {}), size {}x{}",
-    ///         i, region.start_row, region.start_col, region.width, region.height
-    ///     );
-    /// }
-    /// ```
-    pub fn kitty_regions(&self) -> &[KittyRegion] {
-        &self.state.kitty_regions
+    #[test]
+    fn test_tab_set_clear_and_cht() {
+        let mut screen = ScreenState::new(40, 1);
+        // Set a stop at column 3, clear the one at 16
+        screen.feed(b"\x1b[1;4H\x1bH\x1b[1;17H\x1b[g\r\x1b[2I");
+        assert_eq!(screen.tab_stops(), vec![3, 8, 24, 32]);
+        assert_eq!(screen.cursor_position(), (0, 8));
+
+        screen.feed(b"\x1b[I");
+        assert_eq!(screen.cursor_position(), (0, 24));
+        screen.feed(b"\x1b[3g\r\t");
+        assert!(screen.tab_stops().is_empty());
+        assert_eq!(screen.cursor_position(), (0, 39));
     }
 
-    /// Returns a mutable reference to the Kitty regions.
-    ///
-    /// This is primarily intended for testing purposes.
-    ///
-    /// # Returns
-    ///
-    /// A mutable reference to the vector of Kitty regions.
-    pub fn kitty_regions_mut(&mut self) -> &mut Vec<KittyRegion> {
-        &mut self.state.kitty_regions
+    #[test]
+    fn test_dec_special_graphics_in_g0() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"\x1b(0lqk\x1b(Bx`a~");
+
+        assert_eq!(screen.row_contents(0), "┌─┐x`a~   ");
     }
 
-    /// Returns all iTerm2 inline image regions currently on screen.
-    ///
-    /// This method provides access to all iTerm2 inline images that have been rendered
-    /// via OSC 1337 sequences. Each region includes position and dimension information.
-    ///
-    /// # Returns
-    ///
-    /// A slice of [`ITerm2Region`] containing all detected iTerm2 inline images.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let mut screen = ScreenState::new(80, 24);
-    /// // ... render some iTerm2 inline images ...
-    ///
-    /// let regions = screen.iterm2_regions();
-    /// for (i, region) in regions.iter().enumerate() {
-    ///     println!(
-    ///         "iTerm2 region {}: position ({}, {}), size {}x{}",
-    ///         i, region.start_row, region.start_col, region.width, region.height
-    ///     );
-    /// }
-    /// ```
-    pub fn iterm2_regions(&self) -> &[ITerm2Region] {
-        &self.state.iterm2_regions
+    #[test]
+    fn test_shift_out_invokes_g1() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"\x1b)0x\x0ex\x0fx\x1b*A\x1bN#\x1b(A#");
+
+        assert_eq!(screen.row_contents(0), "x│x££     ");
     }
 
-    /// Returns a mutable reference to the iTerm2 regions.
-    ///
-    /// This is primarily intended for testing purposes.
-    ///
-    /// # Returns
-    ///
-    /// A mutable reference to the vector of iTerm2 regions.
-    pub fn iterm2_regions_mut(&mut self) -> &mut Vec<ITerm2Region> {
-        &mut self.state.iterm2_regions
+    #[test]
+    fn test_charsets_saved_with_cursor() {
+        let mut screen = ScreenState::new(10, 1);
+        screen.feed(b"\x1b(0\x1b7\x1b(Bq\x1b8q");
+
+        assert_eq!(screen.row_contents(0), "─         ");
     }
 
-    /// Returns the screen contents for debugging purposes.
-    ///
-    /// This is currently an alias for [`contents()`](Self::contents), but may
-    /// include additional debug information in the future.
-    ///
-    /// # Returns
-    ///
-    /// A string containing the screen contents.
-    pub fn debug_contents(&self) -> String {
-        self.contents()
+    #[test]
+    fn test_full_reset() {
+        let mut screen = ScreenState::new(20, 3);
+        screen.feed(b"\x1b]2;app\x07one\r\ntwo\r\nthree\r\nfour");
+        screen.feed(b"\x1b[2;3r\x1b[3g\x1b(0\x1b[?25l\x1b[?1000h\x1b[1;31m");
+        screen.feed(b"\x1bPq\"1;1;10;10#0~\x1b\\\x1b[?1049hfull screen");
+        screen.feed(b"\x1bc");
+
+        assert!(!screen.is_alternate_screen());
+        assert_eq!(screen.contents().trim(), "");
+        assert_eq!(screen.cursor_position(), (0, 0));
+        assert!(screen.sixel_regions().is_empty());
+        assert_eq!(screen.modes(), &TerminalModes::default());
+        assert_eq!(screen.tab_stops(), vec![8, 16]);
+
+        // Default charset and attributes
+        screen.feed(b"q");
+        assert_eq!(screen.get_cell(0, 0).unwrap().c, 'q');
+        assert_eq!(screen.get_cell(0, 0).unwrap().fg, Color::Default);
+
+        // Scrolling uses the whole screen, and the scrollback survives
+        screen.feed(b"\x1b[3;1H\n");
+        assert_eq!(screen.scrollback().len(), 2);
+        assert_eq!(screen.title(), "app");
+        assert_eq!(screen.take_resets(), vec![TerminalReset::Full]);
     }
 
-    /// Checks if the screen contains the specified text.
-    ///
-    /// This is a convenience method that searches the entire screen contents
-    /// for the given substring. It's useful for simple text-based assertions
-    /// in tests.
-    ///
-    /// # Arguments
-    ///
-    /// * `text` - Text to search for
-    ///
-    /// # Returns
-    ///
-    /// `true` if the text appears anywhere on the screen, `false` otherwise.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use ratatui_testlib::ScreenState;
-    ///
-    /// let mut screen = ScreenState::new(80, 24);
-    /// screen.feed(b"Welcome to the application");
-    ///
-    /// assert!(screen.contains("Welcome"));
-    /// assert!(screen.contains("application"));
-    /// assert!(!screen.contains("goodbye"));
-    /// ```
-    pub fn contains(&self, text: &str) -> bool {
-        self.contents().contains(text)
+    #[test]
+    fn test_soft_reset_keeps_contents() {
+        let mut screen = ScreenState::new(10, 4);
+        screen.feed(b"\x1b[2;3r\x1b[4h\x1b[?7l\x1b[?25l\x1b[7m\x1b[Htext\x1b(0\x1b7\x1b[!p");
+
+        assert_eq!(screen.row_contents(0), "text      ");
+        assert_eq!(screen.cursor_position(), (0, 4));
+        assert!(screen.modes().autowrap && screen.cursor_visible());
+        assert!(!screen.modes().insert_mode);
+
+        // ASCII, normal rendition, and the saved cursor is back at home
+        screen.feed(b"q\x1b8");
+        assert_eq!(screen.get_cell(0, 4).unwrap().c, 'q');
+        assert!(!screen.get_cell(0, 4).unwrap().reverse);
+        assert_eq!(screen.cursor_position(), (0, 0));
+
+        // The scroll region covers the whole screen again
+        screen.feed(b"\x1b[4;1H\n");
+        assert_eq!(screen.row_contents(0).trim(), "");
+        assert_eq!(screen.take_resets(), vec![TerminalReset::Soft]);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_resize_reflows_narrower() {
+        let mut screen = ScreenState::new(10, 3);
+        screen.feed(b"abcdefghij\r\nxy");
+
+        screen.resize(4, 3);
+        assert_eq!(screen.scrollback(), vec!["abcd"]);
+        assert_eq!(screen.row_contents(0), "efgh");
+        assert_eq!(screen.row_contents(1), "ij  ");
+        assert!(screen.is_wrapped(0) && !screen.is_wrapped(1));
+        assert_eq!(screen.row_contents(2), "xy  ");
+        assert_eq!(screen.cursor_position(), (2, 2));
+    }
 
     #[test]
-    fn test_create_screen() {
-        let screen = ScreenState::new(80, 24);
-        assert_eq!(screen.size(), (80, 24));
+    fn test_resize_reflows_wider_from_scrollback() {
+        let mut screen = ScreenState::new(4, 2);
+        screen.feed(b"abcdefgh\r\nz");
+        assert_eq!(screen.scrollback(), vec!["abcd"]);
+
+        screen.resize(8, 3);
+        assert!(screen.scrollback().is_empty());
+        assert_eq!(screen.logical_lines(), vec!["abcdefgh", "z", ""]);
+        assert_eq!(screen.cursor_position(), (1, 1));
     }
 
     #[test]
-    fn test_feed_simple_text() {
-        let mut screen = ScreenState::new(80, 24);
-        screen.feed(b"Hello, World!");
-        assert!(screen.contents().contains("Hello, World!"));
+    fn test_resize_moves_wide_char_to_next_row() {
+        let mut screen = ScreenState::new(6, 2);
+        screen.feed("ab世界".as_bytes());
+
+        // Neither wide character fits in the last column of its row
+        screen.resize(3, 2);
+        assert_eq!(screen.scrollback(), vec!["ab "]);
+        assert_eq!(screen.row_contents(0), "世 ");
+        assert_eq!(screen.row_contents(1), "界 ");
+        assert!(screen.get_cell(1, 1).unwrap().is_wide_continuation());
+        // The cursor stays on the last column of 界
+        assert_eq!(screen.cursor_position(), (1, 1));
     }
 
     #[test]
-    fn test_cursor_position() {
-        let mut screen = ScreenState::new(80, 24);
+    fn test_resize_with_wide_chars_is_reversible() {
+        let mut screen = ScreenState::new(5, 3);
+        screen.feed("abcd日本".as_bytes());
+
+        for _ in 0..2 {
+            screen.resize(4, 3);
+            assert_eq!(screen.logical_lines()[0], "abcd日本");
+            screen.resize(10, 3);
+            assert_eq!(screen.logical_lines()[0], "abcd日本");
+            assert_eq!(screen.row_contents(0), "abcd日本  ");
+        }
+    }
 
-        // Initial position
-        assert_eq!(screen.cursor_position(), (0, 0));
+    #[test]
+    fn test_resize_truncate_mode() {
+        let mut screen = ScreenState::new(8, 2).with_resize_mode(ResizeMode::Truncate);
+        screen.feed(b"abcdefgh\r\n12345\x1b[1;7H");
+
+        screen.resize(4, 2);
+        assert_eq!(screen.row_contents(0), "abcd");
+        assert_eq!(screen.row_contents(1), "1234");
+        assert_eq!(screen.cursor_position(), (0, 3));
+        assert_eq!(screen.tab_stops(), Vec::<u16>::new());
+    }
 
-        // Move cursor using CSI sequence (ESC [ 5 ; 10 H = row 5, col 10)
-        screen.feed(b"\x1b[5;10H");
-        let (row, col) = screen.cursor_position();
+    #[test]
+    fn test_resize_keeps_primary_under_alternate_screen() {
+        let mut screen = ScreenState::new(6, 2);
+        screen.feed(b"hello world\x1b[?1049h\x1b[2;1Hmenu!!");
 
-        // CSI uses 1-based, we convert to 0-based
-        assert_eq!(row, 4); // 5-1 = 4
-        assert_eq!(col, 9); // 10-1 = 9
+        screen.resize(12, 2);
+        assert_eq!(screen.row_contents(1), "menu!!      ");
+
+        screen.feed(b"\x1b[?1049l");
+        assert_eq!(screen.row_contents(0), "hello world ");
+        assert_eq!(screen.cursor_position(), (0, 11));
     }
 
     #[test]
-    fn test_text_at() {
-        let mut screen = ScreenState::new(80, 24);
-        screen.feed(b"Test");
+    fn test_prompt_markers_follow_scrolling() {
+        let mut screen = ScreenState::new(10, 2).with_scrollback_limit(2);
+        screen.feed(b"\x1b]133;A\x07$ x\r\n1\r\n");
+        assert_eq!(screen.prompt_markers()[0].line, 0);
+
+        // Scrolling moves the prompt into the scrollback without changing its line
+        screen.feed(b"2\r\n");
+        assert_eq!(screen.scrollback(), vec!["$ x       ", "1         "]);
+        assert_eq!(screen.prompt_markers()[0].line, 0);
+
+        // Trimming the scrollback drops the line and the marker with it
+        screen.feed(b"3\r\n\x1b]133;A\x07$ ");
+        let markers = screen.prompt_markers();
+        assert_eq!(markers.len(), 1);
+        assert_eq!(markers[0].line, 3);
+    }
 
-        assert_eq!(screen.text_at(0, 0), Some('T'));
-        assert_eq!(screen.text_at(0, 1), Some('e'));
-        assert_eq!(screen.text_at(0, 2), Some('s'));
-        assert_eq!(screen.text_at(0, 3), Some('t'));
-        assert_eq!(screen.text_at(0, 4), Some(' '));
-        assert_eq!(screen.text_at(100, 100), None);
+    #[test]
+    fn test_prompt_markers_exit_codes() {
+        let mut screen = ScreenState::new(10, 3);
+        screen.feed(b"\x1b]133;D;127\x07\x1b]133;D\x07\x1b]133;D;aid=1\x07\x1b]133;X\x07");
+
+        let codes: Vec<_> = screen.prompt_markers().iter().map(|m| m.exit_code).collect();
+        assert_eq!(codes, vec![Some(127), None, None]);
+    }
+
+    #[test]
+    fn test_prompt_markers_survive_reflow_and_clear() {
+        let mut screen = ScreenState::new(6, 3);
+        screen.feed(b"abcd\x1b]133;B\x07efgh");
+        assert_eq!(screen.prompt_markers()[0].line, 0);
+        assert_eq!(screen.prompt_markers()[0].command.as_deref(), Some("efgh"));
+
+        // Narrower, the mark moves with its cell onto the second row
+        screen.resize(3, 4);
+        let marker = &screen.prompt_markers()[0];
+        assert_eq!(marker.line, 1);
+        assert_eq!(marker.command.as_deref(), Some("efgh"));
+
+        // Erasing the screen removes its markers
+        screen.feed(b"\x1b[2J");
+        assert!(screen.prompt_markers().is_empty());
     }
 
     #[test]
@@ -1589,22 +6596,27 @@ mod tests {
 
     #[test]
     fn test_pixels_to_cells() {
+        let mut state = TerminalState::new(80, 24);
         // Standard conversions (8 pixels/col, 6 pixels/row)
-        assert_eq!(TerminalState::pixels_to_cells(80, 60), (10, 10));
-        assert_eq!(TerminalState::pixels_to_cells(0, 0), (0, 0));
+        assert_eq!(state.pixels_to_cells(80, 60), (10, 10));
+        assert_eq!(state.pixels_to_cells(0, 0), (0, 0));
 
         // Exact multiples
-        assert_eq!(TerminalState::pixels_to_cells(800, 600), (100, 100));
-        assert_eq!(TerminalState::pixels_to_cells(16, 12), (2, 2));
+        assert_eq!(state.pixels_to_cells(800, 600), (100, 100));
+        assert_eq!(state.pixels_to_cells(16, 12), (2, 2));
 
         // Fractional cells (should round up)
-        assert_eq!(TerminalState::pixels_to_cells(81, 61), (11, 11));
-        assert_eq!(TerminalState::pixels_to_cells(100, 50), (13, 9));
-        assert_eq!(TerminalState::pixels_to_cells(1, 1), (1, 1));
+        assert_eq!(state.pixels_to_cells(81, 61), (11, 11));
+        assert_eq!(state.pixels_to_cells(100, 50), (13, 9));
+        assert_eq!(state.pixels_to_cells(1, 1), (1, 1));
 
         // Typical Sixel dimensions from real use
-        assert_eq!(TerminalState::pixels_to_cells(640, 480), (80, 80));
-        assert_eq!(TerminalState::pixels_to_cells(320, 240), (40, 40));
+        assert_eq!(state.pixels_to_cells(640, 480), (80, 80));
+        assert_eq!(state.pixels_to_cells(320, 240), (40, 40));
+
+        // Configured cell size
+        state.cell_size = (10, 20);
+        assert_eq!(state.pixels_to_cells(100, 41), (10, 3));
     }
 
     #[test]
@@ -1690,4 +6702,282 @@ mod tests {
         assert_eq!(region.width, 200);
         assert_eq!(region.height, 150);
     }
+
+    #[test]
+    fn test_kitty_chunked_transmit_and_display() {
+        let mut screen = ScreenState::new(80, 24);
+
+        // 16x12 RGB image sent in three chunks, displayed at row 1, column 2
+        screen.feed(b"\x1b[2;3H\x1b_Ga=T,f=24,s=16,v=12,i=5,m=1;AAAA\x1b\\");
+        screen.feed(b"\x1b_Gm=1;AAAA\x1b\\");
+        assert!(screen.kitty_images().is_empty(), "image is stored after the last chunk");
+        screen.feed(b"\x1b_Gm=0;AAAA\x1b\\");
+
+        let images = screen.kitty_images();
+        assert_eq!(images.len(), 1);
+        assert_eq!((images[0].id, images[0].format), (5, 24));
+        assert_eq!((images[0].width, images[0].height), (16, 12));
+        assert_eq!((images[0].chunks, images[0].data.len()), (3, 9));
+
+        let placements = screen.kitty_placements();
+        assert_eq!(placements.len(), 1);
+        assert_eq!(placements[0].bounds(), (1, 2, 2, 2));
+        assert_eq!(screen.kitty_regions().len(), 1);
+        assert_eq!(screen.kitty_regions()[0].data, b"Ga=T,f=24,s=16,v=12,i=5,m=1;AAAAAAAAAAAA");
+    }
+
+    #[test]
+    fn test_kitty_placements_by_id_and_number() {
+        let mut screen = ScreenState::new(80, 24);
+
+        // Without i=, images get distinct ids; I= lets the client refer to them
+        screen.feed(b"\x1b_Ga=t,I=9,s=8,v=6;AAAA\x1b\\");
+        screen.feed(b"\x1b_Ga=t,s=8,v=6;AAAA\x1b\\");
+        let ids: Vec<u32> = screen.kitty_images().iter().map(|image| image.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(screen.kitty_images()[0].number, Some(9));
+
+        screen.feed(b"\x1b[5;5H\x1b_Ga=p,I=9,p=1\x1b\\");
+        screen.feed(b"\x1b[6;6H\x1b_Ga=p,i=2,c=4,r=3,z=2\x1b\\");
+        // Same placement id again: moves the placement instead of adding one
+        screen.feed(b"\x1b[7;7H\x1b_Ga=p,i=1,p=1,x=4,w=4,X=6\x1b\\");
+        // Unknown images are not placed
+        screen.feed(b"\x1b_Ga=p,i=42\x1b\\");
+
+        let placements = screen.kitty_placements();
+        assert_eq!(placements.len(), 2);
+        assert_eq!((placements[0].image_id, placements[0].bounds()), (2, (5, 5, 4, 3)));
+        assert_eq!(placements[0].z_index, 2);
+        assert_eq!((placements[1].image_id, placements[1].bounds()), (1, (6, 6, 2, 1)));
+
+        // Retransmitting an image replaces it and drops its placements
+        screen.feed(b"\x1b_Ga=t,i=2,s=1,v=1;AAAA\x1b\\");
+        assert_eq!(screen.kitty_images().len(), 2);
+        assert_eq!(screen.kitty_image(2).map(|image| image.width), Some(1));
+        assert_eq!(screen.kitty_placements().len(), 1);
+    }
+
+    #[test]
+    fn test_kitty_placements_follow_scrolling() {
+        let mut screen = ScreenState::new(20, 6);
+        let rows = |screen: &ScreenState| -> Vec<(u32, u16)> {
+            screen.kitty_placements().iter().map(|p| (p.image_id, p.row)).collect()
+        };
+        screen.feed(b"\x1b[3;1H\x1b_Ga=T,i=1,c=2,r=2\x1b\\");
+        screen.feed(b"\x1b[5;1H\x1b_Ga=T,i=2,c=2,r=2\x1b\\");
+
+        // Scrolling moves placements with the text; off the top they are gone
+        screen.feed(b"\x1b[6;1H\n\n");
+        assert_eq!(rows(&screen), vec![(1, 0), (2, 2)]);
+        screen.feed(b"\n");
+        assert_eq!(rows(&screen), vec![(2, 1)]);
+        screen.feed(b"\x1b[2T");
+        assert_eq!(rows(&screen), vec![(2, 3)]);
+
+        // Each screen buffer has its own placements
+        screen.feed(b"\x1b[?1049h");
+        assert!(screen.kitty_placements().is_empty());
+        screen.feed(b"\x1b[1;1H\x1b_Ga=T,i=3,c=2,r=2\x1b\\");
+        assert_eq!(rows(&screen), vec![(3, 0)]);
+        screen.feed(b"\x1b[?1049l");
+        assert_eq!(rows(&screen), vec![(2, 3)]);
+
+        // Clearing the screen removes them
+        screen.feed(b"\x1b[2J");
+        assert!(screen.kitty_placements().is_empty());
+    }
+
+    #[test]
+    fn test_kitty_deletions() {
+        let mut screen = ScreenState::new(80, 24);
+        let setup = |screen: &mut ScreenState| {
+            screen.feed(b"\x1b[2J\x1b_Ga=d,d=A\x1b\\");
+            screen.feed(b"\x1b[1;1H\x1b_Ga=T,i=1,c=2,r=2\x1b\\");
+            screen.feed(b"\x1b[1;11H\x1b_Ga=T,i=2,c=2,r=2,z=5\x1b\\");
+            screen.feed(b"\x1b[11;11H\x1b_Ga=T,i=3,c=2,r=2\x1b\\");
+            screen.feed(b"\x1b_Ga=t,i=4\x1b\\");
+        };
+        let placed = |screen: &ScreenState| -> Vec<u32> {
+            screen.kitty_placements().iter().map(|p| p.image_id).collect()
+        };
+        let stored = |screen: &ScreenState| -> Vec<u32> {
+            screen.kitty_images().iter().map(|image| image.id).collect()
+        };
+
+        setup(&mut screen);
+        screen.feed(b"\x1b_Ga=d\x1b\\");
+        assert!(placed(&screen).is_empty());
+        assert_eq!(stored(&screen), vec![1, 2, 3, 4]);
+
+        setup(&mut screen);
+        screen.feed(b"\x1b_Ga=d,d=I,i=2\x1b\\\x1b_Ga=d,d=I,i=4\x1b\\");
+        assert_eq!(placed(&screen), vec![1, 3]);
+        assert_eq!(stored(&screen), vec![1, 3]);
+
+        setup(&mut screen);
+        screen.feed(b"\x1b[12;12H\x1b_Ga=d,d=c\x1b\\");
+        assert_eq!(placed(&screen), vec![1, 2]);
+
+        setup(&mut screen);
+        screen.feed(b"\x1b_Ga=d,d=p,x=2,y=2\x1b\\\x1b_Ga=d,d=q,x=11,y=1,z=4\x1b\\");
+        assert_eq!(placed(&screen), vec![2, 3]);
+
+        setup(&mut screen);
+        screen.feed(b"\x1b_Ga=d,d=x,x=12\x1b\\");
+        assert_eq!(placed(&screen), vec![1]);
+
+        setup(&mut screen);
+        screen.feed(b"\x1b_Ga=d,d=Y,y=1\x1b\\");
+        assert_eq!(placed(&screen), vec![3]);
+        assert_eq!(stored(&screen), vec![3, 4]);
+
+        setup(&mut screen);
+        screen.feed(b"\x1b_Ga=d,d=z,z=5\x1b\\");
+        assert_eq!(placed(&screen), vec![1, 3]);
+
+        setup(&mut screen);
+        screen.feed(b"\x1b_Ga=d,d=R,x=2,y=4\x1b\\");
+        assert_eq!(placed(&screen), vec![1]);
+        assert_eq!(stored(&screen), vec![1]);
+    }
+
+    #[test]
+    fn test_kitty_responses() {
+        let mut screen = ScreenState::new(80, 24);
+        let response = |image_id, image_number, placement_id, status: &str| {
+            TerminalQuery::KittyGraphics {
+                image_id,
+                image_number,
+                placement_id,
+                status: status.to_string(),
+            }
+        };
+
+        screen.feed(b"\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\");
+        let probe = screen.take_queries();
+        assert_eq!(probe, vec![response(31, 0, 0, "OK")]);
+        assert!(screen.kitty_images().is_empty());
+        assert_eq!(probe[0].reply(TerminalProfile::Kitty), Some(b"\x1b_Gi=31;OK\x1b\\".to_vec()));
+        assert_eq!(probe[0].reply(TerminalProfile::Xterm256), None);
+
+        // Transmissions with a number report the assigned id
+        screen.feed(b"\x1b_Ga=T,I=7,p=3,s=1,v=1;AAAA\x1b\\");
+        let query = screen.take_queries().remove(0);
+        assert_eq!(query, response(1, 7, 3, "OK"));
+        let reply = query.reply(TerminalProfile::Kitty);
+        assert_eq!(reply, Some(b"\x1b_Gi=1,I=7,p=3;OK\x1b\\".to_vec()));
+
+        // No id, or quiet mode: no response. q=1 still reports errors.
+        screen.feed(b"\x1b_Ga=t;AAAA\x1b\\\x1b_Ga=t,i=2,q=1;AAAA\x1b\\");
+        screen.feed(b"\x1b_Ga=p,i=9,q=2\x1b\\");
+        assert!(screen.take_queries().is_empty());
+        screen.feed(b"\x1b_Ga=p,i=9,q=1\x1b\\");
+        match &screen.take_queries()[..] {
+            [TerminalQuery::KittyGraphics { image_id: 9, status, .. }] => {
+                assert!(status.starts_with("ENOENT:"), "{status}");
+            }
+            queries => panic!("unexpected queries: {queries:?}"),
+        }
+    }
+
+    #[test]
+    fn test_kitty_virtual_placements_and_placeholders() {
+        let mut screen = ScreenState::new(80, 24);
+        screen.feed(b"\x1b[5;5H\x1b_Ga=T,U=1,i=16777218,c=3,r=2,q=2;AAAA\x1b\\");
+
+        let placement = &screen.kitty_placements()[0];
+        assert!(placement.virtual_placement);
+        assert_eq!(placement.bounds(), (0, 0, 3, 2));
+        assert!(screen.kitty_regions().is_empty(), "virtual placements are not drawn");
+        assert_eq!(screen.cursor_position(), (4, 4));
+
+        // Image id 0x01000002: the lower 24 bits (2) in the truecolor
+        // foreground and the high byte (1, U+030D) as the first cell's third
+        // diacritic. Placement 5 is in the underline color.
+        let first = "\u{10EEEE}\u{305}\u{305}\u{30D}";
+        let second_row = "\u{10EEEE}\u{30D}\u{10EEEE}\u{30D}\u{30D}\u{10EEEE}";
+        screen.feed(
+            format!(
+                "\x1b[1;1H\x1b[38;2;0;0;2m\x1b[58;5;5m{first}\u{10EEEE}\u{10EEEE}\r\n\
+                 {second_row}\x1b[39;59m\u{10EEEE}\u{305}"
+            )
+            .as_bytes(),
+        );
+
+        let cells: Vec<_> = screen
+            .kitty_placeholders()
+            .iter()
+            .map(|p| (p.row, p.col, p.image_id, p.placement_id, p.image_row, p.image_col))
+            .collect();
+        assert_eq!(
+            cells,
+            vec![
+                (0, 0, 0x0100_0002, 5, 0, 0),
+                (0, 1, 0x0100_0002, 5, 0, 1),
+                (0, 2, 0x0100_0002, 5, 0, 2),
+                // Row diacritic only: the id high byte is not inherited
+                // across rows, and columns restart at 0
+                (1, 0, 2, 5, 1, 0),
+                (1, 1, 2, 5, 1, 1),
+                (1, 2, 2, 5, 1, 2),
+            ]
+        );
+        // A placeholder without a foreground color does not name an image
+        assert_eq!(screen.kitty_placeholder_at(1, 3), None);
+        assert_eq!(screen.kitty_placeholder_at(0, 1).map(|p| p.image_col), Some(1));
+
+        // Screen-based deletions leave virtual placements alone
+        screen.feed(b"\x1b_Ga=d,d=a\x1b\\");
+        assert_eq!(screen.kitty_placements().len(), 1);
+        screen.feed(b"\x1b_Ga=d,d=i,i=16777218\x1b\\");
+        assert!(screen.kitty_placements().is_empty());
+    }
+
+    #[test]
+    fn test_iterm2_image_sizes() {
+        let mut screen = ScreenState::new(80, 24);
+        let mut footprint = |args: &str| {
+            screen.feed(format!("\x1b]1337;File={args}:SGVsbG8=\x07").as_bytes());
+            let region = screen.iterm2_regions().last().expect("region").clone();
+            (region.width, region.height)
+        };
+
+        assert_eq!(footprint("inline=1;width=10;height=3"), (10, 3));
+        assert_eq!(footprint("inline=1;width=20px;height=13px"), (3, 3));
+        assert_eq!(footprint("inline=1;width=25%;height=50%"), (20, 12));
+        // Without a decodable image, auto dimensions are unknown
+        assert_eq!(footprint("inline=1;width=auto;height=2"), (0, 2));
+        assert_eq!(footprint("inline=1;width=bogus"), (0, 0));
+
+        // Downloads and other OSC 1337 commands are not images
+        let count = screen.iterm2_regions().len();
+        screen.feed(b"\x1b]1337;File=name=eC5iaW4=;size=5:SGVsbG8=\x07");
+        screen.feed(b"\x1b]1337;File=inline=0:SGVsbG8=\x07\x1b]1337;StealFocus\x07");
+        assert_eq!(screen.iterm2_regions().len(), count);
+    }
+
+    #[cfg(feature = "sixel-image")]
+    #[test]
+    fn test_iterm2_decodes_embedded_image() {
+        let image = image::RgbaImage::from_pixel(32, 12, image::Rgba([255, 0, 0, 255]));
+        let mut png = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let payload = BASE64.encode(&png);
+
+        let mut screen = ScreenState::new(80, 24);
+        for args in [
+            "inline=1",
+            "inline=1;width=8",
+            "inline=1;width=8;preserveAspectRatio=0",
+            "inline=1;height=24px",
+        ] {
+            screen.feed(format!("\x1b]1337;File={args}:{payload}\x07").as_bytes());
+        }
+
+        let sizes: Vec<_> = screen.iterm2_regions().iter().map(|r| (r.width, r.height)).collect();
+        assert_eq!(sizes, [(4, 2), (8, 4), (8, 2), (8, 4)]);
+        assert_eq!(screen.iterm2_regions()[0].to_image().unwrap(), image);
+    }
 }
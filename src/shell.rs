@@ -0,0 +1,668 @@
+//! Interactive shell sessions for command-level testing.
+//!
+//! [`ShellSession`] runs bash, zsh or sh in a [`TestTerminal`] with a known
+//! prompt and, by default, OSC 133 shell integration hooks. Each command run
+//! through it returns a [`CommandOutput`] with the text the command printed,
+//! its exit code and how long it took, so tests no longer have to guess where
+//! a command's output starts and ends on the screen. The full [`ScreenState`]
+//! stays available for assertions on colors, cursor position or TUI programs
+//! started from the shell.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use ratatui_testlib::shell::{Shell, ShellSession};
+//!
+//! # fn test() -> ratatui_testlib::Result<()> {
+//! let mut session = ShellSession::new(Shell::Bash)?;
+//!
+//! let output = session.run("echo hello")?;
+//! assert_eq!(output.text, "hello");
+//! assert_eq!(output.exit_code, Some(0));
+//!
+//! let output = session.run("ls /nonexistent")?;
+//! assert_ne!(output.exit_code, Some(0));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use portable_pty::CommandBuilder;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    error::{Result, TermTestError},
+    navigation::PromptMarkerType,
+    pty::TestTerminal,
+    screen::ScreenState,
+    terminal_profiles::TerminalProfile,
+};
+
+/// Default time a command may run before [`ShellSession::run`] gives up.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default prompt shown by the shell.
+const DEFAULT_PROMPT: &str = "$ ";
+
+/// Scrollback kept by the session, so the output of long commands stays
+/// available.
+const SESSION_SCROLLBACK_LIMIT: usize = 100_000;
+
+/// OSC 133 command start marker, which ends every prompt when the hooks are
+/// installed.
+const COMMAND_START: &[u8] = b"\x1b]133;B";
+
+/// A shell that [`ShellSession`] can run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    /// GNU Bash, started without profile or rc files.
+    Bash,
+    /// Zsh, started without rc files.
+    Zsh,
+    /// The system POSIX shell (`sh`), started without `$ENV`.
+    Sh,
+}
+
+impl Shell {
+    /// Returns the program name used to start the shell.
+    pub fn program(&self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Sh => "sh",
+        }
+    }
+
+    /// Arguments that start the shell interactively without user configuration.
+    fn args(&self) -> &'static [&'static str] {
+        match self {
+            Shell::Bash => &["--norc", "--noprofile", "-i"],
+            Shell::Zsh => &["-f", "-i"],
+            Shell::Sh => &["-i"],
+        }
+    }
+
+    /// Wraps escape sequences in a prompt so line editing does not count them
+    /// as printed columns.
+    fn invisible(&self, text: &str) -> String {
+        match self {
+            Shell::Bash => format!("\\[{}\\]", text),
+            Shell::Zsh => format!("%{{{}%}}", text),
+            Shell::Sh => text.to_string(),
+        }
+    }
+
+    /// Prompt expansion giving the exit code of the last command.
+    fn exit_code(&self) -> &'static str {
+        match self {
+            Shell::Zsh => "%?",
+            Shell::Bash | Shell::Sh => "$?",
+        }
+    }
+
+    /// Builds `PS1` for `prompt`, with OSC 133 hooks when `hooks` is set.
+    ///
+    /// Each prompt reports the previous command's exit code (`D`), then marks
+    /// the prompt (`A`) and the start of the command line (`B`).
+    fn ps1(&self, prompt: &str, hooks: bool) -> String {
+        if !hooks {
+            return prompt.to_string();
+        }
+        let before = format!("\x1b]133;D;{}\x07\x1b]133;A\x07", self.exit_code());
+        format!("{}{}{}", self.invisible(&before), prompt, self.invisible("\x1b]133;B\x07"))
+    }
+}
+
+/// The result of a command run with [`ShellSession::run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandOutput {
+    /// Text printed by the command, as shown on the screen between the
+    /// command line and the next prompt. Trailing whitespace is removed from
+    /// each line and from the end.
+    pub text: String,
+    /// Exit code reported by the shell, or `None` when the session runs
+    /// without OSC 133 hooks.
+    pub exit_code: Option<i32>,
+    /// Time from sending the command until the next prompt was seen.
+    pub duration: Duration,
+}
+
+impl CommandOutput {
+    /// Returns true if the command reported exit code 0.
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// An interactive shell running in a PTY.
+///
+/// Create one with [`ShellSession::new`] or [`ShellSession::builder`]. The
+/// shell is started without user configuration and with a known prompt
+/// (`$ ` by default). With OSC 133 hooks enabled (the default), the prompt
+/// reports each command's exit code and marks where commands start, and
+/// [`run`](Self::run) uses those markers to find the command's output;
+/// without them, it looks for the prompt text at the cursor, so the prompt
+/// should be something the commands do not print.
+///
+/// Queries from programs run in the session, such as cursor position reports,
+/// are answered like [`TuiTestHarness`](crate::TuiTestHarness) does, with the
+/// replies of the builder's terminal profile.
+///
+/// The prompt is placed in `PS1` as given, so it should not contain text the
+/// shell expands in prompts, such as `\` (bash), `%` (zsh) or `$` followed by
+/// a name.
+pub struct ShellSession {
+    terminal: TestTerminal,
+    state: ScreenState,
+    shell: Shell,
+    prompt: String,
+    prompt_markers: bool,
+    timeout: Duration,
+    terminal_profile: TerminalProfile,
+}
+
+impl std::fmt::Debug for ShellSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShellSession")
+            .field("shell", &self.shell)
+            .field("prompt", &self.prompt)
+            .field("prompt_markers", &self.prompt_markers)
+            .field("timeout", &self.timeout)
+            .field("terminal_profile", &self.terminal_profile)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ShellSession {
+    /// Starts `shell` in an 80x24 terminal with the default settings and
+    /// waits for its first prompt.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shell cannot be started, or a `Timeout` error
+    /// if its prompt does not appear.
+    pub fn new(shell: Shell) -> Result<Self> {
+        Self::builder(shell).build()
+    }
+
+    /// Creates a builder for configuring a session running `shell`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// use ratatui_testlib::shell::{Shell, ShellSession};
+    ///
+    /// let mut session = ShellSession::builder(Shell::Sh)
+    ///     .with_size(100, 30)
+    ///     .with_prompt("test> ")
+    ///     .with_timeout(Duration::from_secs(30))
+    ///     .build()?;
+    /// # Ok::<(), ratatui_testlib::TermTestError>(())
+    /// ```
+    pub fn builder(shell: Shell) -> ShellSessionBuilder {
+        ShellSessionBuilder::new(shell)
+    }
+
+    /// Runs `command` and waits for the shell's next prompt, using the
+    /// session's timeout.
+    ///
+    /// `command` is typed as a single line followed by Enter.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Timeout` error if the command does not finish in time (it
+    /// is then interrupted with Ctrl+C), or `ProcessExited` if the shell
+    /// exits.
+    pub fn run(&mut self, command: &str) -> Result<CommandOutput> {
+        self.run_with_timeout(command, self.timeout)
+    }
+
+    /// Runs `command` like [`run`](Self::run), with its own timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Timeout` error if the command does not finish within
+    /// `timeout` (it is then interrupted with Ctrl+C), or `ProcessExited` if
+    /// the shell exits.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// use ratatui_testlib::{
+    ///     shell::{Shell, ShellSession},
+    ///     TermTestError,
+    /// };
+    ///
+    /// let mut session = ShellSession::new(Shell::Bash)?;
+    /// let result = session.run_with_timeout("sleep 10", Duration::from_millis(200));
+    /// assert!(matches!(result, Err(TermTestError::Timeout { .. })));
+    ///
+    /// // The session is still usable afterwards
+    /// assert_eq!(session.run("echo ok")?.text, "ok");
+    /// # Ok::<(), ratatui_testlib::TermTestError>(())
+    /// ```
+    pub fn run_with_timeout(&mut self, command: &str, timeout: Duration) -> Result<CommandOutput> {
+        self.update_state()?;
+        let command_line = self.state.primary_cursor_line();
+
+        let start = Instant::now();
+        self.terminal.write_all(format!("{}\r", command).as_bytes())?;
+
+        if let Err(e) = self.wait_for_prompt(Some(command_line), timeout) {
+            if matches!(e, TermTestError::Timeout { .. }) {
+                // Interrupt the command and wait for its prompt, so the
+                // session can be used again
+                self.terminal.write_all(b"\x03")?;
+                let _ = self.wait_for_prompt(Some(command_line), timeout);
+            }
+            return Err(e);
+        }
+        let duration = start.elapsed();
+
+        let finished = self
+            .state
+            .prompt_markers()
+            .into_iter()
+            .rev()
+            .find(|marker| marker.marker_type == PromptMarkerType::CommandFinished)
+            .filter(|_| self.prompt_markers);
+
+        // The output ends where the prompt starts: at the finish marker, or
+        // before the prompt text at the cursor
+        let cursor_line = self.state.primary_cursor_line();
+        let end_line = finished.as_ref().map_or(cursor_line, |marker| marker.line);
+        let end_col = if end_line == cursor_line {
+            let (_, col) = self.state.cursor_position();
+            col.saturating_sub(self.prompt.width() as u16)
+        } else {
+            0
+        };
+        let first_line = self.state.logical_line_end(command_line) + 1;
+        let text = self.state.text_between((first_line, 0), (end_line, end_col));
+
+        Ok(CommandOutput {
+            text,
+            exit_code: finished.and_then(|marker| marker.exit_code),
+            duration,
+        })
+    }
+
+    /// Sends raw text to the shell, without waiting for a prompt.
+    ///
+    /// Use this to interact with a program started from the shell, together
+    /// with [`wait_for`](Self::wait_for).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the write fails.
+    pub fn send_text(&mut self, text: &str) -> Result<()> {
+        self.terminal.write_all(text.as_bytes())?;
+        self.update_state()
+    }
+
+    /// Waits until `condition` holds for the screen, using the session's
+    /// timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Timeout` error if the condition does not become true in
+    /// time, or `ProcessExited` if the shell exits first.
+    pub fn wait_for<F>(&mut self, condition: F) -> Result<()>
+    where
+        F: Fn(&ScreenState) -> bool,
+    {
+        let start = Instant::now();
+        loop {
+            self.update_state()?;
+            if condition(&self.state) {
+                return Ok(());
+            }
+            if start.elapsed() >= self.timeout {
+                return Err(TermTestError::Timeout {
+                    timeout_ms: self.timeout.as_millis() as u64,
+                });
+            }
+        }
+    }
+
+    /// Returns the screen state of the session's terminal.
+    ///
+    /// The scrollback keeps up to 100,000 lines, so the output of every
+    /// command run so far stays available through
+    /// [`ScreenState::contents_with_scrollback`].
+    pub fn state(&self) -> &ScreenState {
+        &self.state
+    }
+
+    /// Returns the shell this session runs.
+    pub fn shell(&self) -> Shell {
+        self.shell
+    }
+
+    /// Returns true if the shell is still running.
+    pub fn is_running(&mut self) -> bool {
+        self.terminal.is_running()
+    }
+
+    /// Reads whatever output is available and feeds it to the screen state.
+    fn update_state(&mut self) -> Result<()> {
+        self.read_output().map(|_| ())
+    }
+
+    /// Reads available output into the screen state and returns it.
+    ///
+    /// Queries in the output (cursor position, device attributes, colors,
+    /// Kitty graphics probes, ...) are answered as the session's terminal
+    /// profile would, so programs started from the shell do not block
+    /// waiting for a reply.
+    ///
+    /// Waits up to one read timeout when nothing is available yet, and
+    /// returns `ProcessExited` once the shell has exited and its output has
+    /// been read.
+    fn read_output(&mut self) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            match self.terminal.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    self.state.feed(&buf[..n]);
+                    output.extend_from_slice(&buf[..n]);
+                    // Resets are only of interest to recordings
+                    self.state.take_resets();
+                    self.answer_queries()?;
+                }
+                // EIO once the shell has exited
+                Err(TermTestError::Io(_)) if !self.terminal.is_running() => break,
+                Err(e) => return Err(e),
+            }
+        }
+        if output.is_empty() && !self.terminal.is_running() {
+            return Err(TermTestError::ProcessExited);
+        }
+        Ok(output)
+    }
+
+    /// Writes replies to the queries found in the output fed so far.
+    fn answer_queries(&mut self) -> Result<()> {
+        for query in self.state.take_queries() {
+            if let Some(reply) = query.reply(self.terminal_profile) {
+                self.terminal.write_all(&reply)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true once a new prompt is shown, given the `output` read since
+    /// the command was sent from `command_line`.
+    ///
+    /// With OSC 133 hooks, every prompt ends with a command start marker;
+    /// without them, the prompt text must appear before the cursor on a line
+    /// below the command.
+    fn prompt_returned(&self, output: &[u8], command_line: Option<usize>) -> bool {
+        if self.prompt_markers {
+            return output
+                .windows(COMMAND_START.len())
+                .any(|window| window == COMMAND_START);
+        }
+
+        let line = self.state.primary_cursor_line();
+        if command_line.is_some_and(|command| line <= self.state.logical_line_end(command)) {
+            return false;
+        }
+        let (_, col) = self.state.cursor_position();
+        self.state
+            .text_between((line, 0), (line, col))
+            .ends_with(self.prompt.trim_end())
+    }
+
+    /// Waits for a prompt following a command sent from `command_line`, or
+    /// for the shell's first prompt when `command_line` is `None`.
+    fn wait_for_prompt(&mut self, command_line: Option<usize>, timeout: Duration) -> Result<()> {
+        let start = Instant::now();
+        let mut output = Vec::new();
+        while !self.prompt_returned(&output, command_line) {
+            if start.elapsed() >= timeout {
+                return Err(TermTestError::Timeout { timeout_ms: timeout.as_millis() as u64 });
+            }
+            output.extend(self.read_output()?);
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`ShellSession`].
+#[derive(Debug, Clone)]
+pub struct ShellSessionBuilder {
+    shell: Shell,
+    width: u16,
+    height: u16,
+    prompt: String,
+    prompt_markers: bool,
+    timeout: Duration,
+    env: Vec<(String, String)>,
+    cwd: Option<PathBuf>,
+    terminal_profile: TerminalProfile,
+}
+
+impl ShellSessionBuilder {
+    /// Creates a builder for `shell` with the default settings.
+    fn new(shell: Shell) -> Self {
+        Self {
+            shell,
+            width: 80,
+            height: 24,
+            prompt: DEFAULT_PROMPT.to_string(),
+            prompt_markers: true,
+            timeout: DEFAULT_TIMEOUT,
+            env: Vec::new(),
+            cwd: None,
+            terminal_profile: TerminalProfile::default(),
+        }
+    }
+
+    /// Sets the terminal size.
+    pub fn with_size(mut self, width: u16, height: u16) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Sets the prompt the shell shows before each command (`$ ` by default).
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    /// Enables or disables the OSC 133 prompt hooks (enabled by default).
+    ///
+    /// Without them, exit codes are not reported and the end of a command's
+    /// output is found by looking for the prompt text.
+    pub fn with_prompt_markers(mut self, enabled: bool) -> Self {
+        self.prompt_markers = enabled;
+        self
+    }
+
+    /// Sets how long commands, and the shell's startup, may take.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets an environment variable for the shell.
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Sets the shell's working directory.
+    pub fn with_cwd(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(dir.into());
+        self
+    }
+
+    /// Sets the terminal profile whose replies answer queries from the
+    /// programs run in the session.
+    pub fn with_terminal_profile(mut self, profile: TerminalProfile) -> Self {
+        self.terminal_profile = profile;
+        self
+    }
+
+    /// Starts the shell and waits for its first prompt.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal cannot be created or the shell cannot
+    /// be started, or a `Timeout` error if its prompt does not appear.
+    pub fn build(self) -> Result<ShellSession> {
+        let mut terminal = TestTerminal::new(self.width, self.height)?;
+
+        let mut cmd = CommandBuilder::new(self.shell.program());
+        cmd.args(self.shell.args());
+        cmd.env("TERM", "xterm-256color");
+        cmd.env("PS1", self.shell.ps1(&self.prompt, self.prompt_markers));
+        cmd.env("PS2", "> ");
+        cmd.env("HISTFILE", "");
+        match self.shell {
+            Shell::Bash if self.prompt_markers => cmd.env("PS0", "\x1b]133;C\x07"),
+            // Keep zsh from marking output that lacks a final newline
+            Shell::Zsh => cmd.env("PROMPT_EOL_MARK", ""),
+            Shell::Sh => cmd.env_remove("ENV"),
+            Shell::Bash => {}
+        }
+        for (key, value) in &self.env {
+            cmd.env(key, value);
+        }
+        if let Some(cwd) = &self.cwd {
+            cmd.cwd(cwd);
+        }
+        terminal.spawn(cmd)?;
+
+        let state = ScreenState::new(self.width, self.height)
+            .with_scrollback_limit(SESSION_SCROLLBACK_LIMIT);
+        let mut session = ShellSession {
+            terminal,
+            state,
+            shell: self.shell,
+            prompt: self.prompt,
+            prompt_markers: self.prompt_markers,
+            timeout: self.timeout,
+            terminal_profile: self.terminal_profile,
+        };
+        session.wait_for_prompt(None, self.timeout)?;
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns true if `program` is found on `PATH`.
+    fn on_path(program: &str) -> bool {
+        std::env::var_os("PATH").is_some_and(|path| {
+            std::env::split_paths(&path).any(|dir| dir.join(program).is_file())
+        })
+    }
+
+    #[test]
+    fn test_ps1_hooks() {
+        assert_eq!(Shell::Bash.ps1("$ ", false), "$ ");
+        assert_eq!(
+            Shell::Bash.ps1("$ ", true),
+            "\\[\x1b]133;D;$?\x07\x1b]133;A\x07\\]$ \\[\x1b]133;B\x07\\]"
+        );
+        assert_eq!(
+            Shell::Zsh.ps1("> ", true),
+            "%{\x1b]133;D;%?\x07\x1b]133;A\x07%}> %{\x1b]133;B\x07%}"
+        );
+    }
+
+    #[test]
+    fn test_run_captures_output_and_exit_code() {
+        let mut session = ShellSession::new(Shell::Bash).unwrap();
+
+        let output = session.run("printf 'one\\ntwo\\n'").unwrap();
+        assert_eq!(output.text, "one\ntwo");
+        assert_eq!(output.exit_code, Some(0));
+        assert!(output.success());
+
+        let output = session.run("(exit 3)").unwrap();
+        assert_eq!(output.text, "");
+        assert_eq!(output.exit_code, Some(3));
+
+        // Output without a final newline ends where the prompt starts
+        let output = session.run("printf partial").unwrap();
+        assert_eq!(output.text, "partial");
+    }
+
+    #[test]
+    fn test_run_in_zsh() {
+        if !on_path("zsh") {
+            eprintln!("skipping test_run_in_zsh: zsh is not installed");
+            return;
+        }
+        let mut session = ShellSession::new(Shell::Zsh).unwrap();
+
+        let output = session.run("print -l one two").unwrap();
+        assert_eq!(output.text, "one\ntwo");
+        assert_eq!(output.exit_code, Some(0));
+
+        // `%?` in the prompt reports the exit code
+        let output = session.run("(exit 4)").unwrap();
+        assert_eq!(output.exit_code, Some(4));
+
+        // PROMPT_EOL_MARK is empty, so no `%` marks the missing newline
+        let output = session.run("printf partial").unwrap();
+        assert_eq!(output.text, "partial");
+    }
+
+    #[test]
+    fn test_run_without_prompt_markers() {
+        let mut session = ShellSession::builder(Shell::Sh)
+            .with_prompt("sh-test> ")
+            .with_prompt_markers(false)
+            .build()
+            .unwrap();
+
+        let output = session.run("echo hello; echo world").unwrap();
+        assert_eq!(output.text, "hello\nworld");
+        assert_eq!(output.exit_code, None);
+    }
+
+    #[test]
+    fn test_run_answers_queries() {
+        let mut session = ShellSession::builder(Shell::Bash)
+            .with_terminal_profile(TerminalProfile::WezTerm)
+            .build()
+            .unwrap();
+
+        // Without a reply, `read` gives up after its timeout
+        let output = session
+            .run(
+                "stty -icanon -echo; printf '\\033[c'; IFS= read -rs -t 2 -d c reply; \
+                 stty sane; echo \"reply:${reply#?}\"",
+            )
+            .unwrap();
+        assert_eq!(output.text, "reply:[?62;4;22");
+    }
+
+    #[test]
+    fn test_run_timeout_interrupts_command() {
+        let mut session = ShellSession::new(Shell::Bash).unwrap();
+
+        let result = session.run_with_timeout("sleep 5", Duration::from_millis(300));
+        assert!(matches!(result, Err(TermTestError::Timeout { .. })));
+
+        let output = session.run("echo after").unwrap();
+        assert_eq!(output.text, "after");
+    }
+}
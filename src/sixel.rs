@@ -19,6 +19,13 @@
 //! - [`SixelSequence`]: Represents a single Sixel graphic with position/bounds
 //! - [`SixelCapture`]: Collection of captured Sixel sequences with query methods
 //!
+//! # Decoding
+//!
+//! With the `sixel-image` feature, `SixelRegion::to_image()` decodes a region
+//! into an `image::RgbaImage`, and `assert_image_matches()` compares an image
+//! against a reference PNG with a per-channel tolerance. This lets tests check
+//! what a graphic shows, not just where it is.
+//!
 //! # Migration to Graphics Module
 //!
 //! This module now re-exports the unified graphics API from [`crate::graphics`].
@@ -75,9 +82,13 @@
 //! # }
 //! ```
 
+#[cfg(feature = "sixel-image")]
+use std::path::Path;
+
 use crate::{
     error::{Result, TermTestError},
     graphics::{GraphicsCapture as UnifiedGraphicsCapture, GraphicsProtocol, GraphicsRegion},
+    screen::DEFAULT_CELL_SIZE,
 };
 
 /// Represents a captured Sixel sequence with position information.
@@ -256,25 +267,75 @@ impl SixelCapture {
 
     /// Creates a Sixel capture from raw terminal output.
     ///
-    /// This parses the output and extracts all Sixel sequences with their positions.
+    /// This scans the output for Sixel sequences (`ESC P ... q ... ESC \`) and
+    /// decodes each one to determine its size. Sequences without a string
+    /// terminator are ignored, since the terminal has not drawn them yet.
+    ///
+    /// Sizes are converted to cells assuming the default 8x6 pixel cell; use
+    /// [`from_output_with_cell_size`](Self::from_output_with_cell_size) to
+    /// match a screen or harness built with another cell size.
     ///
     /// # Arguments
     ///
     /// * `output` - Raw terminal output bytes
-    /// * `cursor_positions` - Cursor positions corresponding to each sequence
-    ///
-    /// # Note
-    ///
-    /// Phase 1 implementation is a stub. Full Sixel parsing will be implemented
-    /// in Phase 3 after validating vt100 capabilities.
-    pub fn from_output(_output: &[u8], _cursor_positions: &[(u16, u16)]) -> Self {
-        // TODO: Phase 3 - Implement Sixel sequence detection and parsing
-        // This requires:
-        // 1. Scanning for Sixel escape sequences (ESC P ... ESC \)
-        // 2. Parsing Sixel data to extract dimensions
-        // 3. Associating cursor positions with sequences
-        // 4. Calculating bounding rectangles
-        Self::new()
+    /// * `cursor_positions` - Cursor positions corresponding to each sequence;
+    ///   sequences without a matching entry are placed at (0, 0)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::sixel::SixelCapture;
+    ///
+    /// // A 16x12 pixel image drawn at row 2, column 4
+    /// let output = b"\x1bPq\"1;1;16;12#1!16~-!16~\x1b\\";
+    /// let capture = SixelCapture::from_output(output, &[(2, 4)]);
+    ///
+    /// assert_eq!(capture.sequences().len(), 1);
+    /// assert_eq!(capture.sequences()[0].bounds, (2, 4, 2, 2));
+    /// ```
+    pub fn from_output(output: &[u8], cursor_positions: &[(u16, u16)]) -> Self {
+        Self::from_output_with_cell_size(output, cursor_positions, DEFAULT_CELL_SIZE)
+    }
+
+    /// Creates a Sixel capture from raw terminal output like
+    /// [`from_output`](Self::from_output), converting sizes to cells with
+    /// `cell_size` (width and height in pixels), as given to
+    /// [`ScreenState::with_cell_size`](crate::ScreenState::with_cell_size).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::sixel::SixelCapture;
+    ///
+    /// let output = b"\x1bPq\"1;1;16;12#1!16~-!16~\x1b\\";
+    /// let capture = SixelCapture::from_output_with_cell_size(output, &[(2, 4)], (10, 20));
+    ///
+    /// assert_eq!(capture.sequences()[0].bounds, (2, 4, 2, 1));
+    /// ```
+    pub fn from_output_with_cell_size(
+        output: &[u8],
+        cursor_positions: &[(u16, u16)],
+        cell_size: (u16, u16),
+    ) -> Self {
+        let cell_width = u32::from(cell_size.0.max(1));
+        let cell_height = u32::from(cell_size.1.max(1));
+
+        let sequences = scan_sixel_sequences(output)
+            .into_iter()
+            .enumerate()
+            .map(|(index, (raw, params, body))| {
+                let (row, col) = cursor_positions.get(index).copied().unwrap_or_default();
+                let (width, height) = decode(&params, body)
+                    .map(|bitmap| (bitmap.width, bitmap.height))
+                    .unwrap_or((0, 0));
+                let width_cells = width.div_ceil(cell_width) as u16;
+                let height_cells = height.div_ceil(cell_height) as u16;
+
+                SixelSequence::new(raw.to_vec(), (row, col), (row, col, width_cells, height_cells))
+            })
+            .collect();
+
+        Self { sequences }
     }
 
     /// Creates a Sixel capture from a ScreenState.
@@ -460,6 +521,471 @@ impl Default for SixelCapture {
     }
 }
 
+/// Largest width or height, in pixels, that the Sixel decoder will produce.
+///
+/// Guards tests against runaway repeat counts or raster attributes allocating
+/// gigabytes of pixels.
+const MAX_SIXEL_DIMENSION: u32 = 10_000;
+
+/// Number of color registers available to a Sixel image (xterm's maximum).
+const SIXEL_COLOR_REGISTERS: usize = 1024;
+
+/// Default VT340 color registers, as RGB percentages.
+const VT340_PALETTE: [[u32; 3]; 16] = [
+    [0, 0, 0],
+    [20, 20, 80],
+    [80, 13, 13],
+    [20, 80, 20],
+    [80, 20, 80],
+    [20, 80, 80],
+    [80, 80, 20],
+    [53, 53, 53],
+    [26, 26, 26],
+    [33, 33, 60],
+    [60, 26, 26],
+    [33, 60, 33],
+    [60, 33, 60],
+    [33, 60, 60],
+    [60, 60, 33],
+    [80, 80, 80],
+];
+
+/// A decoded Sixel image in row-major RGBA8 layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SixelBitmap {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) rgba: Vec<u8>,
+}
+
+/// Decodes the body of a Sixel sequence (the bytes after the `q` final byte).
+///
+/// `params` are the DCS parameters `P1;P2;P3`. When `P2 = 1`, pixels that no
+/// sixel touches stay fully transparent; otherwise they are filled with color
+/// register 0, matching the VT340. Pixels use a 1:1 aspect ratio.
+///
+/// The image is as large as the raster attributes (`"Pan;Pad;Ph;Pv`) say, or
+/// larger if sixels are drawn beyond them. Without raster attributes, the size
+/// is the extent of the drawn pixels.
+pub(crate) fn decode(params: &[i64], data: &[u8]) -> Result<SixelBitmap> {
+    let transparent = params.get(1) == Some(&1);
+    let mut decoder = SixelDecoder::new();
+    let mut i = 0;
+
+    while i < data.len() {
+        let byte = data[i];
+        i += 1;
+        match byte {
+            b'"' => {
+                let (args, next) = numeric_args(data, i);
+                i = next;
+                // "Pan;Pad;Ph;Pv; the two-argument form is the aspect ratio only
+                if let [_, _, width, height, ..] = args[..] {
+                    decoder.raster = (width, height);
+                }
+            }
+            b'#' => {
+                let (args, next) = numeric_args(data, i);
+                i = next;
+                decoder.color_introducer(&args);
+            }
+            b'!' => {
+                let (args, next) = numeric_args(data, i);
+                i = next;
+                let count = args.first().copied().unwrap_or(1).max(1);
+                if let Some(&sixel @ 0x3F..=0x7E) = data.get(i) {
+                    i += 1;
+                    decoder.draw(sixel - 0x3F, count)?;
+                }
+            }
+            b'$' => decoder.x = 0,
+            b'-' => {
+                decoder.x = 0;
+                decoder.band += 1;
+            }
+            0x3F..=0x7E => decoder.draw(byte - 0x3F, 1)?,
+            _ => {}
+        }
+    }
+
+    decoder.finish(transparent)
+}
+
+/// Parses `;`-separated decimal parameters starting at `start`.
+///
+/// Returns the parameters (empty ones as 0) and the index of the first byte
+/// after them.
+fn numeric_args(data: &[u8], start: usize) -> (Vec<u32>, usize) {
+    let mut args = Vec::new();
+    let mut current: Option<u32> = None;
+    let mut i = start;
+
+    while let Some(&byte) = data.get(i) {
+        match byte {
+            b'0'..=b'9' => {
+                let digit = u32::from(byte - b'0');
+                current = Some(current.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+            }
+            b';' => args.push(current.take().unwrap_or(0)),
+            _ => break,
+        }
+        i += 1;
+    }
+    if current.is_some() || i > start {
+        args.push(current.unwrap_or(0));
+    }
+
+    (args, i)
+}
+
+/// Converts a Sixel color percentage (0-100) to an 8-bit channel value.
+fn percent_to_channel(percent: u32) -> u8 {
+    ((percent.min(100) * 255 + 50) / 100) as u8
+}
+
+/// Converts a Sixel HLS color to RGB.
+///
+/// Sixel hue angles start at blue (0°), with red at 120° and green at 240°.
+/// Lightness and saturation are percentages.
+fn hls_to_rgb(hue: u32, lightness: u32, saturation: u32) -> [u8; 3] {
+    let hue = f64::from((hue % 360 + 240) % 360);
+    let lightness = f64::from(lightness.min(100)) / 100.0;
+    let saturation = f64::from(saturation.min(100)) / 100.0;
+
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |value: f64| ((value + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+
+    [channel(r), channel(g), channel(b)]
+}
+
+/// Drawing state for [`decode`].
+struct SixelDecoder {
+    palette: Vec<[u8; 3]>,
+    color: usize,
+    x: u32,
+    band: u32,
+    raster: (u32, u32),
+    /// Drawn pixels, row by row. Rows grow on demand.
+    pixels: Vec<Vec<Option<[u8; 3]>>>,
+    width: u32,
+}
+
+impl SixelDecoder {
+    fn new() -> Self {
+        let mut palette = vec![[0, 0, 0]; SIXEL_COLOR_REGISTERS];
+        for (register, [r, g, b]) in palette.iter_mut().zip(VT340_PALETTE) {
+            *register = [percent_to_channel(r), percent_to_channel(g), percent_to_channel(b)];
+        }
+
+        Self {
+            palette,
+            color: 0,
+            x: 0,
+            band: 0,
+            raster: (0, 0),
+            pixels: Vec::new(),
+            width: 0,
+        }
+    }
+
+    /// Handles `#Pc` (select) and `#Pc;Pu;Px;Py;Pz` (define and select).
+    fn color_introducer(&mut self, args: &[u32]) {
+        let Some(&register) = args.first() else {
+            return;
+        };
+        let register = register as usize % SIXEL_COLOR_REGISTERS;
+
+        match args[1..] {
+            [1, hue, lightness, saturation] => {
+                self.palette[register] = hls_to_rgb(hue, lightness, saturation);
+            }
+            [2, r, g, b] => {
+                self.palette[register] =
+                    [percent_to_channel(r), percent_to_channel(g), percent_to_channel(b)];
+            }
+            _ => {}
+        }
+        self.color = register;
+    }
+
+    /// Draws `count` copies of a sixel (six vertical pixels, LSB on top).
+    fn draw(&mut self, bits: u8, count: u32) -> Result<()> {
+        let end = self.x.saturating_add(count);
+        let top = self.band.saturating_mul(6);
+        if end > MAX_SIXEL_DIMENSION || top >= MAX_SIXEL_DIMENSION {
+            return Err(TermTestError::SixelValidation(format!(
+                "Sixel image exceeds {MAX_SIXEL_DIMENSION} pixels per side"
+            )));
+        }
+
+        let color = self.palette[self.color];
+        for bit in 0..6 {
+            if bits & (1 << bit) == 0 {
+                continue;
+            }
+            let y = (top + bit) as usize;
+            if self.pixels.len() <= y {
+                self.pixels.resize(y + 1, Vec::new());
+            }
+            let row = &mut self.pixels[y];
+            if row.len() < end as usize {
+                row.resize(end as usize, None);
+            }
+            row[self.x as usize..end as usize].fill(Some(color));
+        }
+
+        self.x = end;
+        self.width = self.width.max(end);
+        Ok(())
+    }
+
+    fn finish(self, transparent: bool) -> Result<SixelBitmap> {
+        let (raster_width, raster_height) = self.raster;
+        if raster_width > MAX_SIXEL_DIMENSION || raster_height > MAX_SIXEL_DIMENSION {
+            return Err(TermTestError::SixelValidation(format!(
+                "Sixel raster attributes {raster_width}x{raster_height} exceed \
+                 {MAX_SIXEL_DIMENSION} pixels per side"
+            )));
+        }
+
+        let drawn_height = self
+            .pixels
+            .iter()
+            .rposition(|row| row.iter().any(Option::is_some))
+            .map_or(0, |y| y as u32 + 1);
+        let width = raster_width.max(self.width);
+        let height = raster_height.max(drawn_height);
+
+        let background = if transparent {
+            [0, 0, 0, 0]
+        } else {
+            let [r, g, b] = self.palette[0];
+            [r, g, b, 255]
+        };
+
+        let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height as usize {
+            let row = self.pixels.get(y);
+            for x in 0..width as usize {
+                match row.and_then(|row| row.get(x)).copied().flatten() {
+                    Some([r, g, b]) => rgba.extend_from_slice(&[r, g, b, 255]),
+                    None => rgba.extend_from_slice(&background),
+                }
+            }
+        }
+
+        Ok(SixelBitmap { width, height, rgba })
+    }
+}
+
+/// Finds complete Sixel sequences in raw output.
+///
+/// Returns `(raw, params, body)` for each sequence, where `raw` includes the
+/// DCS introducer and string terminator, and `body` is the data after `q`.
+fn scan_sixel_sequences(output: &[u8]) -> Vec<(&[u8], Vec<i64>, &[u8])> {
+    let mut sequences = Vec::new();
+    let mut i = 0;
+
+    while i + 1 < output.len() {
+        if output[i] != 0x1b || output[i + 1] != b'P' {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let params_start = i + 2;
+        let params_end = output[params_start..]
+            .iter()
+            .position(|&b| !(b.is_ascii_digit() || b == b';'))
+            .map_or(output.len(), |offset| params_start + offset);
+        let Some(terminator) = output[params_end..]
+            .windows(2)
+            .position(|window| window == b"\x1b\\")
+            .map(|offset| params_end + offset)
+        else {
+            break;
+        };
+
+        if output.get(params_end) == Some(&b'q') {
+            let params = output[params_start..params_end]
+                .split(|&b| b == b';')
+                .map(|param| {
+                    std::str::from_utf8(param)
+                        .ok()
+                        .and_then(|param| param.parse().ok())
+                        .unwrap_or(0)
+                })
+                .collect();
+            sequences.push((
+                &output[start..terminator + 2],
+                params,
+                &output[params_end + 1..terminator],
+            ));
+        }
+        i = terminator + 2;
+    }
+
+    sequences
+}
+
+#[cfg(feature = "sixel-image")]
+impl crate::screen::SixelRegion {
+    /// Decodes this Sixel region into an RGBA image.
+    ///
+    /// Color registers, HLS/RGB palette definitions, repeat introducers and
+    /// the `P2` transparency parameter are honored. See
+    /// [`assert_image_matches`] for comparing the result with a reference PNG.
+    ///
+    /// Requires the `sixel-image` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TermTestError::SixelValidation`] if the image would exceed
+    /// 10,000 pixels in either dimension.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::ScreenState;
+    ///
+    /// # fn main() -> ratatui_testlib::Result<()> {
+    /// let mut screen = ScreenState::new(80, 24);
+    /// // A 4x6 red square, with the register defined as 100% red
+    /// screen.feed(b"\x1bPq#1;2;100;0;0!4~\x1b\\");
+    ///
+    /// let image = screen.sixel_regions()[0].to_image()?;
+    /// assert_eq!(image.dimensions(), (4, 6));
+    /// assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_image(&self) -> Result<image::RgbaImage> {
+        let bitmap = decode(&self.params, &self.data)?;
+        image::RgbaImage::from_raw(bitmap.width, bitmap.height, bitmap.rgba).ok_or_else(|| {
+            TermTestError::SixelValidation("decoded Sixel buffer has the wrong size".to_string())
+        })
+    }
+
+    /// Decodes this region and compares it with a reference PNG.
+    ///
+    /// This is shorthand for [`to_image`](Self::to_image) followed by
+    /// [`assert_image_matches`].
+    ///
+    /// Requires the `sixel-image` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TermTestError::SixelValidation`] if decoding fails or the
+    /// image does not match the reference.
+    pub fn assert_matches_png(&self, reference: impl AsRef<Path>, tolerance: u8) -> Result<()> {
+        assert_image_matches(&self.to_image()?, reference, tolerance)
+    }
+}
+
+/// Asserts that an image matches a reference PNG.
+///
+/// Two pixels match when every channel differs by at most `tolerance`. Pixels
+/// that are fully transparent in both images always match, whatever their
+/// color channels hold.
+///
+/// When `UPDATE_GOLDENS=1` is set, the reference file is (re)written from
+/// `actual` instead, the same way golden files are updated.
+///
+/// Requires the `sixel-image` feature.
+///
+/// # Errors
+///
+/// Returns [`TermTestError::SixelValidation`] if the reference cannot be read,
+/// the dimensions differ, or any pixel is outside the tolerance. The message
+/// includes the number of mismatched pixels and the first one found.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ratatui_testlib::{sixel::assert_image_matches, ScreenState};
+///
+/// # fn main() -> ratatui_testlib::Result<()> {
+/// let screen = ScreenState::new(80, 24);
+/// // ... feed output from an app that renders a Sixel preview ...
+///
+/// let image = screen.sixel_regions()[0].to_image()?;
+/// assert_image_matches(&image, "tests/fixtures/preview.png", 2)?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "sixel-image")]
+pub fn assert_image_matches(
+    actual: &image::RgbaImage,
+    reference: impl AsRef<Path>,
+    tolerance: u8,
+) -> Result<()> {
+    let reference = reference.as_ref();
+
+    if crate::golden::should_update_goldens() {
+        if let Some(parent) = reference.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        return actual.save(reference).map_err(|e| {
+            TermTestError::SixelValidation(format!(
+                "failed to write reference image {}: {e}",
+                reference.display()
+            ))
+        });
+    }
+
+    let expected = image::open(reference)
+        .map_err(|e| {
+            TermTestError::SixelValidation(format!(
+                "failed to load reference image {}: {e}",
+                reference.display()
+            ))
+        })?
+        .to_rgba8();
+
+    if actual.dimensions() != expected.dimensions() {
+        return Err(TermTestError::SixelValidation(format!(
+            "image is {}x{} but reference {} is {}x{}",
+            actual.width(),
+            actual.height(),
+            reference.display(),
+            expected.width(),
+            expected.height()
+        )));
+    }
+
+    let matches = |a: &image::Rgba<u8>, b: &image::Rgba<u8>| {
+        (a[3] == 0 && b[3] == 0) || a.0.iter().zip(b.0).all(|(&x, y)| x.abs_diff(y) <= tolerance)
+    };
+    let mut mismatches = actual
+        .enumerate_pixels()
+        .zip(expected.pixels())
+        .filter(|((_, _, a), b)| !matches(a, b));
+
+    if let Some(((x, y, a), b)) = mismatches.next() {
+        let count = 1 + mismatches.count();
+        return Err(TermTestError::SixelValidation(format!(
+            "{count} pixel(s) differ from reference {} by more than {tolerance}; \
+             first at ({x}, {y}): got {:?}, expected {:?}",
+            reference.display(),
+            a.0,
+            b.0
+        )));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -500,4 +1026,105 @@ mod tests {
         assert_eq!(capture.sequences_in_area(area).len(), 1);
         assert_eq!(capture.sequences_outside_area(area).len(), 1);
     }
+
+    fn pixel(bitmap: &SixelBitmap, x: u32, y: u32) -> [u8; 4] {
+        let offset = ((y * bitmap.width + x) * 4) as usize;
+        bitmap.rgba[offset..offset + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_decode_palette_and_repeats() {
+        // Register 1 = RGB red, register 2 = HLS green (hue 240 in Sixel's HLS)
+        let bitmap = decode(&[0, 1], b"#1;2;100;0;0#2;1;240;50;100#1!3~$#2~~~~-#1@").unwrap();
+
+        assert_eq!((bitmap.width, bitmap.height), (4, 7));
+        // The second pass overdraws the first three columns and extends the row.
+        assert_eq!(pixel(&bitmap, 0, 0), [0, 255, 0, 255]);
+        assert_eq!(pixel(&bitmap, 3, 5), [0, 255, 0, 255]);
+        // '@' sets only the top pixel of the second band.
+        assert_eq!(pixel(&bitmap, 0, 6), [255, 0, 0, 255]);
+        assert_eq!(pixel(&bitmap, 1, 6), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_decode_background_depends_on_p2() {
+        // '~' at column 0 of the first band, then raster attributes pad to 3x8.
+        let data = b"\"1;1;3;8#0;2;0;0;100#3~";
+
+        let opaque = decode(&[0, 0], data).unwrap();
+        assert_eq!((opaque.width, opaque.height), (3, 8));
+        assert_eq!(pixel(&opaque, 2, 7), [0, 0, 255, 255]);
+        assert_eq!(pixel(&opaque, 0, 0), [51, 204, 51, 255]); // VT340 green
+
+        let transparent = decode(&[0, 1], data).unwrap();
+        assert_eq!(pixel(&transparent, 2, 7), [0, 0, 0, 0]);
+        assert_eq!(pixel(&transparent, 0, 0), [51, 204, 51, 255]);
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_images() {
+        assert!(matches!(
+            decode(&[], b"!99999~"),
+            Err(TermTestError::SixelValidation(_))
+        ));
+        assert!(decode(&[], b"\"1;1;50000;50000").is_err());
+    }
+
+    #[test]
+    fn test_decode_aspect_ratio_without_size() {
+        // "Pan;Pad sets the pixel aspect ratio only, not the raster size
+        let bitmap = decode(&[], b"\"2;1").unwrap();
+        assert_eq!((bitmap.width, bitmap.height), (0, 0));
+
+        let bitmap = decode(&[], b"\"1;1#1~~").unwrap();
+        assert_eq!((bitmap.width, bitmap.height), (2, 6));
+    }
+
+    #[test]
+    fn test_from_output_scans_sequences() {
+        let output = b"text\x1bP0;1;0q\"1;1;20;12#1~\x1b\\more\x1bPq!9~\x1b\\\x1bP$q\x1b\\";
+        let capture = SixelCapture::from_output(output, &[(1, 2)]);
+
+        let sequences = capture.sequences();
+        assert_eq!(sequences.len(), 2);
+        assert!(sequences[0].raw.starts_with(b"\x1bP0;1;0q"));
+        assert!(sequences[0].raw.ends_with(b"\x1b\\"));
+        assert_eq!(sequences[0].bounds, (1, 2, 3, 2));
+        assert_eq!(sequences[1].bounds, (0, 0, 2, 1));
+
+        let capture = SixelCapture::from_output_with_cell_size(output, &[(1, 2)], (10, 20));
+        assert_eq!(capture.sequences()[0].bounds, (1, 2, 2, 1));
+    }
+
+    #[cfg(feature = "sixel-image")]
+    #[test]
+    fn test_region_matches_reference_png() {
+        let mut screen = crate::ScreenState::new(80, 24);
+        screen.feed(b"\x1bP0;1q#1;2;100;50;0!4~-#1!2N\x1b\\");
+        let region = &screen.sixel_regions()[0];
+        assert_eq!(region.params.get(1), Some(&1));
+
+        let image = region.to_image().unwrap();
+        assert_eq!(image.dimensions(), (4, 10));
+        assert_eq!(image.get_pixel(3, 0).0, [255, 128, 0, 255]);
+        assert_eq!(image.get_pixel(3, 9).0, [0, 0, 0, 0]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let reference = dir.path().join("reference.png");
+        let mut expected = image.clone();
+        expected.save(&reference).unwrap();
+        region.assert_matches_png(&reference, 0).unwrap();
+
+        expected.get_pixel_mut(0, 0).0 = [250, 130, 5, 255];
+        expected.save(&reference).unwrap();
+        region.assert_matches_png(&reference, 5).unwrap();
+        let err = region.assert_matches_png(&reference, 4).unwrap_err();
+        assert!(err.to_string().contains("1 pixel(s) differ"));
+        assert!(err.to_string().contains("first at (0, 0)"));
+
+        let smaller = image::RgbaImage::new(2, 2);
+        smaller.save(&reference).unwrap();
+        let err = region.assert_matches_png(&reference, 255).unwrap_err();
+        assert!(err.to_string().contains("4x10"));
+    }
 }
@@ -603,6 +603,95 @@ impl TerminalProfile {
         }
     }
 
+    /// Returns the reply this terminal sends to a Primary Device Attributes
+    /// (DA1, `CSI c`) query.
+    ///
+    /// VT100 reports itself as a VT100 with the advanced video option; every
+    /// other profile reports a VT220-class terminal, adding Sixel graphics (4)
+    /// and ANSI color (22) when the profile supports them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ratatui_testlib::TerminalProfile;
+    ///
+    /// assert_eq!(TerminalProfile::VT100.primary_device_attributes(), "\x1b[?1;2c");
+    /// assert_eq!(TerminalProfile::WezTerm.primary_device_attributes(), "\x1b[?62;4;22c");
+    /// ```
+    pub fn primary_device_attributes(&self) -> String {
+        if *self == Self::VT100 {
+            return "\x1b[?1;2c".to_string();
+        }
+
+        let caps = self.capabilities();
+        let mut reply = String::from("\x1b[?62");
+        if caps.sixel_support {
+            reply.push_str(";4");
+        }
+        if caps.color_depth != ColorDepth::Monochrome {
+            reply.push_str(";22");
+        }
+        reply.push('c');
+        reply
+    }
+
+    /// Returns the reply this terminal sends to a Secondary Device Attributes
+    /// (DA2, `CSI > c`) query: terminal type, firmware version and ROM
+    /// cartridge number.
+    pub fn secondary_device_attributes(&self) -> &str {
+        match self {
+            Self::VT100 => "\x1b[>0;95;0c",
+            Self::Xterm256 | Self::XtermTrueColor | Self::Minimal | Self::Maximum => {
+                "\x1b[>41;390;0c"
+            }
+            Self::Screen => "\x1b[>83;40800;0c",
+            Self::Tmux => "\x1b[>84;0;0c",
+            Self::Konsole => "\x1b[>1;115;0c",
+            Self::GnomeTerminal => "\x1b[>65;7600;1c",
+            Self::Alacritty => "\x1b[>0;1;1c",
+            Self::Kitty => "\x1b[>1;4000;35c",
+            Self::WezTerm => "\x1b[>1;277;0c",
+            Self::ITerm2 => "\x1b[>0;95;0c",
+            Self::WindowsTerminal => "\x1b[>0;10;1c",
+            Self::VSCode => "\x1b[>0;276;0c",
+        }
+    }
+
+    /// Returns the name and version this terminal reports to an XTVERSION
+    /// (`CSI > q`) query, or `None` if it does not answer the query.
+    pub fn version_string(&self) -> Option<&str> {
+        match self {
+            Self::Xterm256 | Self::XtermTrueColor | Self::Maximum => Some("XTerm(390)"),
+            Self::Tmux => Some("tmux 3.4"),
+            Self::Konsole => Some("Konsole 24.02.1"),
+            Self::Kitty => Some("kitty(0.35.2)"),
+            Self::WezTerm => Some("WezTerm 20240203-110809-5046fc22"),
+            Self::ITerm2 => Some("iTerm2 3.5.0"),
+            Self::VSCode => Some("xterm.js(5.5.0)"),
+            Self::VT100
+            | Self::Screen
+            | Self::GnomeTerminal
+            | Self::Alacritty
+            | Self::WindowsTerminal
+            | Self::Minimal => None,
+        }
+    }
+
+    /// Returns whether this terminal reports its colors to OSC 10, 11 and 4
+    /// queries (`OSC 11 ; ? ST`).
+    pub fn answers_color_queries(&self) -> bool {
+        !matches!(self, Self::VT100 | Self::Screen | Self::Minimal)
+    }
+
+    /// Returns whether this terminal answers OSC 52 clipboard reads
+    /// (`OSC 52 ; c ; ? ST`) with the clipboard contents.
+    ///
+    /// Most terminals accept clipboard writes but ignore reads, or only allow
+    /// them after a setting is changed; this reflects the default setup.
+    pub fn allows_clipboard_read(&self) -> bool {
+        matches!(self, Self::Xterm256 | Self::XtermTrueColor | Self::Kitty | Self::Maximum)
+    }
+
     /// Returns all available terminal profiles.
     pub fn all() -> Vec<Self> {
         vec![
@@ -852,4 +941,25 @@ mod tests {
         assert!(maximum.supports(Feature::KittyGraphics));
         assert!(maximum.supports(Feature::ITerm2Images));
     }
+
+    #[test]
+    fn test_primary_device_attributes() {
+        assert_eq!(TerminalProfile::VT100.primary_device_attributes(), "\x1b[?1;2c");
+        assert_eq!(TerminalProfile::Xterm256.primary_device_attributes(), "\x1b[?62;22c");
+        assert_eq!(TerminalProfile::Maximum.primary_device_attributes(), "\x1b[?62;4;22c");
+
+        // Every profile answers DA1 with a well-formed reply
+        for profile in TerminalProfile::all() {
+            let reply = profile.primary_device_attributes();
+            assert!(reply.starts_with("\x1b[?") && reply.ends_with('c'), "{:?}", profile);
+            assert!(profile.secondary_device_attributes().starts_with("\x1b[>"));
+        }
+    }
+
+    #[test]
+    fn test_version_string() {
+        assert_eq!(TerminalProfile::Xterm256.version_string(), Some("XTerm(390)"));
+        assert!(TerminalProfile::WezTerm.version_string().unwrap().starts_with("WezTerm"));
+        assert_eq!(TerminalProfile::VT100.version_string(), None);
+    }
 }
@@ -413,3 +413,41 @@ fn test_golden_empty_content() -> Result<()> {
     cleanup_env();
     Ok(())
 }
+
+#[test]
+fn test_golden_records_and_compares_colors() -> Result<()> {
+    let mut state = ScreenState::new(20, 2);
+    state.feed(b"\x1b[31mRed\x1b[0m \x1b[38;2;255;128;0;48;5;236mTheme\x1b[0m");
+
+    let golden = GoldenFile::from_screen_state("test_colors", &state);
+    assert_eq!(
+        golden.colors.as_deref(),
+        Some("0:0..3 fg=1 bg=default\n0:4..9 fg=#ff8000 bg=236")
+    );
+
+    // Colors survive serialization
+    let parsed = GoldenFile::from_string(&golden.to_string())?;
+    assert_eq!(parsed.content, golden.content);
+    assert_eq!(parsed.colors, golden.colors);
+    parsed.compare(&state)?;
+
+    // Same text in a different color is a mismatch
+    let mut recolored = ScreenState::new(20, 2);
+    recolored.feed(b"\x1b[32mRed\x1b[0m \x1b[38;2;255;128;0;48;5;236mTheme\x1b[0m");
+    let err = parsed.compare(&recolored).unwrap_err().to_string();
+    assert!(err.contains("color mismatch"), "{}", err);
+    assert!(err.contains("fg=2"), "{}", err);
+
+    Ok(())
+}
+
+#[test]
+fn test_golden_without_colors_compares_text_only() -> Result<()> {
+    let legacy = "--- GOLDEN FILE ---\ntest: legacy\nsize: 5x1\ntimestamp: 0\n--- CONTENT ---\nHi   ";
+    let golden = GoldenFile::from_string(legacy)?;
+    assert_eq!(golden.colors, None);
+
+    let mut state = ScreenState::new(5, 1);
+    state.feed(b"\x1b[35mHi");
+    golden.compare(&state)
+}
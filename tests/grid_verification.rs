@@ -3,7 +3,7 @@
 //! These tests verify the API for exposing screen/grid state for verification,
 //! enabling comparison between different terminal emulator implementations.
 
-use ratatui_testlib::{Cell, ScreenState};
+use ratatui_testlib::{Cell, Color, ScreenState, UnderlineStyle};
 
 #[test]
 fn test_rows_cols_accessors() {
@@ -22,15 +22,15 @@ fn test_get_cell_basic() {
     // First character should be 'R' with red foreground
     let cell = screen.get_cell(0, 0).expect("Cell should exist");
     assert_eq!(cell.c, 'R');
-    assert_eq!(cell.fg, Some(1), "Red color");
-    assert_eq!(cell.bg, None, "No background");
+    assert_eq!(cell.fg, Color::Indexed(1), "Red color");
+    assert_eq!(cell.bg, Color::Default, "No background");
     assert!(!cell.bold);
     assert!(!cell.italic);
     assert!(!cell.underline);
 
     // After reset, should have default attributes
     let cell = screen.get_cell(0, 3).expect("Cell should exist");
-    assert_eq!(cell.fg, None, "Should be reset");
+    assert_eq!(cell.fg, Color::Default, "Should be reset");
 }
 
 #[test]
@@ -42,8 +42,8 @@ fn test_get_cell_with_all_attributes() {
 
     let cell = screen.get_cell(0, 0).expect("Cell should exist");
     assert_eq!(cell.c, 'S');
-    assert_eq!(cell.fg, Some(1), "Red foreground");
-    assert_eq!(cell.bg, Some(2), "Green background");
+    assert_eq!(cell.fg, Color::Indexed(1), "Red foreground");
+    assert_eq!(cell.bg, Color::Indexed(2), "Green background");
     assert!(cell.bold, "Should be bold");
     assert!(cell.italic, "Should be italic");
     assert!(cell.underline, "Should be underlined");
@@ -57,8 +57,8 @@ fn test_get_cell_256_colors() {
     screen.feed(b"\x1b[38;5;196m\x1b[48;5;21mColor");
 
     let cell = screen.get_cell(0, 0).expect("Cell should exist");
-    assert_eq!(cell.fg, Some(196), "256-color foreground");
-    assert_eq!(cell.bg, Some(21), "256-color background");
+    assert_eq!(cell.fg, Color::Indexed(196), "256-color foreground");
+    assert_eq!(cell.bg, Color::Indexed(21), "256-color background");
 }
 
 #[test]
@@ -104,15 +104,15 @@ fn test_iter_rows_with_attributes() {
 
     // First row - red
     assert_eq!(rows[0][0].c, 'R');
-    assert_eq!(rows[0][0].fg, Some(1));
+    assert_eq!(rows[0][0].fg, Color::Indexed(1));
 
     // Second row - green
     assert_eq!(rows[1][0].c, 'G');
-    assert_eq!(rows[1][0].fg, Some(2));
+    assert_eq!(rows[1][0].fg, Color::Indexed(2));
 
     // Third row - blue
     assert_eq!(rows[2][0].c, 'B');
-    assert_eq!(rows[2][0].fg, Some(4));
+    assert_eq!(rows[2][0].fg, Color::Indexed(4));
 }
 
 #[test]
@@ -150,11 +150,11 @@ fn test_iter_row_valid() {
 
     assert_eq!(cells.len(), 10, "Should have 10 cells");
     assert_eq!(cells[0].c, 'R');
-    assert_eq!(cells[0].fg, Some(1), "Red");
+    assert_eq!(cells[0].fg, Color::Indexed(1), "Red");
     assert_eq!(cells[3].c, 'G');
-    assert_eq!(cells[3].fg, Some(2), "Green");
+    assert_eq!(cells[3].fg, Color::Indexed(2), "Green");
     assert_eq!(cells[8].c, 'B');
-    assert_eq!(cells[8].fg, Some(4), "Blue");
+    assert_eq!(cells[8].fg, Color::Indexed(4), "Blue");
 }
 
 #[test]
@@ -176,8 +176,8 @@ fn test_iter_row_with_colors() {
 
     let cells: Vec<&Cell> = screen.iter_row(0).expect("Row exists").collect();
 
-    let colors: Vec<Option<u8>> = cells.iter().take(5).map(|c| c.fg).collect();
-    assert_eq!(colors, vec![Some(1), Some(2), Some(3), Some(4), Some(5)]);
+    let colors: Vec<Color> = cells.iter().take(5).map(|c| c.fg).collect();
+    assert_eq!(colors, (1..=5).map(Color::Indexed).collect::<Vec<_>>());
 }
 
 #[test]
@@ -203,11 +203,11 @@ fn test_snapshot_with_colors() {
 
     // Verify red text
     assert_eq!(snapshot.cells[0][0].c, 'R');
-    assert_eq!(snapshot.cells[0][0].fg, Some(1));
+    assert_eq!(snapshot.cells[0][0].fg, Color::Indexed(1));
 
     // Verify normal text (reset)
     assert_eq!(snapshot.cells[0][4].c, 'N');
-    assert_eq!(snapshot.cells[0][4].fg, None);
+    assert_eq!(snapshot.cells[0][4].fg, Color::Default);
 }
 
 #[test]
@@ -302,9 +302,9 @@ fn test_verification_oracle_use_case() {
 
     // Verify specific cells
     assert_eq!(snapshot.cells[0][0].c, 'R');
-    assert_eq!(snapshot.cells[0][0].fg, Some(1), "Red");
+    assert_eq!(snapshot.cells[0][0].fg, Color::Indexed(1), "Red");
     assert_eq!(snapshot.cells[0][4].c, 'G');
-    assert_eq!(snapshot.cells[0][4].fg, Some(2), "Green");
+    assert_eq!(snapshot.cells[0][4].fg, Color::Indexed(2), "Green");
 }
 
 #[test]
@@ -325,9 +325,9 @@ fn test_grid_iteration_pattern() {
     }
 
     assert_eq!(cells_inspected.len(), 3);
-    assert_eq!(cells_inspected[0], (0, 0, 'A', Some(1), None));
-    assert_eq!(cells_inspected[1], (0, 1, 'B', Some(2), None));
-    assert_eq!(cells_inspected[2], (0, 2, 'C', Some(3), None));
+    assert_eq!(cells_inspected[0], (0, 0, 'A', Color::Indexed(1), Color::Default));
+    assert_eq!(cells_inspected[1], (0, 1, 'B', Color::Indexed(2), Color::Default));
+    assert_eq!(cells_inspected[2], (0, 2, 'C', Color::Indexed(3), Color::Default));
 }
 
 #[test]
@@ -345,7 +345,7 @@ fn test_snapshot_export_pattern() {
 
     // Verify cells are accessible
     assert_eq!(snapshot.cells[0][0].c, 'T');
-    assert_eq!(snapshot.cells[0][0].fg, Some(1));
+    assert_eq!(snapshot.cells[0][0].fg, Color::Indexed(1));
 }
 
 #[test]
@@ -353,19 +353,28 @@ fn test_cell_public_fields() {
     // Verify that Cell fields are public and accessible
     let cell = Cell {
         c: 'A',
-        fg: Some(1),
-        bg: Some(2),
+        combining: String::new(),
+        width: 1,
+        fg: Color::Indexed(1),
+        bg: Color::Indexed(2),
         bold: true,
         italic: true,
         underline: true,
+        underline_style: UnderlineStyle::Single,
+        underline_color: Color::Indexed(3),
+        ..Cell::default()
     };
 
     assert_eq!(cell.c, 'A');
-    assert_eq!(cell.fg, Some(1));
-    assert_eq!(cell.bg, Some(2));
+    assert_eq!(cell.width, 1);
+    assert_eq!(cell.fg, Color::Indexed(1));
+    assert_eq!(cell.bg, Color::Indexed(2));
     assert!(cell.bold);
     assert!(cell.italic);
     assert!(cell.underline);
+    assert_eq!(cell.underline_style, UnderlineStyle::Single);
+    assert_eq!(cell.underline_color, Color::Indexed(3));
+    assert!(!cell.dim && !cell.reverse && !cell.hidden && !cell.strikethrough);
 }
 
 #[test]
@@ -379,7 +388,7 @@ fn test_multiline_grid_inspection() {
 
     // Verify we can inspect all rows
     assert!(snapshot.cells[0][0].c == 'L');
-    assert!(snapshot.cells[1][0].fg == Some(1)); // Red
+    assert!(snapshot.cells[1][0].fg == Color::Indexed(1)); // Red
     assert!(snapshot.cells[2][0].bold); // Bold
 }
 
@@ -3,7 +3,7 @@
 //! This test verifies the exact use case described in the issue:
 //! comparing the final state of another terminal emulator against ratatui-testlib.
 
-use ratatui_testlib::ScreenState;
+use ratatui_testlib::{Color, ScreenState};
 
 /// Mock terminal emulator for testing comparison.
 /// In real usage, this would be Scarab or another terminal emulator.
@@ -40,8 +40,8 @@ impl MockTerminalEmulator {
 #[derive(Debug, PartialEq)]
 struct MockCell {
     char: char,
-    fg: Option<u8>,
-    bg: Option<u8>,
+    fg: Color,
+    bg: Color,
 }
 
 #[test]
@@ -109,9 +109,9 @@ fn test_issue_8_snapshot_export() {
 
     // Verify we can access cell data
     assert_eq!(snapshot.cells[0][0].c, 'R');
-    assert_eq!(snapshot.cells[0][0].fg, Some(1)); // Red
+    assert_eq!(snapshot.cells[0][0].fg, Color::Indexed(1)); // Red
     assert_eq!(snapshot.cells[0][3].c, 'G');
-    assert_eq!(snapshot.cells[0][3].fg, Some(2)); // Green
+    assert_eq!(snapshot.cells[0][3].fg, Color::Indexed(2)); // Green
 }
 
 #[test]
@@ -143,8 +143,8 @@ fn test_issue_8_cell_field_access() {
 
     // Verify they have correct values
     assert_eq!(cell.c, 'T');
-    assert_eq!(cell.fg, Some(1)); // Red
-    assert_eq!(cell.bg, Some(2)); // Green
+    assert_eq!(cell.fg, Color::Indexed(1)); // Red
+    assert_eq!(cell.bg, Color::Indexed(2)); // Green
     assert!(cell.bold);
     assert!(cell.italic);
     assert!(cell.underline);
@@ -196,9 +196,9 @@ fn test_issue_8_complete_workflow() {
 
     // Verify specific cells have expected values
     assert_eq!(oracle_snapshot.cells[0][0].c, 'H');
-    assert_eq!(oracle_snapshot.cells[0][0].fg, Some(1)); // Red
+    assert_eq!(oracle_snapshot.cells[0][0].fg, Color::Indexed(1)); // Red
     assert!(oracle_snapshot.cells[0][0].bold);
 
     // After the text, cells should be reset
-    assert_eq!(oracle_snapshot.cells[0][13].fg, None); // After "Hello, World!"
+    assert_eq!(oracle_snapshot.cells[0][13].fg, Color::Default); // After "Hello, World!"
 }
@@ -0,0 +1,151 @@
+//! Integration tests for automatic replies to terminal queries.
+//!
+//! Applications that ask the terminal for its cursor position or device
+//! attributes at startup block until a reply arrives; the harness answers
+//! these queries so such applications can be tested.
+
+use std::time::Duration;
+
+use portable_pty::CommandBuilder;
+use ratatui_testlib::{Palette, Result, TerminalProfile, TerminalQuery, TuiTestHarness};
+
+/// Sends `query`, reads the reply up to `terminator` and prints it with the
+/// ESC bytes made visible.
+fn query_command(query: &str, terminator: char) -> CommandBuilder {
+    let script = format!(
+        "stty -icanon -echo; printf '{}'; \
+         if IFS= read -r -s -t 2 -d '{}' reply; then \
+         printf 'reply:%s\\n' \"${{reply#?}}\"; else echo timeout; fi",
+        query, terminator
+    );
+    let mut cmd = CommandBuilder::new("bash");
+    cmd.args(["-c", &script]);
+    cmd
+}
+
+#[test]
+fn test_cursor_position_report_is_answered() -> Result<()> {
+    let mut harness = TuiTestHarness::new(80, 24)?.with_timeout(Duration::from_secs(5));
+    harness.spawn(query_command("\\033[3;7H\\033[6n", 'R'))?;
+
+    harness.wait_for_text("reply:")?;
+    assert!(harness.screen_contents().contains("reply:[3;7"));
+    Ok(())
+}
+
+#[test]
+fn test_primary_device_attributes_follow_profile() -> Result<()> {
+    let mut harness = TuiTestHarness::new(80, 24)?
+        .with_terminal_profile(TerminalProfile::WezTerm)
+        .with_timeout(Duration::from_secs(5));
+    harness.spawn(query_command("\\033[c", 'c'))?;
+
+    harness.wait_for_text("reply:")?;
+    assert!(harness.screen_contents().contains("reply:[?62;4;22"));
+    Ok(())
+}
+
+#[test]
+fn test_query_replies_can_be_disabled() -> Result<()> {
+    let mut harness = TuiTestHarness::new(80, 24)?
+        .with_query_replies(false)
+        .with_timeout(Duration::from_secs(5));
+    harness.spawn(query_command("\\033[6n", 'R'))?;
+
+    // The script gives up waiting and exits
+    harness.wait_exit()?;
+    let _ = harness.update_state();
+    assert!(harness.screen_contents().contains("timeout"));
+    Ok(())
+}
+
+#[test]
+fn test_query_handler_overrides_profile() -> Result<()> {
+    let mut harness = TuiTestHarness::builder()
+        .with_timeout(Duration::from_secs(5))
+        .with_query_handler(|query| match query {
+            TerminalQuery::CursorPosition { .. } => Some(b"\x1b[42;42R".to_vec()),
+            _ => None,
+        })
+        .build()?;
+    harness.spawn(query_command("\\033[6n", 'R'))?;
+
+    harness.wait_for_text("reply:")?;
+    assert!(harness.screen_contents().contains("reply:[42;42"));
+    Ok(())
+}
+
+#[test]
+fn test_cell_size_report_uses_configured_cell_size() -> Result<()> {
+    let mut harness = TuiTestHarness::builder()
+        .with_cell_size(10, 20)
+        .with_timeout(Duration::from_secs(5))
+        .build()?;
+    harness.spawn(query_command("\\033[16t", 't'))?;
+
+    harness.wait_for_text("reply:")?;
+    assert!(harness.screen_contents().contains("reply:[6;20;10"));
+    Ok(())
+}
+
+#[test]
+fn test_kitty_graphics_probe_follows_profile() -> Result<()> {
+    let probe = "\\033_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\\033\\\\";
+
+    let mut kitty = TuiTestHarness::new(80, 24)?
+        .with_terminal_profile(TerminalProfile::Kitty)
+        .with_timeout(Duration::from_secs(5));
+    kitty.spawn(query_command(probe, '\\'))?;
+    kitty.wait_for_text("reply:")?;
+    assert!(kitty.screen_contents().contains("reply:_Gi=31;OK"));
+    assert!(kitty.state().kitty_images().is_empty(), "queries store no image");
+
+    let mut xterm = TuiTestHarness::new(80, 24)?
+        .with_terminal_profile(TerminalProfile::Xterm256)
+        .with_timeout(Duration::from_secs(5));
+    xterm.spawn(query_command(probe, '\\'))?;
+    xterm.wait_exit()?;
+    let _ = xterm.update_state();
+    assert!(xterm.screen_contents().contains("timeout"));
+    Ok(())
+}
+
+#[test]
+fn test_clipboard_write_is_recorded() -> Result<()> {
+    let mut harness = TuiTestHarness::new(80, 24)?.with_timeout(Duration::from_secs(5));
+    let mut cmd = CommandBuilder::new("bash");
+    cmd.args(["-c", "printf '\\033]52;c;%s\\007' \"$(printf yank | base64)\"; echo copied"]);
+    harness.spawn(cmd)?;
+
+    harness.wait_for_text("copied")?;
+    assert_eq!(harness.clipboard_contents(), Some("yank"));
+    assert_eq!(harness.clipboard_writes()[0].selection, "c");
+    Ok(())
+}
+
+#[test]
+fn test_clipboard_read_is_answered() -> Result<()> {
+    let mut harness = TuiTestHarness::new(80, 24)?
+        .with_terminal_profile(TerminalProfile::Xterm256)
+        .with_timeout(Duration::from_secs(5));
+    harness.set_clipboard("pasted");
+    harness.spawn(query_command("\\033]52;c;?\\007", '\\'))?;
+
+    harness.wait_for_text("reply:")?;
+    // base64 of "pasted"
+    assert!(harness.screen_contents().contains("reply:]52;c;cGFzdGVk"));
+    Ok(())
+}
+
+#[test]
+fn test_background_color_query_follows_palette() -> Result<()> {
+    let mut harness = TuiTestHarness::builder()
+        .with_palette(Palette::light())
+        .with_timeout(Duration::from_secs(5))
+        .build()?;
+    harness.spawn(query_command("\\033]11;?\\007", '\\'))?;
+
+    harness.wait_for_text("reply:")?;
+    assert!(harness.screen_contents().contains("reply:]11;rgb:ffff/ffff/ffff"));
+    Ok(())
+}
@@ -0,0 +1,61 @@
+//! Integration tests for running commands through `ShellSession`.
+
+use ratatui_testlib::{
+    shell::{Shell, ShellSession},
+    Color,
+};
+
+#[test]
+fn test_output_longer_than_the_screen() {
+    let mut session = ShellSession::builder(Shell::Bash)
+        .with_size(40, 10)
+        .build()
+        .unwrap();
+
+    let output = session.run("seq 1 50").unwrap();
+    let lines: Vec<_> = output.text.lines().collect();
+    assert_eq!(lines.len(), 50);
+    assert_eq!(lines[0], "1");
+    assert_eq!(lines[49], "50");
+    assert!(output.success());
+}
+
+#[test]
+fn test_wrapped_command_line() {
+    let mut session = ShellSession::builder(Shell::Bash)
+        .with_size(20, 10)
+        .build()
+        .unwrap();
+
+    // The command line wraps; its output starts on the row after it
+    let output = session.run("echo abcdefghijklmnopqrstuvwxyz").unwrap();
+    assert_eq!(output.text, "abcdefghijklmnopqrstuvwxyz");
+}
+
+#[test]
+fn test_screen_state_available_for_assertions() {
+    let mut session = ShellSession::builder(Shell::Bash)
+        .with_env("GREETING", "hi")
+        .build()
+        .unwrap();
+
+    let output = session.run("printf '\\033[31m%s\\033[0m\\n' \"$GREETING\"").unwrap();
+    assert_eq!(output.text, "hi");
+
+    let state = session.state();
+    let (row, _) = state.cursor_position();
+    let cell = state.get_cell(row - 1, 0).unwrap();
+    assert_eq!(cell.c, 'h');
+    assert_eq!(cell.fg, Color::Indexed(1));
+}
+
+#[test]
+fn test_exit_codes_follow_each_command() {
+    let mut session = ShellSession::new(Shell::Sh).unwrap();
+
+    let codes: Vec<_> = ["true", "false", "exit_status_42() { return 42; }; exit_status_42"]
+        .iter()
+        .map(|command| session.run(command).unwrap().exit_code)
+        .collect();
+    assert_eq!(codes, vec![Some(0), Some(1), Some(42)]);
+}
@@ -3,7 +3,10 @@
 //! These tests demonstrate using ratatui-testlib as a verification oracle
 //! for terminal emulators by feeding raw byte streams directly without PTY overhead.
 
-use ratatui_testlib::ScreenState;
+use ratatui_testlib::{
+    navigation::PromptMarkerType, Color, CursorStyle, MouseTracking, Notification, ScreenState,
+    UnderlineStyle,
+};
 
 #[test]
 fn test_basic_ansi_color_sequences() {
@@ -19,11 +22,11 @@ fn test_basic_ansi_color_sequences() {
     // Verify color attribute (red = color 1 in ANSI)
     let cell = screen.get_cell(0, 0).expect("Cell should exist");
     assert_eq!(cell.c, 'H');
-    assert_eq!(cell.fg, Some(1), "Foreground should be red (color 1)");
+    assert_eq!(cell.fg, Color::Indexed(1), "Foreground should be red (color 1)");
 
     // Verify reset worked (cell after "Hello" should have default color)
     let cell = screen.get_cell(0, 5).expect("Cell should exist");
-    assert_eq!(cell.fg, None, "Foreground should be reset to default");
+    assert_eq!(cell.fg, Color::Default, "Foreground should be reset to default");
 }
 
 #[test]
@@ -41,10 +44,10 @@ fn test_cursor_positioning() {
     assert_eq!(col, 23, "Col should be 23 (0-based from 20, plus 4 for 'Test')");
 
     // Verify text at correct position
-    assert_eq!(screen.text_at(9, 19), Some('T'));
-    assert_eq!(screen.text_at(9, 20), Some('e'));
-    assert_eq!(screen.text_at(9, 21), Some('s'));
-    assert_eq!(screen.text_at(9, 22), Some('t'));
+    assert_eq!(screen.text_at(9, 19).as_deref(), Some("T"));
+    assert_eq!(screen.text_at(9, 20).as_deref(), Some("e"));
+    assert_eq!(screen.text_at(9, 21).as_deref(), Some("s"));
+    assert_eq!(screen.text_at(9, 22).as_deref(), Some("t"));
 }
 
 #[test]
@@ -83,7 +86,7 @@ fn test_partial_escape_sequence_handling() {
     // Verify red text was rendered
     assert!(screen.contains("Red"));
     let cell = screen.get_cell(0, 0).unwrap();
-    assert_eq!(cell.fg, Some(1), "Should be red");
+    assert_eq!(cell.fg, Color::Indexed(1), "Should be red");
 }
 
 #[test]
@@ -96,7 +99,7 @@ fn test_complex_sgr_sequence() {
 
     let cell = screen.get_cell(0, 0).unwrap();
     assert_eq!(cell.c, 'S');
-    assert_eq!(cell.fg, Some(1), "Should be red");
+    assert_eq!(cell.fg, Color::Indexed(1), "Should be red");
     assert!(cell.bold, "Should be bold");
     assert!(cell.italic, "Should be italic");
     assert!(cell.underline, "Should be underlined");
@@ -119,8 +122,24 @@ fn test_256_color_mode() {
 
     let cell = screen.get_cell(0, 0).unwrap();
     assert_eq!(cell.c, 'C');
-    assert_eq!(cell.fg, Some(196), "Foreground should be color 196");
-    assert_eq!(cell.bg, Some(21), "Background should be color 21");
+    assert_eq!(cell.fg, Color::Indexed(196), "Foreground should be color 196");
+    assert_eq!(cell.bg, Color::Indexed(21), "Background should be color 21");
+}
+
+#[test]
+fn test_truecolor_mode() {
+    let mut screen = ScreenState::new(80, 24);
+
+    // ESC [ 38 ; 2 ; R ; G ; B m = 24-bit foreground
+    // ESC [ 48 : 2 : : R : G : B m = 24-bit background, colon form
+    let input = b"\x1b[38;2;235;219;178m\x1b[48:2::40:40:40mTheme\x1b[0m";
+    screen.feed(input);
+
+    let cell = screen.get_cell(0, 0).unwrap();
+    assert_eq!(cell.c, 'T');
+    assert_eq!(cell.fg, Color::Rgb(235, 219, 178));
+    assert_eq!(cell.bg, Color::Rgb(40, 40, 40));
+    assert_eq!(screen.snapshot().cells[0][4].fg, Color::Rgb(235, 219, 178));
 }
 
 #[test]
@@ -131,7 +150,7 @@ fn test_newline_and_carriage_return() {
     screen.feed(b"Line 1\r\nLine 2");
 
     // Verify both lines
-    assert_eq!(screen.text_at(0, 0), Some('L'));
+    assert_eq!(screen.text_at(0, 0).as_deref(), Some("L"));
     assert_eq!(screen.row_contents(0).trim(), "Line 1");
     assert_eq!(screen.row_contents(1).trim(), "Line 2");
 
@@ -149,10 +168,10 @@ fn test_tab_character() {
     screen.feed(b"AB\tCD");
 
     // 'A' at 0, 'B' at 1, tab advances to 8, 'C' at 8, 'D' at 9
-    assert_eq!(screen.text_at(0, 0), Some('A'));
-    assert_eq!(screen.text_at(0, 1), Some('B'));
-    assert_eq!(screen.text_at(0, 8), Some('C'));
-    assert_eq!(screen.text_at(0, 9), Some('D'));
+    assert_eq!(screen.text_at(0, 0).as_deref(), Some("A"));
+    assert_eq!(screen.text_at(0, 1).as_deref(), Some("B"));
+    assert_eq!(screen.text_at(0, 8).as_deref(), Some("C"));
+    assert_eq!(screen.text_at(0, 9).as_deref(), Some("D"));
 }
 
 #[test]
@@ -164,10 +183,10 @@ fn test_cursor_movement_sequences() {
     screen.feed(b"\x1b[10;10HSecond"); // Row 10, col 10
 
     // Verify both texts at correct positions
-    assert_eq!(screen.text_at(4, 4), Some('F'));
+    assert_eq!(screen.text_at(4, 4).as_deref(), Some("F"));
     assert!(screen.row_contents(4).contains("First"));
 
-    assert_eq!(screen.text_at(9, 9), Some('S'));
+    assert_eq!(screen.text_at(9, 9).as_deref(), Some("S"));
     assert!(screen.row_contents(9).contains("Second"));
 }
 
@@ -212,15 +231,15 @@ fn test_deterministic_byte_sequence() {
     screen.feed(sequence);
 
     // Verify the oracle state
-    assert_eq!(screen.text_at(0, 0), Some('R'));
-    assert_eq!(screen.text_at(0, 1), Some('e'));
-    assert_eq!(screen.text_at(0, 2), Some('d'));
-    assert_eq!(screen.text_at(0, 4), Some('G'));
+    assert_eq!(screen.text_at(0, 0).as_deref(), Some("R"));
+    assert_eq!(screen.text_at(0, 1).as_deref(), Some("e"));
+    assert_eq!(screen.text_at(0, 2).as_deref(), Some("d"));
+    assert_eq!(screen.text_at(0, 4).as_deref(), Some("G"));
 
     // Verify colors
-    assert_eq!(screen.get_cell(0, 0).unwrap().fg, Some(1)); // Red
-    assert_eq!(screen.get_cell(0, 4).unwrap().fg, Some(2)); // Green
-    assert_eq!(screen.get_cell(0, 10).unwrap().fg, Some(4)); // Blue
+    assert_eq!(screen.get_cell(0, 0).unwrap().fg, Color::Indexed(1)); // Red
+    assert_eq!(screen.get_cell(0, 4).unwrap().fg, Color::Indexed(2)); // Green
+    assert_eq!(screen.get_cell(0, 10).unwrap().fg, Color::Indexed(4)); // Blue
 }
 
 #[test]
@@ -254,7 +273,7 @@ fn test_zero_cost_without_pty() {
     // Verify we can query the state
     assert!(screen.contains("Test Output"));
     assert_eq!(screen.size(), (80, 24));
-    assert_eq!(screen.get_cell(0, 0).unwrap().fg, Some(2)); // Green
+    assert_eq!(screen.get_cell(0, 0).unwrap().fg, Color::Indexed(2)); // Green
 }
 
 #[test]
@@ -302,7 +321,7 @@ fn test_stream_based_comparison() {
     // - Sixel regions: oracle.sixel_regions()
 
     assert_eq!(oracle.contents().lines().next().unwrap().trim(), "Hello");
-    assert_eq!(oracle.get_cell(0, 0).unwrap().fg, Some(1));
+    assert_eq!(oracle.get_cell(0, 0).unwrap().fg, Color::Indexed(1));
 }
 
 #[test]
@@ -368,3 +387,255 @@ fn test_debug_contents() {
     // debug_contents should be equivalent to contents (for now)
     assert_eq!(contents, screen.contents());
 }
+
+#[test]
+fn test_redraw_after_clear_leaves_no_stale_text() {
+    let mut screen = ScreenState::new(40, 5);
+
+    // First frame
+    screen.feed(b"\x1b[1;1HLoading project...\x1b[2;1HStatus: busy");
+
+    // Typical ratatui redraw: clear the screen, then shorten one line in place
+    screen.feed(b"\x1b[2J\x1b[1;1HReady");
+    screen.feed(b"\x1b[2;1HStatus: busy\x1b[2;9Hok\x1b[K");
+
+    assert_eq!(screen.row_contents(0).trim_end(), "Ready");
+    assert_eq!(screen.row_contents(1).trim_end(), "Status: ok");
+    assert!(!screen.contains("Loading"), "Erased text should not remain");
+}
+
+#[test]
+fn test_log_output_scrolls_into_scrollback() {
+    let mut screen = ScreenState::new(20, 5);
+
+    for i in 1..=30 {
+        screen.feed(format!("log line {}\r\n", i).as_bytes());
+    }
+
+    // The last four lines plus the empty cursor line stay visible
+    assert_eq!(screen.row_contents(0).trim_end(), "log line 27");
+    assert_eq!(screen.row_contents(3).trim_end(), "log line 30");
+    assert_eq!(screen.cursor_position(), (4, 0));
+
+    // Everything else is available from the scrollback
+    let scrollback = screen.scrollback();
+    assert_eq!(scrollback.len(), 26);
+    assert_eq!(scrollback[0].trim_end(), "log line 1");
+    assert_eq!(scrollback[25].trim_end(), "log line 26");
+    assert!(screen.contents_with_scrollback().starts_with("log line 1 "));
+}
+
+#[test]
+fn test_status_bar_stays_fixed_with_scroll_region() {
+    let mut screen = ScreenState::new(20, 4);
+
+    // Pin a status bar to the last row and scroll output above it
+    screen.feed(b"\x1b[4;1H[status]\x1b[1;3r\x1b[3;1H");
+    for i in 1..=5 {
+        screen.feed(format!("\r\nout {}", i).as_bytes());
+    }
+
+    assert_eq!(screen.row_contents(0).trim_end(), "out 3");
+    assert_eq!(screen.row_contents(2).trim_end(), "out 5");
+    assert_eq!(screen.row_contents(3).trim_end(), "[status]");
+}
+
+#[test]
+fn test_tui_app_lifecycle_restores_shell_output() {
+    let mut screen = ScreenState::new(30, 6);
+    screen.feed(b"$ cargo run\r\n");
+
+    // Typical ratatui startup: enter the alternate screen, hide the cursor, draw
+    screen.feed(b"\x1b[?1049h\x1b[?25l\x1b[H\x1b[2J");
+    screen.feed(b"\x1b[1;1H\xe2\x94\x8c Dashboard \xe2\x94\x90");
+    assert!(screen.is_alternate_screen());
+    assert!(screen.contains("Dashboard"));
+    assert!(!screen.contains("$ cargo run"));
+    assert!(screen.primary_contents().contains("$ cargo run"));
+
+    // Shutdown: show the cursor and leave the alternate screen
+    screen.feed(b"\x1b[?25h\x1b[?1049l");
+    assert!(!screen.is_alternate_screen());
+    assert!(screen.contains("$ cargo run"));
+    assert!(!screen.contains("Dashboard"));
+    assert_eq!(screen.cursor_position(), (1, 0));
+}
+
+#[test]
+fn test_long_cli_line_wraps_and_rejoins() {
+    let mut screen = ScreenState::new(20, 5);
+    let path = "/home/user/projects/ratatui-testlib/src/screen.rs";
+    screen.feed(format!("$ ls {}\r\nok\r\n", path).as_bytes());
+
+    assert_eq!(screen.row_contents(0), "$ ls /home/user/proj");
+    assert_eq!(screen.row_contents(3).trim_end(), "ok");
+    assert!(!screen.contains(path), "The path is split across rows");
+
+    let lines = screen.logical_lines();
+    assert_eq!(lines[0], format!("$ ls {}", path));
+    assert_eq!(lines[1], "ok");
+}
+
+#[test]
+fn test_localized_ui_keeps_columns_aligned() {
+    let mut screen = ScreenState::new(20, 2);
+
+    // A ratatui table cell holding CJK text followed by a column separator
+    screen.feed("\x1b[1;1H名前│値\x1b[2;1Hname│val".as_bytes());
+
+    // The separator lines up because each CJK character takes two columns
+    assert_eq!(screen.text_at(0, 4).as_deref(), Some("│"));
+    assert_eq!(screen.text_at(1, 4).as_deref(), Some("│"));
+    assert_eq!(screen.row_contents(0).trim_end(), "名前│値");
+    assert_eq!(screen.cursor_position(), (1, 8));
+}
+
+#[test]
+fn test_reverse_video_selection_and_spell_check_underline() {
+    let mut screen = ScreenState::new(30, 3);
+
+    // A list whose selected row is drawn in reverse video, followed by an editor
+    // line with a red curly underline under a misspelled word
+    screen.feed(b"  one\r\n\x1b[7m> two\x1b[27m\r\n");
+    screen.feed(b"see \x1b[4:3;58:2::255:0:0mteh\x1b[24;59m cat");
+
+    assert!(!screen.get_cell(0, 2).unwrap().reverse);
+    assert!(screen.iter_row(1).unwrap().take(5).all(|cell| cell.reverse));
+    assert!(!screen.get_cell(1, 5).unwrap().reverse);
+
+    let misspelled = screen.get_cell(2, 4).unwrap();
+    assert_eq!(misspelled.c, 't');
+    assert_eq!(misspelled.underline_style, UnderlineStyle::Curly);
+    assert_eq!(misspelled.underline_color, Color::Rgb(255, 0, 0));
+    assert_eq!(screen.get_cell(2, 8).unwrap().underline_style, UnderlineStyle::None);
+}
+
+#[test]
+fn test_editor_cursor_shape_and_modal_visibility() {
+    let mut screen = ScreenState::new(40, 10);
+
+    // Entering insert mode switches to a blinking bar
+    screen.feed(b"\x1b[5 q");
+    assert_eq!(screen.cursor_style(), CursorStyle::BlinkingBar);
+
+    // A modal dialog saves the cursor, hides it and draws elsewhere
+    screen.feed(b"\x1b[3;7H\x1b7\x1b[?25l\x1b[5;10H\x1b[7m Save? \x1b[0m");
+    assert!(!screen.cursor_visible());
+
+    // Closing the dialog restores the cursor where the user was typing
+    screen.feed(b"\x1b8\x1b[?25h\x1b[2 q");
+    assert!(screen.cursor_visible());
+    assert_eq!(screen.cursor_position(), (2, 6));
+    assert_eq!(screen.cursor_style(), CursorStyle::SteadyBlock);
+}
+
+#[test]
+fn test_mouse_capture_enabled_on_startup_and_disabled_on_quit() {
+    let mut screen = ScreenState::new(80, 24);
+
+    // crossterm's EnterAlternateScreen + EnableMouseCapture + EnableBracketedPaste
+    screen.feed(b"\x1b[?1049h\x1b[?1000h\x1b[?1002h\x1b[?1003h\x1b[?1015h\x1b[?1006h");
+    screen.feed(b"\x1b[?2004h");
+    assert_eq!(screen.modes().mouse_tracking, MouseTracking::AnyEvent);
+    assert!(screen.modes().bracketed_paste);
+
+    // ... and the matching teardown on quit
+    screen.feed(b"\x1b[?1006l\x1b[?1015l\x1b[?1003l\x1b[?1002l\x1b[?1000l");
+    screen.feed(b"\x1b[?2004l\x1b[?1049l");
+    assert!(!screen.modes().mouse_enabled());
+    assert!(!screen.modes().bracketed_paste);
+    assert!(!screen.is_alternate_screen());
+}
+
+#[test]
+fn test_file_manager_title_and_job_notification() {
+    let mut screen = ScreenState::new(80, 24);
+
+    // Navigating into a directory updates the title and reports the directory
+    screen.feed(b"\x1b]2;Files - /home/user/photos\x07\x1b]7;file://host/home/user/photos\x07");
+    assert_eq!(screen.title(), "Files - /home/user/photos");
+    assert_eq!(screen.cwd(), Some("/home/user/photos"));
+
+    // A finished copy job raises a desktop notification
+    screen.feed(b"\x1b]777;notify;Copy complete;3 files copied\x1b\\");
+    assert_eq!(
+        screen.notifications().last(),
+        Some(&Notification::Desktop {
+            title: Some("Copy complete".to_string()),
+            body: "3 files copied".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_hyperlinked_table_cells() {
+    let mut screen = ScreenState::new(40, 3);
+
+    // A table whose "Issue" column links to the tracker
+    screen.feed(b"#1  \x1b]8;id=1;https://github.com/o/r/issues/1\x1b\\open\x1b]8;;\x1b\\\r\n");
+    screen.feed(b"#2  \x1b]8;id=2;https://github.com/o/r/issues/2\x1b\\closed\x1b]8;;\x1b\\");
+
+    let links = screen.hyperlinks();
+    assert_eq!(links.len(), 2);
+    assert_eq!(links[0].text, "open");
+    assert_eq!(links[1].uri, "https://github.com/o/r/issues/2");
+    assert_eq!(links[1].id.as_deref(), Some("2"));
+    assert_eq!(screen.link_at(1, 6).unwrap().uri, links[1].uri);
+    assert!(screen.link_at(1, 0).is_none());
+}
+
+#[test]
+fn test_table_aligned_with_custom_tab_stops() {
+    let mut screen = ScreenState::new(40, 3);
+
+    // A CLI tool clears the default stops and sets its own at columns 6 and 16
+    screen.feed(b"\x1b[3g\x1b[1;7H\x1bH\x1b[1;17H\x1bH\r");
+    screen.feed(b"PID\tNAME\tSTATE\r\n1\tinit\tS\r\n4213\tcargo\tR");
+
+    assert_eq!(screen.tab_stops(), vec![6, 16]);
+    assert_eq!(screen.text_at(1, 6).as_deref(), Some("i"));
+    assert_eq!(screen.text_at(2, 16).as_deref(), Some("R"));
+    assert!(screen.contents().contains("4213  cargo     R"));
+}
+
+#[test]
+fn test_ncurses_line_drawing_box() {
+    let mut screen = ScreenState::new(8, 3);
+
+    // ncurses designates DEC Special Graphics into G1 and shifts in and out
+    screen.feed(b"\x1b)0\x0elqqqqqqk\x0f\r\n");
+    screen.feed(b"\x0ex\x0f menu \x0ex\x0f\r\n");
+    screen.feed(b"\x0emqqqqqqj\x0f");
+
+    assert_eq!(screen.row_contents(0), "┌──────┐");
+    assert_eq!(screen.row_contents(1), "│ menu │");
+    assert_eq!(screen.row_contents(2), "└──────┘");
+}
+
+#[test]
+fn test_shell_integration_prompt_markers() {
+    let mut screen = ScreenState::new(30, 3);
+
+    // Two commands from a shell with OSC 133 integration enabled
+    for (command, output, code) in [("make", "ok", "0"), ("make test", "1 failed", "2")] {
+        screen.feed(b"\x1b]133;A\x1b\\~/src $ \x1b]133;B\x1b\\");
+        screen.feed(format!("{command}\r\n\x1b]133;C\x1b\\{output}\r\n").as_bytes());
+        screen.feed(format!("\x1b]133;D;{code}\x1b\\").as_bytes());
+    }
+    screen.feed(b"\x1b]133;A\x1b\\~/src $ ");
+
+    let markers = screen.prompt_markers();
+    let prompts: Vec<_> = markers
+        .iter()
+        .filter(|m| m.marker_type == PromptMarkerType::PromptStart)
+        .map(|m| m.line)
+        .collect();
+    assert_eq!(prompts, vec![0, 2, 4]);
+
+    let commands: Vec<_> = markers.iter().filter_map(|m| m.command.as_deref()).collect();
+    assert_eq!(commands, vec!["make", "make test"]);
+
+    let codes: Vec<_> = markers.iter().filter_map(|m| m.exit_code).collect();
+    assert_eq!(codes, vec![0, 2]);
+    assert!(screen.contents_with_scrollback().lines().nth(2).unwrap().contains("make test"));
+}